@@ -0,0 +1,259 @@
+use std::{
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+use clap::{Parser, Subcommand, ValueEnum};
+use remindr_core::{
+    Utils,
+    domain::database::document::DocumentModel,
+    infrastructure::{
+        config::Remindr, markdown::MarkdownConverter,
+        repositories::any_document_repository::AnyDocumentRepository,
+    },
+};
+use serde_json::{Value, json};
+
+/// Read and write the Remindr knowledge base from the command line
+#[derive(Parser)]
+#[command(name = "remindr", version)]
+struct Cli {
+    /// Name of the database context to use, the default local database otherwise
+    #[arg(long, short, global = true)]
+    context: Option<String>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the documents, nested pages being indented under their parent
+    List {
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print a document
+    Show {
+        id: i32,
+        #[arg(long, short, value_enum, default_value_t = Format::Markdown)]
+        format: Format,
+    },
+    /// Create an empty document and print its id
+    Create {
+        title: String,
+        #[arg(long)]
+        parent: Option<i32>,
+    },
+    /// Append each line read from stdin as a text block
+    Append { id: i32 },
+    /// Change the title of a document
+    Rename { id: i32, title: String },
+    /// Move a document and its pages to the trash
+    Delete { id: i32 },
+    /// Write a document as Markdown, `-` for stdout, its attachments going to an
+    /// `attachments` directory next to the file
+    Export { id: i32, path: PathBuf },
+    /// Create a document from a Markdown file, `-` for stdin, and print its id
+    Import {
+        path: PathBuf,
+        #[arg(long)]
+        parent: Option<i32>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    let cli = Cli::parse();
+    let repository = connect(cli.context.as_deref()).await?;
+
+    match cli.command {
+        Command::List { json } => {
+            let documents = repository.get_documents().await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&summaries(&documents))?);
+            } else {
+                print_tree(&documents, None, 0);
+            }
+        }
+        Command::Show { id, format } => {
+            let document = get_document(&repository, id).await?;
+            match format {
+                Format::Markdown => print!("{}", MarkdownConverter::export(&document)),
+                Format::Json => println!("{}", serde_json::to_string_pretty(&document)?),
+            }
+        }
+        Command::Create { title, parent } => {
+            let document = DocumentModel {
+                id: 0,
+                parent_id: parent,
+                position: 0,
+                title,
+                content: json!([]),
+            };
+            println!("{}", repository.insert_document(document).await?);
+        }
+        Command::Append { id } => {
+            let mut document = get_document(&repository, id).await?;
+            let nodes = text_nodes(&read_stdin()?);
+            if nodes.is_empty() {
+                return Ok(());
+            }
+
+            match document.content.as_array_mut() {
+                Some(content) => content.extend(nodes),
+                None => document.content = Value::Array(nodes),
+            }
+            repository.update_document(document).await?;
+        }
+        Command::Rename { id, title } => {
+            let mut document = get_document(&repository, id).await?;
+            document.title = title;
+            repository.update_document(document).await?;
+        }
+        Command::Delete { id } => {
+            get_document(&repository, id).await?;
+            repository.delete_document(id).await?;
+        }
+        Command::Export { id, path } => {
+            let document = get_document(&repository, id).await?;
+            let markdown = MarkdownConverter::export(&document);
+
+            if path.as_os_str() == "-" {
+                std::io::stdout().write_all(markdown.as_bytes())?;
+            } else {
+                tokio::fs::write(&path, markdown)
+                    .await
+                    .with_context(|| format!("Failed to write {:?}", path))?;
+                write_attachments(&repository, &document, &path).await?;
+            }
+        }
+        Command::Import { path, parent } => {
+            let (markdown, fallback_title) = if path.as_os_str() == "-" {
+                (read_stdin()?, "Untitled".to_string())
+            } else {
+                let markdown = tokio::fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("Failed to read {:?}", path))?;
+                let fallback_title = path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Untitled".to_string());
+                (markdown, fallback_title)
+            };
+
+            let mut document = MarkdownConverter::import(&markdown, &fallback_title);
+            document.parent_id = parent;
+            println!("{}", repository.insert_document(document).await?);
+        }
+    }
+
+    Ok(())
+}
+
+/// Connect to the context named `name` in the settings, or to the default local database
+async fn connect(name: Option<&str>) -> Result<AnyDocumentRepository, Error> {
+    let remindr = Remindr::new();
+
+    let Some(name) = name else {
+        return remindr.connect_database(None).await;
+    };
+
+    let settings = remindr.load_settings().await?;
+    let context = settings
+        .contexts
+        .iter()
+        .find(|context| context.name() == Some(name))
+        .with_context(|| format!("No database context named \"{}\"", name))?;
+
+    remindr.connect_database(Some(context)).await
+}
+
+/// Document `id`, those in the trash being reported as not found
+async fn get_document(repository: &AnyDocumentRepository, id: i32) -> Result<DocumentModel, Error> {
+    repository
+        .find_document(id)
+        .await?
+        .with_context(|| format!("Document {} not found", id))
+}
+
+/// Write the attachments the Markdown exported to `path` points at next to it, the ones
+/// missing from the database being left out
+async fn write_attachments(
+    repository: &AnyDocumentRepository,
+    document: &DocumentModel,
+    path: &Path,
+) -> Result<(), Error> {
+    let directory = path.parent().unwrap_or(Path::new(""));
+
+    for (hash, relative_path) in MarkdownConverter::attachments(document) {
+        let Some(attachment) = repository.get_attachment(&hash).await? else {
+            continue;
+        };
+
+        let attachment_path = directory.join(relative_path);
+        if let Some(parent) = attachment_path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {:?}", parent))?;
+        }
+        tokio::fs::write(&attachment_path, attachment.data)
+            .await
+            .with_context(|| format!("Failed to write {:?}", attachment_path))?;
+    }
+
+    Ok(())
+}
+
+fn print_tree(documents: &[DocumentModel], parent_id: Option<i32>, depth: usize) {
+    for document in documents.iter().filter(|d| d.parent_id == parent_id) {
+        println!("{}\t{}{}", document.id, "  ".repeat(depth), document.title);
+        print_tree(documents, Some(document.id), depth + 1);
+    }
+}
+
+/// Documents without their content, the tree being described by `parent_id`
+fn summaries(documents: &[DocumentModel]) -> Vec<Value> {
+    documents
+        .iter()
+        .map(|document| {
+            json!({
+                "id": document.id,
+                "parent_id": document.parent_id,
+                "title": document.title,
+            })
+        })
+        .collect()
+}
+
+fn read_stdin() -> Result<String, Error> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .context("Failed to read stdin")?;
+
+    Ok(input)
+}
+
+/// A text block per non-empty line
+fn text_nodes(input: &str) -> Vec<Value> {
+    input
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            json!({
+                "id": Utils::generate_uuid(),
+                "type": "text",
+                "metadata": { "content": line, "spans": [] },
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,12 @@
+UPDATE documents
+SET content = (
+    SELECT json_group_array(
+        CASE
+            WHEN json_extract(node.value, '$.type') = 'text'
+            THEN json_remove(node.value, '$.metadata.spans')
+            ELSE json(node.value)
+        END
+    )
+    FROM json_each(documents.content) AS node
+)
+WHERE json_valid(content) AND json_type(content) = 'array';
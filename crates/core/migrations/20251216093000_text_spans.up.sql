@@ -0,0 +1,14 @@
+-- Text nodes now persist their rich-text spans: give existing ones an empty list
+UPDATE documents
+SET content = (
+    SELECT json_group_array(
+        CASE
+            WHEN json_extract(node.value, '$.type') = 'text'
+                AND json_type(node.value, '$.metadata.spans') IS NULL
+            THEN json_set(node.value, '$.metadata.spans', json('[]'))
+            ELSE json(node.value)
+        END
+    )
+    FROM json_each(documents.content) AS node
+)
+WHERE json_valid(content) AND json_type(content) = 'array';
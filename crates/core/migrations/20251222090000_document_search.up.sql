@@ -0,0 +1,23 @@
+-- One row holding the title of each document, plus one row per text block with an empty title
+CREATE VIRTUAL TABLE IF NOT EXISTS document_search USING fts5(
+    document_id UNINDEXED,
+    node_id UNINDEXED,
+    title,
+    content,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO document_search (document_id, node_id, title, content)
+SELECT id, NULL, title, '' FROM documents;
+
+INSERT INTO document_search (document_id, node_id, title, content)
+SELECT documents.id,
+    json_extract(node.value, '$.id'),
+    '',
+    json_extract(node.value, '$.metadata.content')
+FROM documents, json_tree(documents.content) AS node
+WHERE json_valid(documents.content)
+    AND node.type = 'object'
+    AND json_extract(node.value, '$.id') IS NOT NULL
+    AND json_type(node.value, '$.metadata.content') = 'text'
+    AND trim(json_extract(node.value, '$.metadata.content')) != '';
@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS documents_parent_idx;
+
+ALTER TABLE documents DROP COLUMN position;
+ALTER TABLE documents DROP COLUMN parent_id;
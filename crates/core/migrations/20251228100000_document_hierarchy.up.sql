@@ -0,0 +1,7 @@
+-- Documents form a tree: root pages have no parent, siblings are ordered by position
+ALTER TABLE documents ADD COLUMN parent_id INTEGER;
+ALTER TABLE documents ADD COLUMN position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE documents SET position = id;
+
+CREATE INDEX IF NOT EXISTS documents_parent_idx ON documents (parent_id, position);
@@ -0,0 +1,4 @@
+-- Deleted documents stay in the trash until restored or purged
+ALTER TABLE documents ADD COLUMN deleted_at TIMESTAMP;
+
+CREATE INDEX IF NOT EXISTS documents_deleted_idx ON documents (deleted_at);
@@ -0,0 +1,6 @@
+DROP TRIGGER IF EXISTS documents_outbox_delete;
+DROP TRIGGER IF EXISTS documents_outbox_update;
+DROP TRIGGER IF EXISTS documents_outbox_insert;
+DROP TABLE IF EXISTS sync_outbox;
+DROP TABLE IF EXISTS sync_documents;
+DROP TABLE IF EXISTS sync_state;
@@ -0,0 +1,46 @@
+-- Offline copies of a remote database. `sync_state` only has a row in such a copy, the
+-- local changes being queued in `sync_outbox` unless they come from the remote database.
+CREATE TABLE IF NOT EXISTS sync_state (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    -- Last remote revision pulled
+    cursor INTEGER NOT NULL DEFAULT 0,
+    -- Set while remote changes are written
+    applying BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+-- Remote document of each local one, with the state both had when last synced
+CREATE TABLE IF NOT EXISTS sync_documents (
+    document_id INTEGER PRIMARY KEY,
+    remote_id INTEGER NOT NULL UNIQUE,
+    parent_id INTEGER,
+    position INTEGER NOT NULL DEFAULT 0,
+    title TEXT NOT NULL,
+    content JSONB NOT NULL DEFAULT '[]',
+    deleted BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS sync_outbox (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    document_id INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS sync_outbox_document_idx ON sync_outbox (document_id);
+
+CREATE TRIGGER IF NOT EXISTS documents_outbox_insert AFTER INSERT ON documents
+WHEN EXISTS (SELECT 1 FROM sync_state WHERE NOT applying)
+BEGIN
+    INSERT INTO sync_outbox (document_id) VALUES (NEW.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS documents_outbox_update AFTER UPDATE ON documents
+WHEN EXISTS (SELECT 1 FROM sync_state WHERE NOT applying)
+BEGIN
+    INSERT INTO sync_outbox (document_id) VALUES (NEW.id);
+END;
+
+CREATE TRIGGER IF NOT EXISTS documents_outbox_delete AFTER DELETE ON documents
+WHEN EXISTS (SELECT 1 FROM sync_state WHERE NOT applying)
+BEGIN
+    INSERT INTO sync_outbox (document_id) VALUES (OLD.id);
+END;
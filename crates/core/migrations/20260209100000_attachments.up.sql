@@ -0,0 +1,7 @@
+-- Files embedded in documents, stored once per content and keyed by its SHA-256
+CREATE TABLE IF NOT EXISTS attachments (
+    hash TEXT PRIMARY KEY NOT NULL,
+    mime_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    data BLOB NOT NULL
+);
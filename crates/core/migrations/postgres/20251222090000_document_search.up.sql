@@ -0,0 +1,20 @@
+-- One row holding the title of each document, plus one row per text block with an empty title
+CREATE TABLE IF NOT EXISTS document_search (
+    document_id INTEGER NOT NULL REFERENCES documents (id) ON DELETE CASCADE,
+    node_id TEXT,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL DEFAULT '',
+    search tsvector GENERATED ALWAYS AS (to_tsvector('simple', title || ' ' || content)) STORED
+);
+
+CREATE INDEX IF NOT EXISTS document_search_idx ON document_search USING GIN (search);
+CREATE INDEX IF NOT EXISTS document_search_document_idx ON document_search (document_id);
+
+INSERT INTO document_search (document_id, node_id, title, content)
+SELECT id, NULL, title, '' FROM documents;
+
+INSERT INTO document_search (document_id, node_id, title, content)
+SELECT documents.id, node ->> 'id', '', node -> 'metadata' ->> 'content'
+FROM documents,
+    jsonb_path_query(documents.content, 'strict $.** ? (exists(@.id) && @.metadata.content.type() == "string")') AS node
+WHERE trim(node -> 'metadata' ->> 'content') != '';
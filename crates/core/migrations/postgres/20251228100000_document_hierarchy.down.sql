@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS documents_parent_idx;
+
+ALTER TABLE documents
+    DROP COLUMN IF EXISTS position,
+    DROP COLUMN IF EXISTS parent_id;
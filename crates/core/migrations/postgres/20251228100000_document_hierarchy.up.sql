@@ -0,0 +1,8 @@
+-- Documents form a tree: root pages have no parent, siblings are ordered by position
+ALTER TABLE documents
+    ADD COLUMN IF NOT EXISTS parent_id INTEGER REFERENCES documents (id) ON DELETE CASCADE,
+    ADD COLUMN IF NOT EXISTS position INTEGER NOT NULL DEFAULT 0;
+
+UPDATE documents SET position = id;
+
+CREATE INDEX IF NOT EXISTS documents_parent_idx ON documents (parent_id, position);
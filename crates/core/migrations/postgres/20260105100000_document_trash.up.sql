@@ -0,0 +1,4 @@
+-- Deleted documents stay in the trash until restored or purged
+ALTER TABLE documents ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS documents_deleted_idx ON documents (deleted_at);
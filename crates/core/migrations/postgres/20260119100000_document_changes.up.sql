@@ -0,0 +1,35 @@
+-- Publish every change of the documents on the `document_changes` channel, so that each
+-- client connected to the database can follow them with LISTEN
+CREATE FUNCTION notify_document_change() RETURNS TRIGGER AS $$
+DECLARE
+    change JSONB;
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        change := jsonb_build_object('kind', 'inserted', 'id', NEW.id);
+    ELSIF TG_OP = 'DELETE' THEN
+        -- Documents in the trash were already reported as deleted
+        IF OLD.deleted_at IS NOT NULL THEN
+            RETURN NULL;
+        END IF;
+        change := jsonb_build_object('kind', 'deleted', 'id', OLD.id);
+    ELSIF OLD.deleted_at IS NULL AND NEW.deleted_at IS NOT NULL THEN
+        change := jsonb_build_object('kind', 'deleted', 'id', NEW.id);
+    ELSIF OLD.deleted_at IS NOT NULL AND NEW.deleted_at IS NULL THEN
+        change := jsonb_build_object('kind', 'inserted', 'id', NEW.id);
+    ELSIF NEW.deleted_at IS NOT NULL THEN
+        RETURN NULL;
+    ELSIF OLD.title IS DISTINCT FROM NEW.title THEN
+        -- Payloads are limited to 8000 bytes
+        change := jsonb_build_object('kind', 'renamed', 'id', NEW.id, 'title', left(NEW.title, 1000));
+    ELSE
+        change := jsonb_build_object('kind', 'updated', 'id', NEW.id);
+    END IF;
+
+    PERFORM pg_notify('document_changes', change::TEXT);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER documents_notify_change
+AFTER INSERT OR UPDATE OR DELETE ON documents
+FOR EACH ROW EXECUTE FUNCTION notify_document_change();
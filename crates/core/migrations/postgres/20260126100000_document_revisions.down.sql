@@ -0,0 +1,7 @@
+DROP TRIGGER IF EXISTS documents_record_tombstone ON documents;
+DROP FUNCTION IF EXISTS record_document_tombstone();
+DROP TABLE IF EXISTS document_tombstones;
+DROP TRIGGER IF EXISTS documents_bump_revision ON documents;
+DROP FUNCTION IF EXISTS bump_document_revision();
+ALTER TABLE documents DROP COLUMN IF EXISTS revision;
+DROP SEQUENCE IF EXISTS document_revisions;
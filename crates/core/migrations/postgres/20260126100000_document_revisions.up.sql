@@ -0,0 +1,39 @@
+-- Every write of a document takes the next revision, so that offline copies can pull the
+-- changes made since the last revision they saw
+CREATE SEQUENCE IF NOT EXISTS document_revisions;
+
+ALTER TABLE documents
+    ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT nextval('document_revisions');
+
+CREATE INDEX IF NOT EXISTS documents_revision_idx ON documents (revision);
+
+CREATE FUNCTION bump_document_revision() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.revision := nextval('document_revisions');
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER documents_bump_revision
+BEFORE UPDATE ON documents
+FOR EACH ROW EXECUTE FUNCTION bump_document_revision();
+
+-- Documents deleted for good, so that the offline copies drop them too
+CREATE TABLE IF NOT EXISTS document_tombstones (
+    document_id INTEGER PRIMARY KEY,
+    revision BIGINT NOT NULL DEFAULT nextval('document_revisions')
+);
+
+CREATE INDEX IF NOT EXISTS document_tombstones_revision_idx ON document_tombstones (revision);
+
+CREATE FUNCTION record_document_tombstone() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO document_tombstones (document_id) VALUES (OLD.id)
+    ON CONFLICT (document_id) DO UPDATE SET revision = nextval('document_revisions');
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER documents_record_tombstone
+AFTER DELETE ON documents
+FOR EACH ROW EXECUTE FUNCTION record_document_tombstone();
@@ -0,0 +1,8 @@
+-- Documents linked from the text of each document, refreshed whenever it is saved
+CREATE TABLE IF NOT EXISTS document_links (
+    source_id INTEGER NOT NULL REFERENCES documents (id) ON DELETE CASCADE,
+    target_id INTEGER NOT NULL,
+    PRIMARY KEY (source_id, target_id)
+);
+
+CREATE INDEX IF NOT EXISTS document_links_target_idx ON document_links (target_id);
@@ -0,0 +1,591 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use anyhow::Error;
+use serde_json::Value;
+use yrs::{
+    Any, Array, ArrayRef, DeepObservable, Doc, GetString, Map, MapPrelim, MapRef, Origin, Out,
+    ReadTxn, StateVector, Subscription, Text, TextPrelim, TextRef, Transact, TransactionMut,
+    Update,
+    types::{Attrs, Delta, Event, PathSegment, text::YChange},
+    updates::{decoder::Decode, encoder::Encode},
+};
+
+/// Node types edited as rich text, their content and spans being a CRDT text
+const RICH_TEXT_TYPES: [&str; 4] = ["text", "bulleted_list", "numbered_list", "todo"];
+
+const BLOCKS: &str = "blocks";
+const NODES: &str = "nodes";
+const NODE: &str = "node";
+const DEPTH: &str = "depth";
+const TEXT: &str = "text";
+
+/// A node of a document in the flat layout of the editor, without its `children`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollabBlock {
+    pub id: String,
+    pub depth: usize,
+    pub node: Value,
+}
+
+/// Formatted range of a rich text, `style` being the name of a `RichTextStyle`, or its JSON
+/// for the styles carrying data such as links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMark {
+    pub start: usize,
+    pub end: usize,
+    pub style: String,
+}
+
+/// Step of a change made to a rich text, offsets being in bytes. Styles are given as the
+/// `style` of the spans, see `TextMark`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextDelta {
+    /// Skip `len` bytes, setting (`true`) or removing (`false`) styles on them
+    Retain {
+        len: usize,
+        styles: Vec<(Value, bool)>,
+    },
+    /// Insert `text` having `styles`
+    Insert {
+        text: String,
+        styles: Vec<Value>,
+    },
+    Delete {
+        len: usize,
+    },
+}
+
+/// Change a peer made to the text of a block
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextUpdate {
+    pub block_id: String,
+    pub delta: Vec<TextDelta>,
+}
+
+/// Content of a document shared between peers. The order of the blocks is a CRDT list of
+/// their ids, each block being a map holding its depth, its node and, for rich text blocks,
+/// a CRDT text whose formatting attributes are the spans of the node.
+#[derive(Clone)]
+pub struct CollabDocument {
+    doc: Doc,
+    blocks: ArrayRef,
+    nodes: MapRef,
+}
+
+impl CollabDocument {
+    pub fn new(client_id: u64) -> Self {
+        let doc = Doc::with_client_id(client_id);
+        let blocks = doc.get_or_insert_array(BLOCKS);
+        let nodes = doc.get_or_insert_map(NODES);
+
+        Self { doc, blocks, nodes }
+    }
+
+    pub fn client_id(&self) -> u64 {
+        self.doc.client_id()
+    }
+
+    pub(crate) fn doc(&self) -> &Doc {
+        &self.doc
+    }
+
+    /// Blocks in document order. Ids present twice after concurrent moves are kept once.
+    pub fn blocks(&self) -> Vec<CollabBlock> {
+        let txn = self.doc.transact();
+        let mut seen = HashSet::new();
+
+        self.block_ids(&txn)
+            .into_iter()
+            .filter(|id| seen.insert(id.clone()))
+            .filter_map(|id| {
+                let Some(Out::YMap(block)) = self.nodes.get(&txn, &id) else {
+                    return None;
+                };
+                read_block(&txn, id, &block)
+            })
+            .collect()
+    }
+
+    /// Content and spans of a rich text block
+    pub fn text(&self, id: &str) -> Option<(String, Vec<TextMark>)> {
+        let txn = self.doc.transact();
+        let text = self.text_ref(&txn, id)?;
+        Some(read_text(&txn, &text))
+    }
+
+    /// Write the blocks as edited locally, as the smallest set of changes from the current
+    /// state so that concurrent edits of other peers are kept
+    pub fn sync_blocks(&self, blocks: &[CollabBlock]) {
+        let mut txn = self.doc.transact_mut();
+
+        let current = self.block_ids(&txn);
+        let wanted: Vec<String> = blocks.iter().map(|block| block.id.clone()).collect();
+        let kept = longest_common_subsequence(&current, &wanted);
+
+        // Remove the blocks that moved or disappeared, then insert them at their new place
+        let mut kept_iter = kept.iter().peekable();
+        let mut removed = Vec::new();
+        for (index, id) in current.iter().enumerate() {
+            if kept_iter.peek() == Some(&id) {
+                kept_iter.next();
+            } else {
+                removed.push(index);
+            }
+        }
+        for index in removed.into_iter().rev() {
+            self.blocks.remove(&mut txn, index as u32);
+        }
+
+        for (index, id) in wanted.iter().enumerate() {
+            let at = self.blocks.get(&txn, index as u32).and_then(out_string);
+            if at.as_ref() != Some(id) {
+                self.blocks.insert(&mut txn, index as u32, id.as_str());
+            }
+        }
+
+        for block in blocks {
+            self.write_block(&mut txn, block);
+        }
+
+        let wanted: HashSet<&str> = wanted.iter().map(String::as_str).collect();
+        let stale: Vec<String> = self
+            .nodes
+            .keys(&txn)
+            .filter(|id| !wanted.contains(id))
+            .map(str::to_string)
+            .collect();
+        for id in stale {
+            self.nodes.remove(&mut txn, &id);
+        }
+    }
+
+    /// Call `f` with the changes made by other peers to the texts of the blocks, as deltas
+    /// to apply in order on the text each block had before
+    pub fn observe_remote_texts<F>(&self, f: F) -> Subscription
+    where
+        F: Fn(TextUpdate) + Send + Sync + 'static,
+    {
+        self.nodes.observe_deep(move |txn, events| {
+            // Local edits carry no origin, see `apply_update`
+            if txn.origin().is_none() {
+                return;
+            }
+
+            for event in events.iter() {
+                let Event::Text(text_event) = event else {
+                    continue;
+                };
+                let Some(PathSegment::Key(block_id)) = event.path().pop_front() else {
+                    continue;
+                };
+
+                f(TextUpdate {
+                    block_id: block_id.to_string(),
+                    delta: text_delta(text_event.delta(txn)),
+                });
+            }
+        })
+    }
+
+    /// Write the content and spans of a rich text block as edited locally
+    pub fn sync_text(&self, id: &str, content: &str, marks: &[TextMark]) {
+        let mut txn = self.doc.transact_mut();
+        if let Some(text) = self.text_ref(&txn, id) {
+            write_text(&mut txn, &text, content, marks);
+        }
+    }
+
+    pub fn state_vector(&self) -> Vec<u8> {
+        self.doc.transact().state_vector().encode_v1()
+    }
+
+    /// Changes missing from a peer whose state vector is `state_vector`
+    pub fn diff(&self, state_vector: &[u8]) -> Result<Vec<u8>, Error> {
+        let state_vector = StateVector::decode_v1(state_vector)?;
+        Ok(self.doc.transact().encode_diff_v1(&state_vector))
+    }
+
+    /// The whole document as a single update
+    pub fn full_update(&self) -> Vec<u8> {
+        self.doc
+            .transact()
+            .encode_state_as_update_v1(&StateVector::default())
+    }
+
+    /// Apply changes received from a peer, `origin` telling them apart from local edits
+    pub fn apply_update(&self, update: &[u8], origin: &str) -> Result<(), Error> {
+        let update = Update::decode_v1(update)?;
+        let mut txn = self.doc.transact_mut_with(Origin::from(origin));
+        txn.apply_update(update)
+            .map_err(|error| Error::msg(error.to_string()))
+    }
+
+    fn block_ids<T: ReadTxn>(&self, txn: &T) -> Vec<String> {
+        self.blocks.iter(txn).filter_map(out_string).collect()
+    }
+
+    fn text_ref<T: ReadTxn>(&self, txn: &T, id: &str) -> Option<TextRef> {
+        let Some(Out::YMap(block)) = self.nodes.get(txn, id) else {
+            return None;
+        };
+        match block.get(txn, TEXT) {
+            Some(Out::YText(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    fn write_block(&self, txn: &mut TransactionMut, block: &CollabBlock) {
+        let (node, text) = split_node(&block.node);
+        let node = node.to_string();
+
+        let existing = match self.nodes.get(txn, &block.id) {
+            Some(Out::YMap(existing)) => Some(existing),
+            _ => None,
+        };
+
+        let Some(existing) = existing else {
+            let map = self
+                .nodes
+                .insert(txn, block.id.as_str(), MapPrelim::default());
+            map.insert(txn, NODE, node);
+            map.insert(txn, DEPTH, block.depth as f64);
+            if let Some((content, marks)) = text {
+                let text = map.insert(txn, TEXT, TextPrelim::new(""));
+                write_text(txn, &text, &content, &marks);
+            }
+            return;
+        };
+
+        if existing.get(txn, NODE).and_then(out_string).as_ref() != Some(&node) {
+            existing.insert(txn, NODE, node);
+        }
+        if existing.get(txn, DEPTH).and_then(out_number) != Some(block.depth) {
+            existing.insert(txn, DEPTH, block.depth as f64);
+        }
+
+        match (existing.get(txn, TEXT), text) {
+            (Some(Out::YText(current)), Some((content, marks))) => {
+                write_text(txn, &current, &content, &marks)
+            }
+            // The block became a rich text block, or stopped being one
+            (None, Some((content, marks))) => {
+                let text = existing.insert(txn, TEXT, TextPrelim::new(""));
+                write_text(txn, &text, &content, &marks);
+            }
+            (Some(_), None) => {
+                existing.remove(txn, TEXT);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A node without its rich text, which is stored apart, and without its `children`
+fn split_node(node: &Value) -> (Value, Option<(String, Vec<TextMark>)>) {
+    let mut node = node.clone();
+    if let Some(object) = node.as_object_mut() {
+        object.remove("children");
+    }
+
+    let is_rich_text = node["type"]
+        .as_str()
+        .is_some_and(|node_type| RICH_TEXT_TYPES.contains(&node_type));
+    if !is_rich_text {
+        return (node, None);
+    }
+
+    let Some(metadata) = node.get_mut("metadata").and_then(Value::as_object_mut) else {
+        return (node, None);
+    };
+    let content = metadata
+        .remove("content")
+        .and_then(|content| content.as_str().map(str::to_string))
+        .unwrap_or_default();
+    let marks = metadata
+        .remove("spans")
+        .and_then(|spans| spans.as_array().cloned())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|span| {
+            Some(TextMark {
+                start: span["start"].as_u64()? as usize,
+                end: span["end"].as_u64()? as usize,
+                style: mark_style(&span["style"])?,
+            })
+        })
+        .collect();
+
+    (node, Some((content, marks)))
+}
+
+fn read_block<T: ReadTxn>(txn: &T, id: String, block: &MapRef) -> Option<CollabBlock> {
+    let mut node: Value = serde_json::from_str(&block.get(txn, NODE).and_then(out_string)?).ok()?;
+    let depth = block.get(txn, DEPTH).and_then(out_number).unwrap_or(0);
+
+    if let (Some(Out::YText(text)), Some(metadata)) = (
+        block.get(txn, TEXT),
+        node.get_mut("metadata").and_then(Value::as_object_mut),
+    ) {
+        let (content, marks) = read_text(txn, &text);
+        metadata.insert("content".to_string(), Value::String(content));
+        metadata.insert(
+            "spans".to_string(),
+            Value::Array(
+                marks
+                    .into_iter()
+                    .map(|mark| {
+                        serde_json::json!({
+                            "start": mark.start,
+                            "end": mark.end,
+                            "style": style_value(&mark.style),
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+    }
+
+    Some(CollabBlock { id, depth, node })
+}
+
+fn read_text<T: ReadTxn>(txn: &T, text: &TextRef) -> (String, Vec<TextMark>) {
+    let mut content = String::new();
+    let mut marks: Vec<TextMark> = Vec::new();
+
+    for chunk in text.diff(txn, YChange::identity) {
+        let Out::Any(Any::String(chunk_text)) = &chunk.insert else {
+            continue;
+        };
+        let start = content.len();
+        content.push_str(chunk_text);
+        let end = content.len();
+
+        for (style, value) in chunk
+            .attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+        {
+            if !matches!(value, Any::Bool(true)) {
+                continue;
+            }
+            // Consecutive chunks of the same style form a single span
+            match marks
+                .iter_mut()
+                .find(|mark| mark.style == style.as_ref() && mark.end == start)
+            {
+                Some(mark) => mark.end = end,
+                None => marks.push(TextMark {
+                    start,
+                    end,
+                    style: style.to_string(),
+                }),
+            }
+        }
+    }
+
+    (content, marks)
+}
+
+fn text_delta(delta: &[Delta]) -> Vec<TextDelta> {
+    let styles = |attributes: &Option<Box<Attrs>>| -> Vec<(Value, bool)> {
+        attributes
+            .iter()
+            .flat_map(|attributes| attributes.iter())
+            .map(|(style, value)| (style_value(style), matches!(value, Any::Bool(true))))
+            .collect()
+    };
+
+    delta
+        .iter()
+        .filter_map(|step| match step {
+            Delta::Retain(len, attributes) => Some(TextDelta::Retain {
+                len: *len as usize,
+                styles: styles(attributes),
+            }),
+            Delta::Inserted(Out::Any(Any::String(text)), attributes) => Some(TextDelta::Insert {
+                text: text.to_string(),
+                styles: styles(attributes)
+                    .into_iter()
+                    .filter(|(_, set)| *set)
+                    .map(|(style, _)| style)
+                    .collect(),
+            }),
+            // Blocks hold no embedded values
+            Delta::Inserted(..) => None,
+            Delta::Deleted(len) => Some(TextDelta::Delete { len: *len as usize }),
+        })
+        .collect()
+}
+
+/// Turn `text` into `content` with `marks`, replacing only what differs
+fn write_text(txn: &mut TransactionMut, text: &TextRef, content: &str, marks: &[TextMark]) {
+    let current = text.get_string(txn);
+    if current != content {
+        let prefix = common_prefix(&current, content);
+        let suffix = common_suffix(&current[prefix..], &content[prefix..]);
+
+        let removed = current.len() - prefix - suffix;
+        if removed > 0 {
+            text.remove_range(txn, prefix as u32, removed as u32);
+        }
+        let inserted = &content[prefix..content.len() - suffix];
+        if !inserted.is_empty() {
+            text.insert(txn, prefix as u32, inserted);
+        }
+    }
+
+    let (_, current_marks) = read_text(txn, text);
+    let mut styles: Vec<&str> = current_marks
+        .iter()
+        .chain(marks)
+        .map(|mark| mark.style.as_str())
+        .collect();
+    styles.sort();
+    styles.dedup();
+
+    for style in styles {
+        let current = ranges(&current_marks, style);
+        let wanted = ranges(marks, style);
+
+        for (start, end) in subtract(&wanted, &current) {
+            format(txn, text, start, end, style, Any::Bool(true));
+        }
+        for (start, end) in subtract(&current, &wanted) {
+            format(txn, text, start, end, style, Any::Null);
+        }
+    }
+}
+
+fn format(
+    txn: &mut TransactionMut,
+    text: &TextRef,
+    start: usize,
+    end: usize,
+    style: &str,
+    value: Any,
+) {
+    let attributes: Attrs = HashMap::from([(Arc::<str>::from(style), value)]);
+    text.format(txn, start as u32, (end - start) as u32, attributes);
+}
+
+/// Name of the formatting attribute of a span style
+fn mark_style(style: &Value) -> Option<String> {
+    match style {
+        Value::String(name) => Some(name.clone()),
+        Value::Object(_) => Some(style.to_string()),
+        _ => None,
+    }
+}
+
+/// Span style of a formatting attribute, see `mark_style`
+fn style_value(style: &str) -> Value {
+    match serde_json::from_str::<Value>(style) {
+        Ok(value @ Value::Object(_)) => value,
+        _ => Value::String(style.to_string()),
+    }
+}
+
+/// Sorted and merged ranges of a style
+fn ranges(marks: &[TextMark], style: &str) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = marks
+        .iter()
+        .filter(|mark| mark.style == style && mark.start < mark.end)
+        .map(|mark| (mark.start, mark.end))
+        .collect();
+    ranges.sort();
+
+    let mut merged: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if last.1 >= start => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Parts of `ranges` not covered by `other`, both being sorted and merged
+fn subtract(ranges: &[(usize, usize)], other: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    for &(start, end) in ranges {
+        let mut start = start;
+        for &(other_start, other_end) in other {
+            if other_end <= start || other_start >= end {
+                continue;
+            }
+            if other_start > start {
+                result.push((start, other_start));
+            }
+            start = start.max(other_end);
+        }
+        if start < end {
+            result.push((start, end));
+        }
+    }
+    result
+}
+
+/// Length in bytes of the common prefix, on a char boundary
+fn common_prefix(a: &str, b: &str) -> usize {
+    a.chars()
+        .zip(b.chars())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum()
+}
+
+/// Length in bytes of the common suffix, on a char boundary
+fn common_suffix(a: &str, b: &str) -> usize {
+    a.chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum()
+}
+
+fn longest_common_subsequence(a: &[String], b: &[String]) -> Vec<String> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            result.push(a[i].clone());
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+fn out_string(value: Out) -> Option<String> {
+    match value {
+        Out::Any(Any::String(value)) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn out_number(value: Out) -> Option<usize> {
+    match value {
+        Out::Any(Any::Number(value)) => Some(value as usize),
+        Out::Any(Any::BigInt(value)) => Some(value as usize),
+        _ => None,
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{Error, bail};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest frame accepted from a paired peer
+pub const MAX_FRAME_LEN: usize = 64 * 1024 * 1024;
+/// Largest frame accepted while pairing, before the peer is known to hold the token
+pub const MAX_PAIRING_FRAME_LEN: usize = 4 * 1024;
+
+/// Message exchanged between two peers. The host first sends a `Challenge`, which the joining
+/// peer answers with a `Hello` proving it holds the pairing token without sending it. The host
+/// sends nothing of the document until the proof matches. Each side then starts with
+/// `SyncStep1`, answered by the changes it misses, after which updates and cursors are sent
+/// as they happen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CollabMessage {
+    Challenge {
+        nonce: String,
+    },
+    /// SHA-256 of the nonce of the challenge followed by the pairing token, in hex
+    Hello {
+        proof: String,
+    },
+    SyncStep1 {
+        #[serde(with = "base64_bytes")]
+        state_vector: Vec<u8>,
+    },
+    SyncStep2 {
+        #[serde(with = "base64_bytes")]
+        update: Vec<u8>,
+    },
+    Update {
+        #[serde(with = "base64_bytes")]
+        update: Vec<u8>,
+    },
+    Cursor(RemoteCursor),
+    /// The peer with this client id closed the document
+    Leave {
+        client_id: u64,
+    },
+}
+
+/// Position of a peer in the document, `None` when no block of it has the focus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteCursor {
+    pub client_id: u64,
+    pub name: String,
+    /// RGB color the cursor is drawn with
+    pub color: u32,
+    pub block_id: Option<String>,
+    pub anchor: usize,
+    pub head: usize,
+}
+
+/// Write a message as its length followed by its JSON
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &CollabMessage,
+) -> Result<(), Error> {
+    let frame = serde_json::to_vec(message)?;
+    writer.write_u32(frame.len() as u32).await?;
+    writer.write_all(&frame).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read the next message of at most `max_len` bytes, `None` once the peer closed the connection
+pub async fn read_message<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    max_len: usize,
+) -> Result<Option<CollabMessage>, Error> {
+    let len = match reader.read_u32().await {
+        Ok(len) => len as usize,
+        Err(error) if error.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    if len > max_len {
+        bail!("frame of {} bytes is too large", len);
+    }
+
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame).await?;
+    Ok(Some(serde_json::from_slice(&frame)?))
+}
+
+/// Binary yrs payloads, sent as base64 rather than as JSON arrays of numbers
+mod base64_bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(de::Error::custom)
+    }
+}
@@ -0,0 +1,3 @@
+pub mod document;
+pub mod message;
+pub mod peer;
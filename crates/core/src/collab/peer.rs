@@ -0,0 +1,522 @@
+use std::{
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use anyhow::{Context, Error, bail};
+use sha2::{Digest, Sha256};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    task::JoinHandle,
+    time::timeout,
+};
+use uuid::Uuid;
+use yrs::{Origin, Subscription};
+
+use crate::{
+    Utils,
+    collab::{
+        document::{CollabDocument, TextUpdate},
+        message::{
+            CollabMessage, MAX_FRAME_LEN, MAX_PAIRING_FRAME_LEN, RemoteCursor, read_message,
+            write_message,
+        },
+    },
+};
+
+/// Prefix of the transaction origin of the changes received through a session
+const SESSION_ORIGIN: &str = "session-";
+
+/// Messages waiting to be sent before a slow session starts missing some
+const OUTGOING_CAPACITY: usize = 1024;
+const EVENTS_CAPACITY: usize = 256;
+
+/// Longest wait for a peer to take its turn in the pairing
+const PAIRING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What happened to a shared document, for the editor showing it
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollabEvent {
+    /// The state of a peer was received after connecting to it
+    Synced,
+    /// Changes of a peer were applied to the document
+    RemoteChange,
+    CursorsChanged,
+    /// A connection ended, with the error that ended it if any
+    Disconnected {
+        error: Option<String>,
+    },
+}
+
+/// How a session proves the joining peer was given the pairing token of the host
+enum Pairing {
+    /// Hosting, the answer to the challenge has to be made from this token
+    Expect(String),
+    /// Joining, the challenge of the host is answered with this token
+    Offer(String),
+}
+
+#[derive(Clone)]
+struct Outgoing {
+    /// Session the message came from, which does not need it back
+    from: Option<u64>,
+    message: CollabMessage,
+}
+
+struct Shared {
+    document: CollabDocument,
+    outgoing: broadcast::Sender<Outgoing>,
+    events: broadcast::Sender<CollabEvent>,
+    cursors: Mutex<HashMap<u64, RemoteCursor>>,
+    local_cursor: Mutex<Option<RemoteCursor>>,
+    /// Whether received updates wait for `apply_pending`, with their session origin
+    deferred: Arc<AtomicBool>,
+    pending: Mutex<Vec<(Vec<u8>, String)>>,
+    /// Text changes made by the updates `apply_pending` applies
+    text_updates: Arc<Mutex<Vec<TextUpdate>>>,
+    next_session: AtomicU64,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+}
+
+/// A copy of a document kept in sync with the peers it is connected to. Changes received
+/// from a peer are relayed to the others, so peers can be connected in a star around a host.
+pub struct CollabPeer {
+    shared: Arc<Shared>,
+    name: String,
+    color: u32,
+    _updates: Subscription,
+    _texts: Subscription,
+}
+
+impl CollabPeer {
+    /// A peer with an empty document, `name` and `color` identifying its cursor
+    pub fn new(name: impl Into<String>, color: u32) -> Result<Self, Error> {
+        // Client ids are kept within the integers JavaScript peers can represent
+        let client_id = (Utils::generate_uuid().as_u128() as u64) & ((1 << 53) - 1);
+        let document = CollabDocument::new(client_id);
+
+        let (outgoing, _) = broadcast::channel(OUTGOING_CAPACITY);
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let deferred = Arc::new(AtomicBool::new(false));
+
+        let updates = {
+            let outgoing = outgoing.clone();
+            let events = events.clone();
+            let deferred = deferred.clone();
+            document
+                .doc()
+                .observe_update_v1(move |txn, event| {
+                    let from = txn.origin().and_then(session_of);
+                    let _ = outgoing.send(Outgoing {
+                        from,
+                        message: CollabMessage::Update {
+                            update: event.update.clone(),
+                        },
+                    });
+                    // Deferred updates were announced when they were received
+                    if from.is_some() && !deferred.load(Ordering::Relaxed) {
+                        let _ = events.send(CollabEvent::RemoteChange);
+                    }
+                })
+                .map_err(|error| Error::msg(error.to_string()))?
+        };
+
+        let text_updates = Arc::new(Mutex::new(Vec::new()));
+        let texts = {
+            let text_updates = text_updates.clone();
+            let deferred = deferred.clone();
+            document.observe_remote_texts(move |update| {
+                if deferred.load(Ordering::Relaxed) {
+                    text_updates.lock().unwrap().push(update);
+                }
+            })
+        };
+
+        Ok(Self {
+            shared: Arc::new(Shared {
+                document,
+                outgoing,
+                events,
+                cursors: Mutex::new(HashMap::new()),
+                local_cursor: Mutex::new(None),
+                deferred,
+                pending: Mutex::new(Vec::new()),
+                text_updates,
+                next_session: AtomicU64::new(0),
+                tasks: Mutex::new(Vec::new()),
+            }),
+            name: name.into(),
+            color,
+            _updates: updates,
+            _texts: texts,
+        })
+    }
+
+    pub fn document(&self) -> &CollabDocument {
+        &self.shared.document
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<CollabEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Keep the updates received from now on until `apply_pending` is called, so that an
+    /// editor only sees the document change on its own thread, between two of its edits
+    pub fn defer_updates(&self) {
+        self.shared.deferred.store(true, Ordering::Relaxed);
+    }
+
+    /// Apply the updates kept by `defer_updates`, returning the changes they made to the
+    /// texts of the blocks in order
+    pub fn apply_pending(&self) -> Result<Vec<TextUpdate>, Error> {
+        self.shared.text_updates.lock().unwrap().clear();
+
+        let pending = std::mem::take(&mut *self.shared.pending.lock().unwrap());
+        for (update, origin) in pending {
+            self.shared.document.apply_update(&update, &origin)?;
+        }
+
+        Ok(std::mem::take(
+            &mut *self.shared.text_updates.lock().unwrap(),
+        ))
+    }
+
+    /// Cursors of the other peers
+    pub fn cursors(&self) -> Vec<RemoteCursor> {
+        self.shared
+            .cursors
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Share the position of the local cursor, `block_id` being `None` when no block has it
+    pub fn set_cursor(&self, block_id: Option<String>, anchor: usize, head: usize) {
+        let cursor = RemoteCursor {
+            client_id: self.shared.document.client_id(),
+            name: self.name.clone(),
+            color: self.color,
+            block_id,
+            anchor,
+            head,
+        };
+
+        let mut local_cursor = self.shared.local_cursor.lock().unwrap();
+        if local_cursor.as_ref() == Some(&cursor) {
+            return;
+        }
+        *local_cursor = Some(cursor.clone());
+
+        let _ = self.shared.outgoing.send(Outgoing {
+            from: None,
+            message: CollabMessage::Cursor(cursor),
+        });
+    }
+
+    /// A random token for `host`, to be given to the peers allowed to join
+    pub fn pairing_token() -> String {
+        Uuid::new_v4().simple().to_string()
+    }
+
+    /// Accept the peers sending `token` on `address`, returning the address actually
+    /// listened on
+    pub async fn host(
+        &self,
+        address: impl ToSocketAddrs,
+        token: impl Into<String>,
+    ) -> Result<SocketAddr, Error> {
+        let listener = TcpListener::bind(address).await?;
+        let local_address = listener.local_addr()?;
+        let token = token.into();
+
+        let shared = self.shared.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let _ = stream.set_nodelay(true);
+                Shared::connect(&shared, stream, Pairing::Expect(token.clone()));
+            }
+        });
+        self.shared.tasks.lock().unwrap().push(task);
+
+        Ok(local_address)
+    }
+
+    /// Connect to a peer hosting the document on `address` with its pairing `token`
+    pub async fn join(&self, address: impl ToSocketAddrs, token: &str) -> Result<(), Error> {
+        let stream = TcpStream::connect(address)
+            .await
+            .context("Could not reach the host")?;
+        let _ = stream.set_nodelay(true);
+
+        Shared::connect(&self.shared, stream, Pairing::Offer(token.to_string()));
+        Ok(())
+    }
+
+    /// Exchange changes over any byte stream with a peer accepting `token`
+    pub fn connect<S>(&self, stream: S, token: &str)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        Shared::connect(&self.shared, stream, Pairing::Offer(token.to_string()));
+    }
+
+    /// Exchange changes over any byte stream with a peer, once it sent `token`
+    pub fn accept<S>(&self, stream: S, token: &str)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        Shared::connect(&self.shared, stream, Pairing::Expect(token.to_string()));
+    }
+}
+
+impl Drop for CollabPeer {
+    fn drop(&mut self) {
+        for task in self.shared.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Shared {
+    fn connect<S>(shared: &Arc<Shared>, stream: S, pairing: Pairing)
+    where
+        S: AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let session = shared.next_session.fetch_add(1, Ordering::Relaxed);
+        let task = tokio::spawn({
+            let shared = shared.clone();
+            async move {
+                let error = shared.run_session(session, stream, pairing).await.err();
+                let _ = shared.events.send(CollabEvent::Disconnected {
+                    error: error.map(|error| format!("{:#}", error)),
+                });
+            }
+        });
+
+        let mut tasks = shared.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task);
+    }
+
+    async fn run_session<S>(&self, session: u64, stream: S, pairing: Pairing) -> Result<(), Error>
+    where
+        S: AsyncRead + AsyncWrite + Send,
+    {
+        let (mut reader, mut writer) = tokio::io::split(stream);
+        let origin = format!("{}{}", SESSION_ORIGIN, session);
+
+        // Nothing of the document is sent to a peer before it proves it was given the token,
+        // and the token itself never goes over the connection
+        let joining = match pairing {
+            Pairing::Expect(token) => {
+                let nonce = Uuid::new_v4().simple().to_string();
+                let expected = pairing_proof(&nonce, &token);
+                write_message(&mut writer, &CollabMessage::Challenge { nonce }).await?;
+
+                let hello = timeout(
+                    PAIRING_TIMEOUT,
+                    read_message(&mut reader, MAX_PAIRING_FRAME_LEN),
+                )
+                .await
+                .context("A peer did not send the pairing token in time")??;
+                match hello {
+                    Some(CollabMessage::Hello { proof }) if same_token(&proof, &expected) => {}
+                    Some(_) => bail!("A peer tried to join without the pairing token"),
+                    None => return Ok(()),
+                }
+                false
+            }
+            Pairing::Offer(token) => {
+                let challenge = timeout(
+                    PAIRING_TIMEOUT,
+                    read_message(&mut reader, MAX_PAIRING_FRAME_LEN),
+                )
+                .await
+                .context("The host did not start the pairing in time")??;
+                let nonce = match challenge {
+                    Some(CollabMessage::Challenge { nonce }) => nonce,
+                    Some(_) => bail!("The host did not start the pairing"),
+                    None => bail!("The host closed the connection"),
+                };
+                let proof = pairing_proof(&nonce, &token);
+                write_message(&mut writer, &CollabMessage::Hello { proof }).await?;
+                true
+            }
+        };
+
+        let greeted = AtomicBool::new(false);
+
+        // Subscribed first, so that no change made while the peer syncs is missed
+        let mut outgoing = self.outgoing.subscribe();
+        let (replies, mut pending_replies) = mpsc::unbounded_channel::<CollabMessage>();
+
+        let mut greeting = vec![CollabMessage::SyncStep1 {
+            state_vector: self.document.state_vector(),
+        }];
+        greeting.extend(
+            self.local_cursor
+                .lock()
+                .unwrap()
+                .clone()
+                .map(CollabMessage::Cursor),
+        );
+        greeting.extend(
+            self.cursors
+                .lock()
+                .unwrap()
+                .values()
+                .cloned()
+                .map(CollabMessage::Cursor),
+        );
+        let client_ids = Mutex::new(HashSet::new());
+
+        let send = async {
+            for message in &greeting {
+                write_message(&mut writer, message).await?;
+            }
+            loop {
+                tokio::select! {
+                    reply = pending_replies.recv() => match reply {
+                        Some(message) => write_message(&mut writer, &message).await?,
+                        None => break,
+                    },
+                    frame = outgoing.recv() => match frame {
+                        Ok(frame) if frame.from != Some(session) => {
+                            write_message(&mut writer, &frame.message).await?
+                        }
+                        Ok(_) => {}
+                        // Updates were missed, the whole document brings the peer up to date
+                        Err(RecvError::Lagged(_)) => {
+                            let update = self.document.full_update();
+                            write_message(&mut writer, &CollabMessage::SyncStep2 { update }).await?
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+            Ok::<_, Error>(())
+        };
+
+        let receive = async {
+            while let Some(message) = read_message(&mut reader, MAX_FRAME_LEN).await? {
+                match message {
+                    CollabMessage::Challenge { .. } | CollabMessage::Hello { .. } => {
+                        bail!("The peer is already paired")
+                    }
+                    CollabMessage::SyncStep1 { state_vector } => {
+                        greeted.store(true, Ordering::Relaxed);
+                        let update = self.document.diff(&state_vector)?;
+                        let _ = replies.send(CollabMessage::SyncStep2 { update });
+                    }
+                    CollabMessage::SyncStep2 { update } => {
+                        self.receive_update(update, &origin)?;
+                        let _ = self.events.send(CollabEvent::Synced);
+                    }
+                    CollabMessage::Update { update } => {
+                        self.receive_update(update, &origin)?;
+                    }
+                    CollabMessage::Cursor(cursor) => {
+                        client_ids.lock().unwrap().insert(cursor.client_id);
+                        self.cursors
+                            .lock()
+                            .unwrap()
+                            .insert(cursor.client_id, cursor.clone());
+                        self.relay(session, CollabMessage::Cursor(cursor));
+                    }
+                    CollabMessage::Leave { client_id } => {
+                        self.cursors.lock().unwrap().remove(&client_id);
+                        self.relay(session, CollabMessage::Leave { client_id });
+                    }
+                }
+            }
+            Ok::<_, Error>(())
+        };
+
+        let result = tokio::select! {
+            result = send => result,
+            result = receive => result,
+        };
+
+        // The peers reached through this session are gone with it
+        for client_id in client_ids.into_inner().unwrap() {
+            self.cursors.lock().unwrap().remove(&client_id);
+            self.relay(session, CollabMessage::Leave { client_id });
+        }
+
+        // The host closes the connection without a word when the token does not match
+        if joining && !greeted.into_inner() {
+            return match result {
+                Ok(()) => bail!("The host refused the pairing token"),
+                Err(error) => Err(error.context("The host refused the pairing token")),
+            };
+        }
+
+        result
+    }
+
+    fn receive_update(&self, update: Vec<u8>, origin: &str) -> Result<(), Error> {
+        if !self.deferred.load(Ordering::Relaxed) {
+            return self.document.apply_update(&update, origin);
+        }
+
+        self.pending
+            .lock()
+            .unwrap()
+            .push((update, origin.to_string()));
+        let _ = self.events.send(CollabEvent::RemoteChange);
+        Ok(())
+    }
+
+    /// Send a message received from `session` to the other sessions
+    fn relay(&self, session: u64, message: CollabMessage) {
+        if matches!(
+            message,
+            CollabMessage::Cursor(_) | CollabMessage::Leave { .. }
+        ) {
+            let _ = self.events.send(CollabEvent::CursorsChanged);
+        }
+        let _ = self.outgoing.send(Outgoing {
+            from: Some(session),
+            message,
+        });
+    }
+}
+
+/// Answer to the challenge `nonce`, which only a peer holding `token` can make
+fn pairing_proof(nonce: &str, token: &str) -> String {
+    let digest = Sha256::new()
+        .chain_update(nonce)
+        .chain_update(token)
+        .finalize();
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Compare tokens in a time not depending on where they differ
+fn same_token(sent: &str, expected: &str) -> bool {
+    sent.len() == expected.len()
+        && sent
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn session_of(origin: &Origin) -> Option<u64> {
+    std::str::from_utf8(origin.as_ref())
+        .ok()?
+        .strip_prefix(SESSION_ORIGIN)?
+        .parse()
+        .ok()
+}
@@ -0,0 +1,32 @@
+use sha2::{Digest, Sha256};
+
+/// File embedded in a document. Attachments are identified by their content, so a file
+/// inserted twice is stored once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttachmentModel {
+    pub hash: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl AttachmentModel {
+    pub fn new(mime_type: impl Into<String>, data: Vec<u8>) -> Self {
+        Self {
+            hash: Self::hash_of(&data),
+            mime_type: mime_type.into(),
+            data,
+        }
+    }
+
+    /// Hex encoded SHA-256 of `data`
+    pub fn hash_of(data: &[u8]) -> String {
+        Sha256::digest(data)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn is_image(&self) -> bool {
+        self.mime_type.starts_with("image/")
+    }
+}
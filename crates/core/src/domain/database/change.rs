@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+/// Change made to the stored documents, published once written
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DocumentChange {
+    /// Created, or brought back from the trash
+    Inserted { id: i32 },
+    /// Content or place in the page tree changed
+    Updated { id: i32 },
+    /// Title changed, possibly along with the content
+    Renamed { id: i32, title: String },
+    /// Moved to the trash or deleted
+    Deleted { id: i32 },
+}
+
+impl DocumentChange {
+    pub fn document_id(&self) -> i32 {
+        match self {
+            Self::Inserted { id }
+            | Self::Updated { id }
+            | Self::Renamed { id, .. }
+            | Self::Deleted { id } => *id,
+        }
+    }
+}
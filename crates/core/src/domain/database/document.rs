@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct DocumentModel {
+    pub id: i32,
+    /// Page containing this one, `None` for root pages
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    /// Order among the pages sharing the same parent
+    #[serde(default)]
+    pub position: i32,
+    pub title: String,
+    pub content: Value,
+}
+
+impl DocumentModel {
+    /// Plain text of every block holding text, nested blocks included, as `(node id, text)`
+    pub fn text_blocks(&self) -> Vec<(String, String)> {
+        fn collect(nodes: &[Value], blocks: &mut Vec<(String, String)>) {
+            for node in nodes {
+                let id = node["id"].as_str();
+                let content = node["metadata"]["content"]
+                    .as_str()
+                    .filter(|content| !content.trim().is_empty());
+
+                if let (Some(id), Some(content)) = (id, content) {
+                    blocks.push((id.to_string(), content.to_string()));
+                }
+
+                if let Some(children) = node["children"].as_array() {
+                    collect(children, blocks);
+                }
+            }
+        }
+
+        let mut blocks = Vec::new();
+        if let Some(nodes) = self.content.as_array() {
+            collect(nodes, &mut blocks);
+        }
+
+        blocks
+    }
+
+    /// Ids of the documents linked from the text blocks, nested blocks included
+    pub fn linked_documents(&self) -> Vec<i32> {
+        fn collect(nodes: &[Value], ids: &mut Vec<i32>) {
+            for node in nodes {
+                if let Some(spans) = node["metadata"]["spans"].as_array() {
+                    ids.extend(
+                        spans
+                            .iter()
+                            .filter_map(|span| span["style"]["DocumentLink"]["id"].as_i64())
+                            .map(|id| id as i32),
+                    );
+                }
+
+                if let Some(children) = node["children"].as_array() {
+                    collect(children, ids);
+                }
+            }
+        }
+
+        let mut ids = Vec::new();
+        if let Some(nodes) = self.content.as_array() {
+            collect(nodes, &mut ids);
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
@@ -0,0 +1,6 @@
+pub mod attachment;
+pub mod change;
+pub mod document;
+pub mod revision;
+pub mod search;
+pub mod version;
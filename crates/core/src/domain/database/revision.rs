@@ -0,0 +1,16 @@
+use crate::domain::database::document::DocumentModel;
+
+/// State of a document at a revision of a remote database, trashed documents included
+#[derive(Debug, Clone)]
+pub struct DocumentRevisionModel {
+    pub document: DocumentModel,
+    pub deleted: bool,
+    pub revision: i64,
+}
+
+/// Document deleted for good at a revision of a remote database
+#[derive(Debug, Clone)]
+pub struct TombstoneModel {
+    pub document_id: i32,
+    pub revision: i64,
+}
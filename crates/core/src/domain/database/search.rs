@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A block (or a title when `node_id` is empty) matching a full-text search
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResultModel {
+    pub document_id: i32,
+    pub node_id: Option<Uuid>,
+    pub title: String,
+    pub snippet: String,
+}
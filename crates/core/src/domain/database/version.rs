@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+/// Snapshot of a document at a point in time
+#[derive(Debug, Clone)]
+pub struct DocumentVersionModel {
+    pub id: i32,
+    pub document_id: i32,
+    pub title: String,
+    pub content: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Difference of a single block between two contents, nested blocks being compared on their own
+#[derive(Debug, Clone)]
+pub enum BlockDiff {
+    Added(Value),
+    Removed(Value),
+    Changed { before: Value, after: Value },
+    Unchanged(Value),
+}
+
+impl DocumentVersionModel {
+    /// Blocks of this version compared to `current`, matched by id in document order
+    pub fn diff(&self, current: &Value) -> Vec<BlockDiff> {
+        let before = flatten_blocks(&self.content);
+        let after = flatten_blocks(current);
+
+        // Longest common subsequence of the block ids
+        let mut lengths = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+        for i in (0..before.len()).rev() {
+            for j in (0..after.len()).rev() {
+                lengths[i][j] = if before[i].0 == after[j].0 {
+                    lengths[i + 1][j + 1] + 1
+                } else {
+                    lengths[i + 1][j].max(lengths[i][j + 1])
+                };
+            }
+        }
+
+        let mut diff = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < before.len() && j < after.len() {
+            if before[i].0 == after[j].0 {
+                if before[i].1 == after[j].1 {
+                    diff.push(BlockDiff::Unchanged(after[j].1.clone()));
+                } else {
+                    diff.push(BlockDiff::Changed {
+                        before: before[i].1.clone(),
+                        after: after[j].1.clone(),
+                    });
+                }
+                i += 1;
+                j += 1;
+            } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+                diff.push(BlockDiff::Removed(before[i].1.clone()));
+                i += 1;
+            } else {
+                diff.push(BlockDiff::Added(after[j].1.clone()));
+                j += 1;
+            }
+        }
+
+        diff.extend(
+            before[i..]
+                .iter()
+                .map(|(_, node)| BlockDiff::Removed(node.clone())),
+        );
+        diff.extend(
+            after[j..]
+                .iter()
+                .map(|(_, node)| BlockDiff::Added(node.clone())),
+        );
+        diff
+    }
+}
+
+/// Blocks of a content in document order as `(id, block without its children)`
+fn flatten_blocks(content: &Value) -> Vec<(String, Value)> {
+    fn collect(nodes: &[Value], blocks: &mut Vec<(String, Value)>) {
+        for node in nodes {
+            let mut block = node.clone();
+            let children = block
+                .as_object_mut()
+                .and_then(|object| object.remove("children"));
+
+            let id = node["id"].as_str().unwrap_or_default().to_string();
+            blocks.push((id, block));
+
+            if let Some(Value::Array(children)) = children {
+                collect(&children, blocks);
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    if let Some(nodes) = content.as_array() {
+        collect(nodes, &mut blocks);
+    }
+
+    blocks
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, from_value};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum DbContext {
+    Local(LocalDatabase),
+    Remote(RemoteDatabase),
+    #[serde(other)]
+    Unknown,
+}
+
+impl DbContext {
+    pub fn parse(value: Value) -> DbContext {
+        from_value::<DbContext>(value).unwrap_or(DbContext::Unknown)
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            DbContext::Local(database) => Some(&database.name),
+            DbContext::Remote(database) => Some(&database.name),
+            DbContext::Unknown => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LocalDatabase {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RemoteDatabase {
+    pub name: String,
+    pub url: String,
+    /// Work on a local copy synced with the database, usable while it cannot be reached
+    #[serde(default)]
+    pub offline: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeMode {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+impl ThemeMode {
+    pub fn next(&self) -> Self {
+        match self {
+            ThemeMode::Light => ThemeMode::Dark,
+            ThemeMode::Dark => ThemeMode::System,
+            ThemeMode::System => ThemeMode::Light,
+        }
+    }
+
+    pub fn icon_name(&self) -> &'static str {
+        match self {
+            ThemeMode::Light => "sun",
+            ThemeMode::Dark => "moon",
+            ThemeMode::System => "monitor",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Settings {
+    pub contexts: Vec<DbContext>,
+    /// Name of the context the app connects to at startup
+    #[serde(default)]
+    pub active_context: Option<String>,
+    #[serde(default)]
+    pub theme: ThemeSettings,
+    #[serde(default)]
+    pub trash: TrashSettings,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ThemeSettings {
+    #[serde(default = "default_light_theme")]
+    pub light: String,
+    #[serde(default = "default_dark_theme")]
+    pub dark: String,
+    #[serde(default)]
+    pub mode: ThemeMode,
+}
+
+fn default_light_theme() -> String {
+    "Default Light".to_string()
+}
+
+fn default_dark_theme() -> String {
+    "Default Dark".to_string()
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            light: default_light_theme(),
+            dark: default_dark_theme(),
+            mode: ThemeMode::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TrashSettings {
+    /// Days a deleted document stays in the trash before being purged, `0` keeps it forever
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u32,
+}
+
+fn default_retention_days() -> u32 {
+    30
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        Self {
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+impl Settings {
+    /// The database context the app connects to: the one named by `active_context`,
+    /// or the first known entry of `contexts`.
+    pub fn active_context(&self) -> Option<&DbContext> {
+        let selected = self.active_context.as_deref().and_then(|name| {
+            self.contexts
+                .iter()
+                .find(|context| context.name() == Some(name))
+        });
+
+        selected.or_else(|| {
+            self.contexts
+                .iter()
+                .find(|context| !matches!(context, DbContext::Unknown))
+        })
+    }
+}
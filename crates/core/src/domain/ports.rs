@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::future::Future;
+
+use crate::domain::database::{document::DocumentModel, search::SearchResultModel};
+
+pub trait DocumentRepositoryPort: Send + Sync {
+    type ListFuture<'a>: Future<Output = Result<Vec<DocumentModel>>> + Send + 'a
+    where
+        Self: 'a;
+
+    /// Future renvoyé par `get`.
+    type GetFuture<'a>: Future<Output = Result<Option<DocumentModel>>> + Send + 'a
+    where
+        Self: 'a;
+
+    type SaveFuture<'a>: Future<Output = Result<()>> + Send + 'a
+    where
+        Self: 'a;
+
+    /// Future returned by `insert`, resolving to the id of the new document.
+    type InsertFuture<'a>: Future<Output = Result<i32>> + Send + 'a
+    where
+        Self: 'a;
+
+    type DeleteFuture<'a>: Future<Output = Result<()>> + Send + 'a
+    where
+        Self: 'a;
+
+    /// Future returned by `search`, best matches first.
+    type SearchFuture<'a>: Future<Output = Result<Vec<SearchResultModel>>> + Send + 'a
+    where
+        Self: 'a;
+
+    /// Future returned by `move_to`, placing a document under a parent (`None` for the root).
+    type MoveFuture<'a>: Future<Output = Result<()>> + Send + 'a
+    where
+        Self: 'a;
+
+    /// Future returned by `restore`, resolving to the ids of the documents out of the trash.
+    type RestoreFuture<'a>: Future<Output = Result<Vec<i32>>> + Send + 'a
+    where
+        Self: 'a;
+
+    fn list<'a>(&'a self) -> Self::ListFuture<'a>;
+    fn get<'a>(&'a self, id: i32) -> Self::GetFuture<'a>;
+    fn save<'a>(&'a self, document: DocumentModel) -> Self::SaveFuture<'a>;
+    fn insert<'a>(&'a self, document: DocumentModel) -> Self::InsertFuture<'a>;
+    fn delete<'a>(&'a self, id: i32) -> Self::DeleteFuture<'a>;
+    fn search<'a>(&'a self, text: String) -> Self::SearchFuture<'a>;
+    fn move_to<'a>(
+        &'a self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Self::MoveFuture<'a>;
+    fn restore<'a>(&'a self, id: i32) -> Self::RestoreFuture<'a>;
+}
@@ -0,0 +1,146 @@
+use std::path::PathBuf;
+
+use anyhow::{Context, Error};
+use serde_json::{from_str, to_string};
+use tokio::fs::{create_dir_all, read_to_string, write};
+
+use crate::{
+    domain::entities::settings::{DbContext, Settings},
+    infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+
+/// Configuration directory of Remindr, holding the settings and the local databases
+#[derive(Clone, Default)]
+pub struct Remindr;
+
+impl Remindr {
+    pub fn new() -> Self {
+        Remindr {}
+    }
+
+    pub fn get_config_dir(&self, project_name: &str) -> Result<PathBuf, Error> {
+        let config_path = if cfg!(target_os = "linux") || cfg!(target_os = "macos") {
+            let home = dirs::home_dir().context("Impossible de trouver le home directory")?;
+            home.join(".config").join(project_name)
+        } else {
+            let base = dirs::config_dir()
+                .context("Impossible de récupérer le dossier de config Windows")?;
+            base.join(project_name)
+        };
+
+        Ok(config_path)
+    }
+
+    /// Create the configuration directory, along with default settings when there are none
+    pub async fn init(&self) -> Result<(), Error> {
+        let config_path = self.get_config_dir("remindr")?;
+        create_dir_all(&config_path)
+            .await
+            .with_context(|| format!("Failed to create {:?}", config_path))?;
+
+        let settings_file = config_path.join("settings.json");
+        if !settings_file.exists() {
+            let settings = Settings::default();
+
+            write(&settings_file, to_string(&settings).unwrap())
+                .await
+                .with_context(|| {
+                    format!("Failed to write default settings to {:?}", settings_file)
+                })?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn init_default_database(&self) -> Result<PathBuf, Error> {
+        let config_path = self.get_config_dir("remindr")?;
+        create_dir_all(&config_path)
+            .await
+            .with_context(|| format!("Failed to create {:?}", config_path))?;
+        let database_path = config_path.join("database.sqlite");
+
+        if !database_path.exists() {
+            write(&database_path, "")
+                .await
+                .with_context(|| format!("Failed to create {:?}", database_path))?;
+        }
+
+        Ok(database_path)
+    }
+
+    /// Local copy of the remote workspace `name`, created empty on first use
+    pub async fn init_replica_database(&self, name: &str) -> Result<PathBuf, Error> {
+        let replicas_path = self.get_config_dir("remindr")?.join("replicas");
+        create_dir_all(&replicas_path)
+            .await
+            .with_context(|| format!("Failed to create {:?}", replicas_path))?;
+
+        let file_name: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        let database_path = replicas_path.join(format!("{}.sqlite", file_name));
+
+        if !database_path.exists() {
+            write(&database_path, "")
+                .await
+                .with_context(|| format!("Failed to create {:?}", database_path))?;
+        }
+
+        Ok(database_path)
+    }
+
+    /// Connects to the database described by `context`, falling back to the
+    /// default local database when no context is configured.
+    pub async fn connect_database(
+        &self,
+        context: Option<&DbContext>,
+    ) -> Result<AnyDocumentRepository, Error> {
+        match context {
+            Some(DbContext::Local(database)) => {
+                AnyDocumentRepository::connect_sqlite(&PathBuf::from(&database.path))
+                    .await
+                    .with_context(|| format!("Failed to open database \"{}\"", database.name))
+            }
+            Some(DbContext::Remote(database)) if database.offline => {
+                let replica_path = self.init_replica_database(&database.name).await?;
+                AnyDocumentRepository::connect_replica(&replica_path, &database.url)
+                    .await
+                    .with_context(|| format!("Failed to open the copy of \"{}\"", database.name))
+            }
+            Some(DbContext::Remote(database)) => {
+                AnyDocumentRepository::connect_postgres(&database.url)
+                    .await
+                    .with_context(|| format!("Failed to connect to \"{}\"", database.name))
+            }
+            _ => {
+                let database_path = self.init_default_database().await?;
+                AnyDocumentRepository::connect_sqlite(&database_path).await
+            }
+        }
+    }
+
+    pub async fn save_settings(&self, settings: &Settings) -> Result<(), Error> {
+        let config_path = self.get_config_dir("remindr")?;
+        let settings_file = config_path.join("settings.json");
+
+        write(&settings_file, to_string(settings)?)
+            .await
+            .with_context(|| format!("Failed to write settings to {:?}", settings_file))
+    }
+
+    pub async fn load_settings(&self) -> Result<Settings, Error> {
+        let config_path = self.get_config_dir("remindr")?;
+        let settings_file = config_path.join("settings.json");
+
+        if !settings_file.exists() {
+            self.init().await?;
+        }
+
+        let settings = read_to_string(&settings_file)
+            .await
+            .with_context(|| format!("Failed to read settings from {:?}", settings_file))?;
+
+        from_str::<Settings>(&settings).context("Failed to parse settings")
+    }
+}
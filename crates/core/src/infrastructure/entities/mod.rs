@@ -0,0 +1,133 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::prelude::FromRow;
+
+use crate::domain::database::{
+    attachment::AttachmentModel,
+    document::DocumentModel,
+    revision::{DocumentRevisionModel, TombstoneModel},
+    search::SearchResultModel,
+    version::DocumentVersionModel,
+};
+
+#[derive(Debug, FromRow)]
+pub struct DocumentEntity {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub position: i32,
+    pub title: String,
+    pub content: Value,
+}
+
+impl From<DocumentEntity> for DocumentModel {
+    fn from(entity: DocumentEntity) -> Self {
+        DocumentModel {
+            id: entity.id,
+            parent_id: entity.parent_id,
+            position: entity.position,
+            title: entity.title,
+            content: entity.content,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct SearchResultEntity {
+    pub document_id: i32,
+    pub node_id: Option<String>,
+    pub title: String,
+    pub snippet: String,
+}
+
+impl From<SearchResultEntity> for SearchResultModel {
+    fn from(entity: SearchResultEntity) -> Self {
+        SearchResultModel {
+            document_id: entity.document_id,
+            node_id: entity
+                .node_id
+                .and_then(|node_id| uuid::Uuid::parse_str(&node_id).ok()),
+            title: entity.title,
+            snippet: entity.snippet,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DocumentVersionEntity {
+    pub id: i32,
+    pub document_id: i32,
+    pub title: String,
+    pub content: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<DocumentVersionEntity> for DocumentVersionModel {
+    fn from(entity: DocumentVersionEntity) -> Self {
+        DocumentVersionModel {
+            id: entity.id,
+            document_id: entity.document_id,
+            title: entity.title,
+            content: entity.content,
+            created_at: entity.created_at,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct DocumentRevisionEntity {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub position: i32,
+    pub title: String,
+    pub content: Value,
+    pub deleted: bool,
+    pub revision: i64,
+}
+
+impl From<DocumentRevisionEntity> for DocumentRevisionModel {
+    fn from(entity: DocumentRevisionEntity) -> Self {
+        DocumentRevisionModel {
+            document: DocumentModel {
+                id: entity.id,
+                parent_id: entity.parent_id,
+                position: entity.position,
+                title: entity.title,
+                content: entity.content,
+            },
+            deleted: entity.deleted,
+            revision: entity.revision,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct TombstoneEntity {
+    pub document_id: i32,
+    pub revision: i64,
+}
+
+impl From<TombstoneEntity> for TombstoneModel {
+    fn from(entity: TombstoneEntity) -> Self {
+        TombstoneModel {
+            document_id: entity.document_id,
+            revision: entity.revision,
+        }
+    }
+}
+
+#[derive(Debug, FromRow)]
+pub struct AttachmentEntity {
+    pub hash: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl From<AttachmentEntity> for AttachmentModel {
+    fn from(entity: AttachmentEntity) -> Self {
+        AttachmentModel {
+            hash: entity.hash,
+            mime_type: entity.mime_type,
+            data: entity.data,
+        }
+    }
+}
@@ -0,0 +1,689 @@
+use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde_json::{Value, json};
+
+use crate::{Utils, domain::database::document::DocumentModel};
+
+const BOLD: &str = "Bold";
+const ITALIC: &str = "Italic";
+const UNDERLINE: &str = "Underline";
+const STRIKETHROUGH: &str = "Strikethrough";
+const CODE: &str = "Code";
+const LINK: &str = "Link";
+
+/// Directory, next to the exported file, the attachments of a document are written to
+const ATTACHMENTS_DIR: &str = "attachments";
+
+/// Converts the node JSON stored in `DocumentModel.content` from and to CommonMark.
+pub struct MarkdownConverter;
+
+impl MarkdownConverter {
+    /// Render a whole document as Markdown, the title becoming the top-level heading.
+    pub fn export(document: &DocumentModel) -> String {
+        let mut blocks = vec![format!("# {}", escape_text(&document.title))];
+
+        if let Some(nodes) = document.content.as_array() {
+            Self::export_nodes(nodes, 0, &mut blocks);
+        }
+
+        let mut markdown = blocks.join("\n\n");
+        markdown.push('\n');
+        markdown
+    }
+
+    /// Name of the file a document is exported to, without the characters paths cannot hold
+    pub fn file_name(title: &str) -> String {
+        let name = path_safe(title);
+
+        if name.is_empty() {
+            "Untitled.md".to_string()
+        } else {
+            format!("{}.md", name)
+        }
+    }
+
+    /// Attachments the Markdown of `document` points at, as `(hash, path)`, the path being
+    /// relative to the exported file the attachments have to be written next to
+    pub fn attachments(document: &DocumentModel) -> Vec<(String, String)> {
+        fn collect(nodes: &[Value], attachments: &mut Vec<(String, String)>) {
+            for node in nodes {
+                if let Some(path) = attachment_path(node) {
+                    let hash = node["metadata"]["hash"].as_str().unwrap_or_default();
+                    attachments.push((hash.to_string(), path));
+                }
+
+                if let Some(children) = node["children"].as_array() {
+                    collect(children, attachments);
+                }
+            }
+        }
+
+        let mut attachments = Vec::new();
+        if let Some(nodes) = document.content.as_array() {
+            collect(nodes, &mut attachments);
+        }
+
+        attachments.sort();
+        attachments.dedup();
+        attachments
+    }
+
+    /// Markdown has no nested paragraphs, so children are written after their parent.
+    /// List items are the exception: their children are indented under the item.
+    fn export_nodes(nodes: &[Value], indent: usize, blocks: &mut Vec<String>) {
+        let mut previous_was_item = false;
+        let mut number = 0;
+
+        for node in nodes {
+            let node_type = node["type"].as_str().unwrap_or_default();
+            number = if node_type == "numbered_list" {
+                number + 1
+            } else {
+                0
+            };
+
+            let Some(block) = Self::export_node(node, number) else {
+                continue;
+            };
+            let block = indent_block(&block, indent);
+            let is_item = list_marker(node, number).is_some();
+
+            // Consecutive items are kept in the same block so the list stays tight
+            match blocks.last_mut() {
+                Some(last) if is_item && previous_was_item => {
+                    last.push('\n');
+                    last.push_str(&block);
+                }
+                _ => blocks.push(block),
+            }
+            previous_was_item = is_item;
+
+            if let Some(children) = node["children"].as_array() {
+                let child_indent = list_marker(node, number)
+                    .map(|marker| indent + marker.len())
+                    .unwrap_or(indent);
+                Self::export_nodes(children, child_indent, blocks);
+                previous_was_item = false;
+            }
+        }
+    }
+
+    fn export_node(node: &Value, number: usize) -> Option<String> {
+        let metadata = &node["metadata"];
+        let content = metadata["content"].as_str().unwrap_or_default();
+
+        match node["type"].as_str()? {
+            "text" => Some(render_inline(content, &read_spans(&metadata["spans"]))),
+            "heading" => {
+                let level = metadata["level"].as_u64().unwrap_or(1).clamp(1, 6) as usize;
+                Some(format!("{} {}", "#".repeat(level), escape_text(content)))
+            }
+            "divider" => Some("---".to_string()),
+            "code" => {
+                let language = match metadata["language"].as_str() {
+                    Some("text") | None => "",
+                    Some(language) => language,
+                };
+                let fence = code_fence(content);
+                Some(format!("{fence}{language}\n{content}\n{fence}"))
+            }
+            "bulleted_list" | "numbered_list" | "todo" => {
+                let marker = list_marker(node, number)?;
+                let checkbox = match node["type"].as_str()? {
+                    "todo" if metadata["checked"].as_bool().unwrap_or(false) => "[x] ",
+                    "todo" => "[ ] ",
+                    _ => "",
+                };
+                Some(format!(
+                    "{}{}{}",
+                    marker,
+                    checkbox,
+                    render_inline(content, &read_spans(&metadata["spans"]))
+                ))
+            }
+            "image" => {
+                let name = metadata["name"].as_str().unwrap_or_default();
+                Some(format!(
+                    "![{}]({})",
+                    escape_text(name),
+                    escape_href(&attachment_path(node)?)
+                ))
+            }
+            "file" => {
+                let name = metadata["name"].as_str().unwrap_or_default();
+                Some(format!(
+                    "[{}]({})",
+                    escape_text(name),
+                    escape_href(&attachment_path(node)?)
+                ))
+            }
+            // Blocks Markdown has no equivalent for keep their text, or leave a trace
+            _ if !content.is_empty() => {
+                Some(render_inline(content, &read_spans(&metadata["spans"])))
+            }
+            node_type => Some(format!("<!-- {} block -->", node_type)),
+        }
+    }
+
+    /// Parse a Markdown file into a document.
+    /// A leading `#` heading is used as the title, `fallback_title` otherwise.
+    pub fn import(markdown: &str, fallback_title: &str) -> DocumentModel {
+        let mut builder = NodeBuilder::default();
+
+        let options = Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        for event in Parser::new_ext(markdown, options) {
+            builder.push(event);
+        }
+        builder.flush();
+
+        let mut nodes = nest_nodes(builder.nodes);
+        let title = match nodes.first() {
+            Some(node) if node["type"] == "heading" && node["metadata"]["level"] == 1 => {
+                let title = node["metadata"]["content"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string();
+                nodes.remove(0);
+                title
+            }
+            _ => fallback_title.to_string(),
+        };
+
+        DocumentModel {
+            id: 0,
+            parent_id: None,
+            position: 0,
+            title,
+            content: Value::from(nodes),
+        }
+    }
+}
+
+/// Characters paths cannot hold replaced, without the dots hiding or losing the file
+fn path_safe(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            ch if ch.is_control() => ' ',
+            ch => ch,
+        })
+        .collect();
+    // Leading dots would hide the file, trailing ones are dropped on Windows
+    name.trim().trim_matches('.').trim().to_string()
+}
+
+/// Where the attachment of an image or file block is written, relative to the exported file
+fn attachment_path(node: &Value) -> Option<String> {
+    if !matches!(node["type"].as_str()?, "image" | "file") {
+        return None;
+    }
+
+    let metadata = &node["metadata"];
+    let hash = metadata["hash"].as_str()?;
+    // The start of the hash keeps files of the same name apart
+    let prefix = hash.get(..12).unwrap_or(hash);
+    let name = path_safe(metadata["name"].as_str().unwrap_or_default());
+
+    Some(if name.is_empty() {
+        format!("{}/{}", ATTACHMENTS_DIR, prefix)
+    } else {
+        format!("{}/{}-{}", ATTACHMENTS_DIR, prefix, name)
+    })
+}
+
+/// Marker written before a list item, `None` for any other block
+fn list_marker(node: &Value, number: usize) -> Option<String> {
+    match node["type"].as_str()? {
+        "bulleted_list" | "todo" => Some("- ".to_string()),
+        "numbered_list" => Some(format!("{}. ", number.max(1))),
+        _ => None,
+    }
+}
+
+/// A fence longer than any run of backticks inside the snippet
+fn code_fence(content: &str) -> String {
+    let longest = content
+        .split(|ch| ch != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn indent_block(block: &str, indent: usize) -> String {
+    if indent == 0 {
+        return block.to_string();
+    }
+
+    let padding = " ".repeat(indent);
+    block
+        .lines()
+        .map(|line| format!("{}{}", padding, line))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Turn the flat `(depth, node)` list built while parsing into nodes nested under `children`
+fn nest_nodes(flat: Vec<(usize, Value)>) -> Vec<Value> {
+    fn attach(node: Value, ancestors: &mut [Value], roots: &mut Vec<Value>) {
+        match ancestors.last_mut() {
+            Some(parent) => {
+                if !parent["children"].is_array() {
+                    parent["children"] = json!([]);
+                }
+                if let Some(children) = parent["children"].as_array_mut() {
+                    children.push(node);
+                }
+            }
+            None => roots.push(node),
+        }
+    }
+
+    let mut roots = Vec::new();
+    let mut ancestors: Vec<Value> = Vec::new();
+
+    for (depth, node) in flat {
+        // A node can never be more than one level deeper than the previous one
+        let depth = depth.min(ancestors.len());
+        while ancestors.len() > depth {
+            let closed = ancestors.pop().unwrap();
+            attach(closed, &mut ancestors, &mut roots);
+        }
+        ancestors.push(node);
+    }
+
+    while let Some(closed) = ancestors.pop() {
+        attach(closed, &mut ancestors, &mut roots);
+    }
+
+    roots
+}
+
+struct Span {
+    start: usize,
+    end: usize,
+    style: String,
+    /// Target of a `Link` span
+    href: Option<String>,
+}
+
+fn read_spans(value: &Value) -> Vec<Span> {
+    value
+        .as_array()
+        .map(|spans| {
+            spans
+                .iter()
+                .filter_map(|span| {
+                    let (style, href) = match &span["style"] {
+                        Value::String(style) => (style.clone(), None),
+                        style => (
+                            LINK.to_string(),
+                            Some(style[LINK]["href"].as_str()?.to_string()),
+                        ),
+                    };
+                    Some(Span {
+                        start: span["start"].as_u64()? as usize,
+                        end: span["end"].as_u64()? as usize,
+                        style,
+                        href,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Markers opening and closing a style, `href` being the target of a link
+fn marker(style: &str, href: &str) -> (String, String) {
+    let (open, close) = match style {
+        BOLD => ("**", "**"),
+        ITALIC => ("_", "_"),
+        UNDERLINE => ("<u>", "</u>"),
+        STRIKETHROUGH => ("~~", "~~"),
+        LINK => return ("[".to_string(), format!("]({})", escape_href(href))),
+        _ => ("", ""),
+    };
+    (open.to_string(), close.to_string())
+}
+
+/// Render text with its spans as inline Markdown.
+///
+/// Formatting is opened and closed at every span boundary so that overlapping
+/// spans always produce properly nested markers.
+fn render_inline(content: &str, spans: &[Span]) -> String {
+    let spans: Vec<&Span> = spans
+        .iter()
+        .filter(|span| {
+            span.start < span.end
+                && span.end <= content.len()
+                && content.is_char_boundary(span.start)
+                && content.is_char_boundary(span.end)
+        })
+        .collect();
+
+    let mut boundaries: Vec<usize> = vec![0, content.len()];
+    for span in &spans {
+        boundaries.push(span.start);
+        boundaries.push(span.end);
+    }
+    boundaries.sort();
+    boundaries.dedup();
+
+    let mut output = String::new();
+    // Open styles along with their link target, empty for the other styles
+    let mut open: Vec<(&str, &str)> = Vec::new();
+
+    for window in boundaries.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let segment = &content[start..end];
+
+        let mut active: Vec<(&str, &str)> = Vec::new();
+        for style in [LINK, BOLD, ITALIC, UNDERLINE, STRIKETHROUGH, CODE] {
+            if let Some(span) = spans
+                .iter()
+                .find(|s| s.style == style && s.start <= start && s.end >= end)
+            {
+                active.push((style, span.href.as_deref().unwrap_or_default()));
+            }
+        }
+
+        let is_code = active.iter().any(|(style, _)| *style == CODE);
+        // Emphasis cannot open on blank text, which only keeps the styles already open
+        if !is_code && segment.trim().is_empty() {
+            active.retain(|style| open.contains(style));
+        }
+
+        // Close every open style that is no longer active, along with the ones opened after it
+        if let Some(index) = open.iter().position(|style| !active.contains(style)) {
+            // Markers cannot close right after whitespace, which is moved after them
+            let whitespace = output.split_off(output.trim_end().len());
+            for (style, href) in open.drain(index..).rev() {
+                output.push_str(&marker(style, href).1);
+            }
+            output.push_str(&whitespace);
+        }
+
+        // Nor open right before it, so leading whitespace is written first
+        let mut segment = segment;
+        let opens = active
+            .iter()
+            .any(|style| style.0 != CODE && !open.contains(style));
+        if opens && !is_code {
+            let trimmed = segment.trim_start();
+            output.push_str(&segment[..segment.len() - trimmed.len()]);
+            segment = trimmed;
+        }
+
+        for style in &active {
+            if style.0 != CODE && !open.contains(style) {
+                output.push_str(&marker(style.0, style.1).0);
+                open.push(*style);
+            }
+        }
+
+        if is_code {
+            output.push_str(&render_code(segment));
+        } else {
+            output.push_str(&escape_inline(segment, start == 0));
+        }
+    }
+
+    let whitespace = output.split_off(output.trim_end().len());
+    for (style, href) in open.drain(..).rev() {
+        output.push_str(&marker(style, href).1);
+    }
+    output.push_str(&whitespace);
+
+    output
+}
+
+fn render_code(segment: &str) -> String {
+    if segment.contains('`') {
+        format!("`` {} ``", segment)
+    } else {
+        format!("`{}`", segment)
+    }
+}
+
+fn escape_inline(text: &str, at_line_start: bool) -> String {
+    // Digits followed by `.` or `)` would start an ordered list
+    let digits = text.len()
+        - text
+            .trim_start_matches(|ch: char| ch.is_ascii_digit())
+            .len();
+    let ordered_marker = at_line_start
+        && (1..=9).contains(&digits)
+        && matches!(text[digits..].chars().next(), Some('.' | ')'));
+
+    let mut escaped = String::with_capacity(text.len());
+    for (index, ch) in text.char_indices() {
+        let block_marker = at_line_start
+            && (index == 0 && matches!(ch, '#' | '-' | '+' | '>')
+                || ordered_marker && index == digits);
+        if block_marker || matches!(ch, '\\' | '*' | '_' | '`' | '~' | '[' | ']' | '<' | '>') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Link targets with spaces or parentheses are kept whole between angle brackets
+fn escape_href(href: &str) -> String {
+    if href.contains([' ', '(', ')', '<', '>']) {
+        format!("<{}>", href.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        href.to_string()
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    escape_inline(text, false)
+}
+
+/// Accumulates pulldown-cmark events into Remindr nodes.
+#[derive(Default)]
+struct NodeBuilder {
+    nodes: Vec<(usize, Value)>,
+    content: String,
+    spans: Vec<Value>,
+    open_styles: Vec<(&'static str, usize)>,
+    /// Target and start of the link being read
+    open_link: Option<(String, usize)>,
+    heading_level: Option<u32>,
+    in_block: bool,
+    /// One entry per open list, `true` when the list is ordered
+    lists: Vec<bool>,
+    /// List item type of the block being built, with its checkbox state
+    item: Option<(&'static str, bool)>,
+    /// Language of the fenced code block being built
+    code_language: Option<String>,
+}
+
+impl NodeBuilder {
+    fn push(&mut self, event: Event) {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                self.flush();
+                self.heading_level = Some(heading_level(level));
+                self.in_block = true;
+            }
+            Event::End(TagEnd::Heading(_)) => self.flush(),
+            Event::Start(Tag::Paragraph) => {
+                self.flush();
+                self.in_block = true;
+            }
+            Event::End(TagEnd::Paragraph) => self.flush(),
+            Event::Start(Tag::Emphasis) => self.open_style(ITALIC),
+            Event::End(TagEnd::Emphasis) => self.close_style(ITALIC),
+            Event::Start(Tag::Strong) => self.open_style(BOLD),
+            Event::End(TagEnd::Strong) => self.close_style(BOLD),
+            Event::Start(Tag::Strikethrough) => self.open_style(STRIKETHROUGH),
+            Event::End(TagEnd::Strikethrough) => self.close_style(STRIKETHROUGH),
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                self.open_link = Some((dest_url.to_string(), self.content.len()));
+            }
+            Event::End(TagEnd::Link) => {
+                if let Some((href, start)) = self.open_link.take() {
+                    let end = self.content.len();
+                    if start < end {
+                        self.spans.push(json!({
+                            "start": start,
+                            "end": end,
+                            "style": { "Link": { "href": href } },
+                        }));
+                    }
+                }
+            }
+            Event::InlineHtml(html) => match html.trim() {
+                "<u>" => self.open_style(UNDERLINE),
+                "</u>" => self.close_style(UNDERLINE),
+                other => self.push_text(other),
+            },
+            Event::Code(code) => {
+                let start = self.content.len();
+                self.push_text(&code);
+                self.push_span(start, self.content.len(), CODE);
+            }
+            Event::Text(text) => self.push_text(&text),
+            Event::SoftBreak | Event::HardBreak => self.push_text(" "),
+            Event::Rule => {
+                self.flush();
+                self.nodes.push((
+                    self.lists.len(),
+                    json!({
+                        "id": Utils::generate_uuid(),
+                        "type": "divider",
+                    }),
+                ));
+            }
+            Event::Start(Tag::List(start)) => {
+                self.flush();
+                self.lists.push(start.is_some());
+            }
+            Event::End(TagEnd::List(_)) => {
+                self.flush();
+                self.lists.pop();
+            }
+            Event::Start(Tag::Item) => {
+                self.flush();
+                let node_type = match self.lists.last() {
+                    Some(true) => "numbered_list",
+                    _ => "bulleted_list",
+                };
+                self.item = Some((node_type, false));
+            }
+            Event::TaskListMarker(checked) => self.item = Some(("todo", checked)),
+            Event::End(TagEnd::Item) => {
+                // Empty items still produce a node
+                if self.item.is_some() {
+                    self.in_block = true;
+                }
+                self.flush();
+            }
+            Event::Start(Tag::CodeBlock(kind)) => {
+                self.flush();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_language = Some(language);
+                self.in_block = true;
+            }
+            Event::End(TagEnd::CodeBlock) => self.flush(),
+            _ => {}
+        }
+    }
+
+    fn push_text(&mut self, text: &str) {
+        self.in_block = true;
+        self.content.push_str(text);
+    }
+
+    fn open_style(&mut self, style: &'static str) {
+        self.open_styles.push((style, self.content.len()));
+    }
+
+    fn close_style(&mut self, style: &'static str) {
+        if let Some(index) = self.open_styles.iter().rposition(|(s, _)| *s == style) {
+            let (_, start) = self.open_styles.remove(index);
+            self.push_span(start, self.content.len(), style);
+        }
+    }
+
+    fn push_span(&mut self, start: usize, end: usize, style: &str) {
+        if start < end {
+            self.spans
+                .push(json!({ "start": start, "end": end, "style": style }));
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.in_block {
+            return;
+        }
+
+        let content = std::mem::take(&mut self.content);
+        let spans = std::mem::take(&mut self.spans);
+        self.open_styles.clear();
+        self.open_link = None;
+        self.in_block = false;
+
+        // Items sit at their list depth, other blocks inside a list are children of the item
+        let item = self.item.take();
+        let depth = match item {
+            Some(_) => self.lists.len().saturating_sub(1),
+            None => self.lists.len(),
+        };
+
+        if let Some(language) = self.code_language.take() {
+            let language = if language.is_empty() {
+                "text".to_string()
+            } else {
+                language.to_lowercase()
+            };
+            let code = json!({
+                "id": Utils::generate_uuid(),
+                "type": "code",
+                "metadata": { "content": content.trim_end_matches('\n'), "language": language },
+            });
+            self.nodes.push((depth, code));
+            return;
+        }
+
+        let node = match (item, self.heading_level.take()) {
+            (Some((node_type, checked)), _) => json!({
+                "id": Utils::generate_uuid(),
+                "type": node_type,
+                "metadata": { "content": content, "spans": spans, "checked": checked },
+            }),
+            (None, Some(level)) => json!({
+                "id": Utils::generate_uuid(),
+                "type": "heading",
+                "metadata": { "content": content.trim(), "level": level },
+            }),
+            (None, None) => json!({
+                "id": Utils::generate_uuid(),
+                "type": "text",
+                "metadata": { "content": content, "spans": spans },
+            }),
+        };
+
+        self.nodes.push((depth, node));
+    }
+}
+
+fn heading_level(level: HeadingLevel) -> u32 {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
@@ -0,0 +1,5 @@
+pub mod config;
+pub mod entities;
+pub mod markdown;
+pub mod repositories;
+pub mod sync;
@@ -0,0 +1,341 @@
+use std::path::Path;
+
+use anyhow::Error;
+use sqlx::{
+    PgPool, SqlitePool, migrate,
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+};
+use tokio::sync::broadcast;
+
+use crate::{
+    domain::{
+        database::{
+            attachment::AttachmentModel, change::DocumentChange, document::DocumentModel,
+            search::SearchResultModel, version::DocumentVersionModel,
+        },
+        ports::DocumentRepositoryPort,
+    },
+    infrastructure::{
+        repositories::{
+            RepositoryFuture, document_repository::DocumentRepository,
+            postgres_document_repository::PostgresDocumentRepository,
+        },
+        sync::SyncEngine,
+    },
+};
+
+/// Document repository backed by whichever database the active context points to.
+#[derive(Clone)]
+pub enum AnyDocumentRepository {
+    Sqlite(DocumentRepository),
+    Postgres(PostgresDocumentRepository),
+}
+
+impl AnyDocumentRepository {
+    /// Opens (and creates if needed) a local SQLite database and runs its migrations.
+    pub async fn connect_sqlite(path: &Path) -> Result<Self, Error> {
+        Ok(Self::Sqlite(Self::open_sqlite(path).await?))
+    }
+
+    /// Opens an offline copy of the Postgres database at `url`, kept at `path` and synced
+    /// whenever the remote database can be reached.
+    pub async fn connect_replica(path: &Path, url: &str) -> Result<Self, Error> {
+        let repository = Self::open_sqlite(path).await?;
+        // The engine keeps a copy without itself, so that dropping the last one stops it
+        let sync = SyncEngine::start(repository.clone(), url).await?;
+
+        Ok(Self::Sqlite(repository.with_sync(sync)))
+    }
+
+    async fn open_sqlite(path: &Path) -> Result<DocumentRepository, Error> {
+        let options = SqliteConnectOptions::new()
+            .filename(path)
+            .create_if_missing(true);
+        let pool: SqlitePool = SqlitePoolOptions::new().connect_with(options).await?;
+
+        migrate!("./migrations")
+            .run(&pool)
+            .await
+            .map_err(|err| Error::msg(err.to_string()))?;
+
+        Ok(DocumentRepository::new(pool))
+    }
+
+    /// Connects to a shared Postgres database and runs its migrations.
+    pub async fn connect_postgres(url: &str) -> Result<Self, Error> {
+        let pool = PgPool::connect(url).await?;
+
+        migrate!("./migrations/postgres")
+            .run(&pool)
+            .await
+            .map_err(|err| Error::msg(err.to_string()))?;
+
+        Ok(Self::Postgres(PostgresDocumentRepository::new(pool)))
+    }
+
+    /// Sync of the offline copy, when the context keeps one
+    pub fn sync(&self) -> Option<&SyncEngine> {
+        match self {
+            Self::Sqlite(repository) => repository.sync(),
+            Self::Postgres(_) => None,
+        }
+    }
+
+    /// Stream of the changes made to the documents, see [`DocumentChange`]
+    pub fn subscribe(&self) -> broadcast::Receiver<DocumentChange> {
+        match self {
+            Self::Sqlite(repository) => repository.subscribe(),
+            Self::Postgres(repository) => repository.subscribe(),
+        }
+    }
+
+    pub async fn get_documents(&self) -> Result<Vec<DocumentModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_documents().await,
+            Self::Postgres(repository) => repository.get_documents().await,
+        }
+    }
+
+    /// `limit` documents from `offset`, along with the number of documents outside of the trash
+    pub async fn get_documents_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<DocumentModel>, i64), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_documents_page(offset, limit).await,
+            Self::Postgres(repository) => repository.get_documents_page(offset, limit).await,
+        }
+    }
+
+    pub async fn get_document_by_id(&self, id: i32) -> Result<DocumentModel, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_document_by_id(id).await,
+            Self::Postgres(repository) => repository.get_document_by_id(id).await,
+        }
+    }
+
+    /// The document with this id, unless it is missing or in the trash
+    pub async fn find_document(&self, id: i32) -> Result<Option<DocumentModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.find_document(id).await,
+            Self::Postgres(repository) => repository.find_document(id).await,
+        }
+    }
+
+    pub async fn insert_document(&self, document: DocumentModel) -> Result<i32, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.insert_document(document).await,
+            Self::Postgres(repository) => repository.insert_document(document).await,
+        }
+    }
+
+    pub async fn update_document(&self, document: DocumentModel) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.update_document(document).await,
+            Self::Postgres(repository) => repository.update_document(document).await,
+        }
+    }
+
+    pub async fn delete_document(&self, id: i32) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.delete_document(id).await,
+            Self::Postgres(repository) => repository.delete_document(id).await,
+        }
+    }
+
+    /// Documents linking to `id`, by title
+    pub async fn get_backlinks(&self, id: i32) -> Result<Vec<DocumentModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_backlinks(id).await,
+            Self::Postgres(repository) => repository.get_backlinks(id).await,
+        }
+    }
+
+    pub async fn get_trashed_documents(&self) -> Result<Vec<DocumentModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_trashed_documents().await,
+            Self::Postgres(repository) => repository.get_trashed_documents().await,
+        }
+    }
+
+    pub async fn restore_document(&self, id: i32) -> Result<Vec<i32>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.restore_document(id).await,
+            Self::Postgres(repository) => repository.restore_document(id).await,
+        }
+    }
+
+    pub async fn delete_document_forever(&self, id: i32) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.delete_document_forever(id).await,
+            Self::Postgres(repository) => repository.delete_document_forever(id).await,
+        }
+    }
+
+    /// Permanently delete the documents trashed more than `retention_days` days ago,
+    /// `0` keeping them forever. Only a local database is purged: the trash of a shared one,
+    /// even through its offline copy, also holds other people's documents.
+    pub async fn purge_trash(&self, retention_days: u32) -> Result<(), Error> {
+        if retention_days == 0 {
+            return Ok(());
+        }
+
+        match self {
+            Self::Sqlite(repository) if repository.sync().is_none() => {
+                repository.purge_trash(retention_days).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub async fn create_version(&self, document_id: i32) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.create_version(document_id).await,
+            Self::Postgres(repository) => repository.create_version(document_id).await,
+        }
+    }
+
+    pub async fn get_versions(&self, document_id: i32) -> Result<Vec<DocumentVersionModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_versions(document_id).await,
+            Self::Postgres(repository) => repository.get_versions(document_id).await,
+        }
+    }
+
+    pub async fn restore_version(&self, document_id: i32, version_id: i32) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.restore_version(document_id, version_id).await,
+            Self::Postgres(repository) => repository.restore_version(document_id, version_id).await,
+        }
+    }
+
+    pub async fn move_document(
+        &self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.move_document(id, parent_id, position).await,
+            Self::Postgres(repository) => repository.move_document(id, parent_id, position).await,
+        }
+    }
+
+    pub async fn search_documents(&self, text: &str) -> Result<Vec<SearchResultModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.search_documents(text).await,
+            Self::Postgres(repository) => repository.search_documents(text).await,
+        }
+    }
+
+    /// `limit` search results from `offset`, along with the number of matches
+    pub async fn search_documents_page(
+        &self,
+        text: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<SearchResultModel>, i64), Error> {
+        match self {
+            Self::Sqlite(repository) => repository.search_documents_page(text, offset, limit).await,
+            Self::Postgres(repository) => {
+                repository.search_documents_page(text, offset, limit).await
+            }
+        }
+    }
+
+    /// Store a file, unless one with the same content already is, and return its hash
+    pub async fn insert_attachment(&self, attachment: AttachmentModel) -> Result<String, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.insert_attachment(attachment).await,
+            Self::Postgres(repository) => repository.insert_attachment(attachment).await,
+        }
+    }
+
+    pub async fn get_attachment(&self, hash: &str) -> Result<Option<AttachmentModel>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_attachment(hash).await,
+            Self::Postgres(repository) => repository.get_attachment(hash).await,
+        }
+    }
+
+    /// Hashes of every stored attachment
+    pub async fn get_attachment_hashes(&self) -> Result<Vec<String>, Error> {
+        match self {
+            Self::Sqlite(repository) => repository.get_attachment_hashes().await,
+            Self::Postgres(repository) => repository.get_attachment_hashes().await,
+        }
+    }
+}
+
+impl DocumentRepositoryPort for AnyDocumentRepository {
+    type ListFuture<'a> = RepositoryFuture<'a, Vec<DocumentModel>>;
+    type GetFuture<'a> = RepositoryFuture<'a, Option<DocumentModel>>;
+    type SaveFuture<'a> = RepositoryFuture<'a, ()>;
+    type InsertFuture<'a> = RepositoryFuture<'a, i32>;
+    type DeleteFuture<'a> = RepositoryFuture<'a, ()>;
+    type SearchFuture<'a> = RepositoryFuture<'a, Vec<SearchResultModel>>;
+    type MoveFuture<'a> = RepositoryFuture<'a, ()>;
+    type RestoreFuture<'a> = RepositoryFuture<'a, Vec<i32>>;
+
+    fn list<'a>(&'a self) -> Self::ListFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.list(),
+            Self::Postgres(repository) => repository.list(),
+        }
+    }
+
+    fn get<'a>(&'a self, id: i32) -> Self::GetFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.get(id),
+            Self::Postgres(repository) => repository.get(id),
+        }
+    }
+
+    fn save<'a>(&'a self, document: DocumentModel) -> Self::SaveFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.save(document),
+            Self::Postgres(repository) => repository.save(document),
+        }
+    }
+
+    fn insert<'a>(&'a self, document: DocumentModel) -> Self::InsertFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.insert(document),
+            Self::Postgres(repository) => repository.insert(document),
+        }
+    }
+
+    fn delete<'a>(&'a self, id: i32) -> Self::DeleteFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.delete(id),
+            Self::Postgres(repository) => repository.delete(id),
+        }
+    }
+
+    fn search<'a>(&'a self, text: String) -> Self::SearchFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.search(text),
+            Self::Postgres(repository) => repository.search(text),
+        }
+    }
+
+    fn move_to<'a>(
+        &'a self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Self::MoveFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.move_to(id, parent_id, position),
+            Self::Postgres(repository) => repository.move_to(id, parent_id, position),
+        }
+    }
+
+    fn restore<'a>(&'a self, id: i32) -> Self::RestoreFuture<'a> {
+        match self {
+            Self::Sqlite(repository) => repository.restore(id),
+            Self::Postgres(repository) => repository.restore(id),
+        }
+    }
+}
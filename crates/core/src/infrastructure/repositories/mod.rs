@@ -0,0 +1,11 @@
+use std::{future::Future, pin::Pin};
+
+pub mod any_document_repository;
+pub mod document_repository;
+pub mod postgres_document_repository;
+
+/// Boxed future used by the repositories to implement the domain ports.
+pub type RepositoryFuture<'a, T> = Pin<Box<dyn Future<Output = anyhow::Result<T>> + Send + 'a>>;
+
+/// Changes a subscriber can lag behind before it starts missing some.
+pub const CHANGES_CAPACITY: usize = 256;
@@ -0,0 +1,793 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Error;
+use sqlx::{PgPool, Postgres, Transaction, postgres::PgListener, query, query_as, query_scalar};
+use tokio::{sync::broadcast, task::JoinHandle, time::sleep};
+
+use crate::{
+    domain::{
+        database::{
+            attachment::AttachmentModel,
+            change::DocumentChange,
+            document::DocumentModel,
+            revision::{DocumentRevisionModel, TombstoneModel},
+            search::SearchResultModel,
+            version::DocumentVersionModel,
+        },
+        ports::DocumentRepositoryPort,
+    },
+    infrastructure::{
+        entities::{
+            AttachmentEntity, DocumentEntity, DocumentRevisionEntity, DocumentVersionEntity,
+            SearchResultEntity, TombstoneEntity,
+        },
+        repositories::{CHANGES_CAPACITY, RepositoryFuture},
+    },
+};
+
+/// Hashes of the attachments embedded in the documents of `subtree` and in their versions,
+/// completing a query defining `subtree`
+const EMBEDDED_ATTACHMENTS: &str = "SELECT DISTINCT \
+        jsonb_path_query(content, '$.**.metadata.hash ? (@.type() == \"string\")') #>> '{}' \
+    FROM ( \
+        SELECT content FROM documents WHERE id IN (SELECT id FROM subtree) \
+        UNION ALL \
+        SELECT content FROM document_versions WHERE document_id IN (SELECT id FROM subtree) \
+    ) AS contents";
+
+/// Maximum number of hits returned by a search
+const SEARCH_LIMIT: i64 = 50;
+
+/// Number of versions kept per document, the oldest ones being dropped first
+const MAX_VERSIONS: i64 = 100;
+
+/// Channel the `documents` trigger notifies on
+const CHANGES_CHANNEL: &str = "document_changes";
+
+/// Wait before listening again after the connection was lost
+const LISTEN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+pub struct PostgresDocumentRepository {
+    pool: PgPool,
+    changes: broadcast::Sender<DocumentChange>,
+    _listener: Arc<ChangeListener>,
+}
+
+/// Task forwarding the database notifications, stopped along with the last repository clone
+struct ChangeListener(JoinHandle<()>);
+
+impl Drop for ChangeListener {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl PostgresDocumentRepository {
+    pub fn new(pool: PgPool) -> Self {
+        let (changes, _) = broadcast::channel(CHANGES_CAPACITY);
+        let listener = tokio::spawn(Self::listen_changes(pool.clone(), changes.clone()));
+
+        Self {
+            pool,
+            changes,
+            _listener: Arc::new(ChangeListener(listener)),
+        }
+    }
+
+    /// Changes made to the database from now on, by this client or any other
+    pub fn subscribe(&self) -> broadcast::Receiver<DocumentChange> {
+        self.changes.subscribe()
+    }
+
+    async fn listen_changes(pool: PgPool, changes: broadcast::Sender<DocumentChange>) {
+        let mut listener = loop {
+            let listener = async {
+                let mut listener = PgListener::connect_with(&pool).await?;
+                listener.listen(CHANGES_CHANNEL).await?;
+                Ok::<_, Error>(listener)
+            }
+            .await;
+
+            match listener {
+                Ok(listener) => break listener,
+                Err(_) => sleep(LISTEN_RETRY_DELAY).await,
+            }
+        };
+
+        loop {
+            match listener.recv().await {
+                Ok(notification) => {
+                    if let Ok(change) = serde_json::from_str(notification.payload()) {
+                        let _ = changes.send(change);
+                    }
+                }
+                // The listener reconnects on the next call, changes made meanwhile are missed
+                Err(_) => sleep(LISTEN_RETRY_DELAY).await,
+            }
+        }
+    }
+
+    pub async fn get_documents(&self) -> Result<Vec<DocumentModel>, Error> {
+        query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents \
+            WHERE deleted_at IS NULL \
+            ORDER BY position ASC, id ASC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .map(|documents| {
+            documents
+                .into_iter()
+                .map(DocumentEntity::into)
+                .collect::<Vec<DocumentModel>>()
+        })
+    }
+
+    /// `limit` documents from `offset` in the order of `get_documents`, along with the
+    /// number of documents outside of the trash
+    pub async fn get_documents_page(
+        &self,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<DocumentModel>, i64), Error> {
+        let total =
+            query_scalar::<_, i64>("SELECT COUNT(*) FROM documents WHERE deleted_at IS NULL")
+                .fetch_one(&self.pool)
+                .await?;
+
+        let documents = query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents \
+            WHERE deleted_at IS NULL \
+            ORDER BY position ASC, id ASC \
+            LIMIT $1 OFFSET $2",
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((
+            documents.into_iter().map(DocumentEntity::into).collect(),
+            total,
+        ))
+    }
+
+    pub async fn get_document_by_id(&self, id: i32) -> Result<DocumentModel, Error> {
+        query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map(|r| r.into())
+        .map_err(anyhow::Error::from)
+    }
+
+    /// The document with this id, unless it is missing or in the trash
+    pub async fn find_document(&self, id: i32) -> Result<Option<DocumentModel>, Error> {
+        query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents \
+            WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|document| document.map(DocumentEntity::into))
+        .map_err(anyhow::Error::from)
+    }
+
+    /// The document with this id along with its revision, trashed or not
+    pub async fn get_document_revision(
+        &self,
+        id: i32,
+    ) -> Result<Option<DocumentRevisionModel>, Error> {
+        query_as::<_, DocumentRevisionEntity>(
+            "SELECT id, parent_id, position, title, content, deleted_at IS NOT NULL AS deleted, \
+                revision \
+            FROM documents WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|document| document.map(DocumentRevisionEntity::into))
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Documents written after `revision`, trashed ones included, oldest revision first
+    pub async fn get_revisions_since(
+        &self,
+        revision: i64,
+    ) -> Result<Vec<DocumentRevisionModel>, Error> {
+        query_as::<_, DocumentRevisionEntity>(
+            "SELECT id, parent_id, position, title, content, deleted_at IS NOT NULL AS deleted, \
+                revision \
+            FROM documents WHERE revision > $1 \
+            ORDER BY revision ASC",
+        )
+        .bind(revision)
+        .fetch_all(&self.pool)
+        .await
+        .map(|documents| {
+            documents
+                .into_iter()
+                .map(DocumentRevisionEntity::into)
+                .collect()
+        })
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Documents deleted for good after `revision`
+    pub async fn get_tombstones_since(&self, revision: i64) -> Result<Vec<TombstoneModel>, Error> {
+        query_as::<_, TombstoneEntity>(
+            "SELECT document_id, revision FROM document_tombstones WHERE revision > $1 \
+            ORDER BY revision ASC",
+        )
+        .bind(revision)
+        .fetch_all(&self.pool)
+        .await
+        .map(|tombstones| tombstones.into_iter().map(TombstoneEntity::into).collect())
+        .map_err(anyhow::Error::from)
+    }
+
+    pub async fn insert_document(&self, document: DocumentModel) -> Result<i32, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        // New pages go after their siblings
+        let id = query_scalar::<_, i32>(
+            "INSERT INTO documents (parent_id, position, title, content) \
+            VALUES ($1, (SELECT COALESCE(MAX(position) + 1, 0) FROM documents \
+                WHERE parent_id IS NOT DISTINCT FROM $1), $2, $3) \
+            RETURNING id",
+        )
+        .bind(document.parent_id)
+        .bind(&document.title)
+        .bind(&document.content)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+        Self::index_document(&mut tx, id, &document).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
+    pub async fn update_document(&self, document: DocumentModel) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        query("UPDATE documents SET title = $1, content = $2, updated_at = now() WHERE id = $3")
+            .bind(&document.title)
+            .bind(&document.content)
+            .bind(document.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::from)?;
+
+        Self::index_document(&mut tx, document.id, &document).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Move a document and all of its sub-pages to the trash
+    pub async fn delete_document(&self, id: i32) -> Result<(), Error> {
+        query(
+            "WITH RECURSIVE subtree(id) AS ( \
+                SELECT $1::INTEGER \
+                UNION ALL \
+                SELECT documents.id FROM documents JOIN subtree ON documents.parent_id = subtree.id \
+            ) \
+            UPDATE documents SET deleted_at = now() \
+            WHERE id IN (SELECT id FROM subtree) AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+        Ok(())
+    }
+
+    /// Documents linking to `id`, by title, the ones in the trash left out
+    pub async fn get_backlinks(&self, id: i32) -> Result<Vec<DocumentModel>, Error> {
+        query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents \
+            WHERE deleted_at IS NULL AND id != $1 AND id IN \
+                (SELECT source_id FROM document_links WHERE target_id = $1) \
+            ORDER BY title ASC, id ASC",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .map(|documents| documents.into_iter().map(DocumentEntity::into).collect())
+    }
+
+    /// Documents in the trash, most recently deleted first. Sub-pages trashed along with
+    /// their parent are left out, they come back when the parent is restored.
+    pub async fn get_trashed_documents(&self) -> Result<Vec<DocumentModel>, Error> {
+        query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents \
+            WHERE deleted_at IS NOT NULL AND (parent_id IS NULL OR parent_id NOT IN \
+                (SELECT id FROM documents WHERE deleted_at IS NOT NULL)) \
+            ORDER BY deleted_at DESC, id DESC",
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .map(|documents| documents.into_iter().map(DocumentEntity::into).collect())
+    }
+
+    /// Bring a document back from the trash, along with the sub-pages trashed at the same
+    /// time. Returns the ids of the restored documents.
+    pub async fn restore_document(&self, id: i32) -> Result<Vec<i32>, Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let restored = query_scalar::<_, i32>(
+            "WITH RECURSIVE subtree(id) AS ( \
+                SELECT $1::INTEGER \
+                UNION ALL \
+                SELECT documents.id FROM documents JOIN subtree ON documents.parent_id = subtree.id \
+            ) \
+            UPDATE documents SET deleted_at = NULL \
+            WHERE id IN (SELECT id FROM subtree) \
+                AND deleted_at = (SELECT deleted_at FROM documents WHERE id = $1) \
+            RETURNING id",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // A page whose parent is still in the trash comes back at the top level
+        query(
+            "UPDATE documents SET parent_id = NULL \
+            WHERE id = $1 AND parent_id IN (SELECT id FROM documents WHERE deleted_at IS NOT NULL)",
+        )
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(restored)
+    }
+
+    /// Permanently delete a document, its sub-pages being removed by the foreign key, along
+    /// with the attachments no other document uses
+    pub async fn delete_document_forever(&self, id: i32) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let attachments = query_scalar::<_, String>(&format!(
+            "WITH RECURSIVE subtree(id) AS ( \
+                SELECT $1::INTEGER \
+                UNION ALL \
+                SELECT documents.id FROM documents JOIN subtree ON documents.parent_id = subtree.id \
+            ) \
+            {}",
+            EMBEDDED_ATTACHMENTS
+        ))
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        query("DELETE FROM documents WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::from)?;
+        Self::delete_unused_attachments(&mut tx, &attachments).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Permanently delete the documents trashed more than `retention_days` days ago, along
+    /// with the attachments no other document uses
+    pub async fn purge_trash(&self, retention_days: u32) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let attachments = query_scalar::<_, String>(&format!(
+            "WITH RECURSIVE subtree(id) AS ( \
+                SELECT id FROM documents WHERE deleted_at < now() - make_interval(days => $1) \
+                UNION ALL \
+                SELECT documents.id FROM documents JOIN subtree ON documents.parent_id = subtree.id \
+            ) \
+            {}",
+            EMBEDDED_ATTACHMENTS
+        ))
+        .bind(retention_days as i32)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        query("DELETE FROM documents WHERE deleted_at < now() - make_interval(days => $1)")
+            .bind(retention_days as i32)
+            .execute(&mut *tx)
+            .await
+            .map_err(anyhow::Error::from)?;
+        Self::delete_unused_attachments(&mut tx, &attachments).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Move a document under `parent_id` (the root when `None`) at `position` among its siblings
+    pub async fn move_document(
+        &self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(parent_id) = parent_id {
+            let is_descendant = query_scalar::<_, bool>(
+                "WITH RECURSIVE ancestors(id) AS ( \
+                    SELECT $1::INTEGER \
+                    UNION ALL \
+                    SELECT documents.parent_id FROM documents \
+                    JOIN ancestors ON documents.id = ancestors.id \
+                    WHERE documents.parent_id IS NOT NULL \
+                ) \
+                SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = $2)",
+            )
+            .bind(parent_id)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            if is_descendant {
+                anyhow::bail!("A page cannot be moved inside itself");
+            }
+        }
+
+        let mut siblings = query_scalar::<_, i32>(
+            "SELECT id FROM documents \
+            WHERE parent_id IS NOT DISTINCT FROM $1 AND id != $2 AND deleted_at IS NULL \
+            ORDER BY position ASC, id ASC",
+        )
+        .bind(parent_id)
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        siblings.insert(position.min(siblings.len()), id);
+
+        for (position, sibling_id) in siblings.into_iter().enumerate() {
+            query("UPDATE documents SET parent_id = $1, position = $2 WHERE id = $3")
+                .bind(parent_id)
+                .bind(position as i32)
+                .bind(sibling_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Full-text search over titles and blocks, every word being matched as a prefix
+    pub async fn search_documents(&self, text: &str) -> Result<Vec<SearchResultModel>, Error> {
+        self.search_documents_page(text, 0, SEARCH_LIMIT)
+            .await
+            .map(|(results, _)| results)
+    }
+
+    /// `limit` search results from `offset`, best matches first, along with the number of
+    /// matches
+    pub async fn search_documents_page(
+        &self,
+        text: &str,
+        offset: i64,
+        limit: i64,
+    ) -> Result<(Vec<SearchResultModel>, i64), Error> {
+        let terms: Vec<String> = text
+            .split_whitespace()
+            .map(|term| {
+                term.chars()
+                    .filter(|ch| ch.is_alphanumeric())
+                    .collect::<String>()
+            })
+            .filter(|term| !term.is_empty())
+            .map(|term| format!("{}:*", term))
+            .collect();
+
+        if terms.is_empty() {
+            return Ok((Vec::new(), 0));
+        }
+        let terms = terms.join(" & ");
+
+        let total = query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM document_search \
+            JOIN documents ON documents.id = document_search.document_id \
+            WHERE document_search.search @@ to_tsquery('simple', $1) \
+                AND documents.deleted_at IS NULL",
+        )
+        .bind(&terms)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let results = query_as::<_, SearchResultEntity>(
+            "SELECT document_search.document_id, document_search.node_id, documents.title, \
+                CASE WHEN document_search.content = '' THEN documents.title \
+                    ELSE ts_headline('simple', document_search.content, search_query, \
+                        'MaxWords=12, MinWords=4') \
+                END AS snippet \
+            FROM document_search \
+            JOIN documents ON documents.id = document_search.document_id, \
+                to_tsquery('simple', $1) AS search_query \
+            WHERE document_search.search @@ search_query AND documents.deleted_at IS NULL \
+            ORDER BY ts_rank(document_search.search, search_query) DESC \
+            LIMIT $2 OFFSET $3",
+        )
+        .bind(&terms)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((
+            results
+                .into_iter()
+                .map(|mut result| {
+                    // ts_headline wraps the matched words in <b></b>
+                    result.snippet = result.snippet.replace("<b>", "").replace("</b>", "");
+                    result.into()
+                })
+                .collect(),
+            total,
+        ))
+    }
+
+    /// Snapshot the stored title and content of a document, unless the latest version
+    /// already holds them
+    pub async fn create_version(&self, document_id: i32) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        Self::snapshot_document(&mut tx, document_id).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Versions of a document, most recent first
+    pub async fn get_versions(&self, document_id: i32) -> Result<Vec<DocumentVersionModel>, Error> {
+        query_as::<_, DocumentVersionEntity>(
+            "SELECT id, document_id, title, content, created_at FROM document_versions \
+            WHERE document_id = $1 \
+            ORDER BY id DESC",
+        )
+        .bind(document_id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)
+        .map(|versions| {
+            versions
+                .into_iter()
+                .map(DocumentVersionEntity::into)
+                .collect()
+        })
+    }
+
+    /// Bring a document back to one of its versions. The state being replaced and the
+    /// restored one are both kept as versions, so a restore can itself be reverted.
+    pub async fn restore_version(&self, document_id: i32, version_id: i32) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+
+        Self::snapshot_document(&mut tx, document_id).await?;
+
+        query(
+            "UPDATE documents SET title = version.title, content = version.content, updated_at = now() \
+            FROM (SELECT title, content FROM document_versions WHERE id = $1 AND document_id = $2) AS version \
+            WHERE documents.id = $2",
+        )
+        .bind(version_id)
+        .bind(document_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+        let document: DocumentModel = query_as::<_, DocumentEntity>(
+            "SELECT id, parent_id, position, title, content FROM documents WHERE id = $1",
+        )
+        .bind(document_id)
+        .fetch_one(&mut *tx)
+        .await?
+        .into();
+
+        Self::index_document(&mut tx, document_id, &document).await?;
+        Self::snapshot_document(&mut tx, document_id).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Store a file, unless one with the same content already is, and return its hash
+    pub async fn insert_attachment(&self, attachment: AttachmentModel) -> Result<String, Error> {
+        query(
+            "INSERT INTO attachments (hash, mime_type, size, data) VALUES ($1, $2, $3, $4) \
+            ON CONFLICT (hash) DO NOTHING",
+        )
+        .bind(&attachment.hash)
+        .bind(&attachment.mime_type)
+        .bind(attachment.data.len() as i64)
+        .bind(&attachment.data)
+        .execute(&self.pool)
+        .await
+        .map_err(anyhow::Error::from)?;
+
+        Ok(attachment.hash)
+    }
+
+    pub async fn get_attachment(&self, hash: &str) -> Result<Option<AttachmentModel>, Error> {
+        query_as::<_, AttachmentEntity>(
+            "SELECT hash, mime_type, data FROM attachments WHERE hash = $1",
+        )
+        .bind(hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map(|attachment| attachment.map(AttachmentEntity::into))
+        .map_err(anyhow::Error::from)
+    }
+
+    /// Hashes of every stored attachment, used by the sync to copy the missing ones
+    pub async fn get_attachment_hashes(&self) -> Result<Vec<String>, Error> {
+        query_scalar::<_, String>("SELECT hash FROM attachments ORDER BY hash")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(anyhow::Error::from)
+    }
+
+    async fn snapshot_document(
+        tx: &mut Transaction<'_, Postgres>,
+        document_id: i32,
+    ) -> Result<(), Error> {
+        query(
+            "INSERT INTO document_versions (document_id, title, content) \
+            SELECT id, title, content FROM documents \
+            WHERE id = $1 AND NOT EXISTS ( \
+                SELECT 1 FROM document_versions AS latest \
+                WHERE latest.id = (SELECT MAX(id) FROM document_versions WHERE document_id = $1) \
+                    AND latest.title = documents.title AND latest.content = documents.content \
+            )",
+        )
+        .bind(document_id)
+        .execute(&mut **tx)
+        .await?;
+
+        query(
+            "DELETE FROM document_versions WHERE document_id = $1 AND id NOT IN ( \
+                SELECT id FROM document_versions WHERE document_id = $1 \
+                ORDER BY id DESC LIMIT $2 \
+            )",
+        )
+        .bind(document_id)
+        .bind(MAX_VERSIONS)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Delete the attachments among `hashes` that no document or version embeds anymore.
+    /// Only the ones of deleted documents are looked at, an attachment being stored before the
+    /// document embedding it is saved.
+    async fn delete_unused_attachments(
+        tx: &mut Transaction<'_, Postgres>,
+        hashes: &[String],
+    ) -> Result<(), Error> {
+        query(
+            "DELETE FROM attachments WHERE hash = ANY($1) AND hash NOT IN ( \
+                SELECT jsonb_path_query(content, '$.**.metadata.hash ? (@.type() == \"string\")') #>> '{}' \
+                FROM (SELECT content FROM documents UNION ALL SELECT content FROM document_versions) AS contents \
+            )",
+        )
+        .bind(hashes)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Replace the search rows and the links of a document with its current title and blocks
+    async fn index_document(
+        tx: &mut Transaction<'_, Postgres>,
+        id: i32,
+        document: &DocumentModel,
+    ) -> Result<(), Error> {
+        query("DELETE FROM document_search WHERE document_id = $1")
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        query("INSERT INTO document_search (document_id, node_id, title, content) VALUES ($1, NULL, $2, '')")
+            .bind(id)
+            .bind(&document.title)
+            .execute(&mut **tx)
+            .await?;
+
+        for (node_id, content) in document.text_blocks() {
+            query("INSERT INTO document_search (document_id, node_id, title, content) VALUES ($1, $2, '', $3)")
+                .bind(id)
+                .bind(node_id)
+                .bind(content)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        query("DELETE FROM document_links WHERE source_id = $1")
+            .bind(id)
+            .execute(&mut **tx)
+            .await?;
+
+        for target_id in document.linked_documents() {
+            query("INSERT INTO document_links (source_id, target_id) VALUES ($1, $2)")
+                .bind(id)
+                .bind(target_id)
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl DocumentRepositoryPort for PostgresDocumentRepository {
+    type ListFuture<'a> = RepositoryFuture<'a, Vec<DocumentModel>>;
+    type GetFuture<'a> = RepositoryFuture<'a, Option<DocumentModel>>;
+    type SaveFuture<'a> = RepositoryFuture<'a, ()>;
+    type InsertFuture<'a> = RepositoryFuture<'a, i32>;
+    type DeleteFuture<'a> = RepositoryFuture<'a, ()>;
+    type SearchFuture<'a> = RepositoryFuture<'a, Vec<SearchResultModel>>;
+    type MoveFuture<'a> = RepositoryFuture<'a, ()>;
+    type RestoreFuture<'a> = RepositoryFuture<'a, Vec<i32>>;
+
+    fn list<'a>(&'a self) -> Self::ListFuture<'a> {
+        Box::pin(self.get_documents())
+    }
+
+    fn get<'a>(&'a self, id: i32) -> Self::GetFuture<'a> {
+        Box::pin(async move {
+            query_as::<_, DocumentEntity>(
+                "SELECT id, parent_id, position, title, content FROM documents WHERE id = $1",
+            )
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await
+            .map(|r| r.map(DocumentEntity::into))
+            .map_err(anyhow::Error::from)
+        })
+    }
+
+    fn save<'a>(&'a self, document: DocumentModel) -> Self::SaveFuture<'a> {
+        Box::pin(self.update_document(document))
+    }
+
+    fn insert<'a>(&'a self, document: DocumentModel) -> Self::InsertFuture<'a> {
+        Box::pin(self.insert_document(document))
+    }
+
+    fn delete<'a>(&'a self, id: i32) -> Self::DeleteFuture<'a> {
+        Box::pin(self.delete_document(id))
+    }
+
+    fn search<'a>(&'a self, text: String) -> Self::SearchFuture<'a> {
+        Box::pin(async move { self.search_documents(&text).await })
+    }
+
+    fn move_to<'a>(
+        &'a self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+    ) -> Self::MoveFuture<'a> {
+        Box::pin(self.move_document(id, parent_id, position))
+    }
+
+    fn restore<'a>(&'a self, id: i32) -> Self::RestoreFuture<'a> {
+        Box::pin(self.restore_document(id))
+    }
+}
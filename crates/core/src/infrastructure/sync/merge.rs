@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+/// A block of a content without its children, which follow it one level deeper
+#[derive(Debug, Clone, PartialEq)]
+struct Block {
+    depth: usize,
+    node: Value,
+}
+
+/// Merge two contents edited from `base`, block by block using the node ids.
+///
+/// A block changed on one side only takes that change, removals included. A block changed
+/// on both sides keeps the local version, and a block removed on one side but edited on the
+/// other is kept. Blocks added on either side are placed after the block preceding them.
+pub fn merge_blocks(base: &Value, local: &Value, remote: &Value) -> Value {
+    if local == base {
+        return remote.clone();
+    }
+    if remote == base || remote == local {
+        return local.clone();
+    }
+
+    let base = flatten(base);
+    let local = flatten(local);
+    let remote = flatten(remote);
+
+    let base_blocks: HashMap<&str, &Block> = blocks_by_id(&base);
+    let local_blocks: HashMap<&str, &Block> = blocks_by_id(&local);
+    let remote_blocks: HashMap<&str, &Block> = blocks_by_id(&remote);
+
+    // The side that reordered the blocks it shares with the base gives the order
+    let (leading, following) = if reordered(&local, &base) && !reordered(&remote, &base) {
+        (&local, &remote)
+    } else {
+        (&remote, &local)
+    };
+
+    let mut ids: Vec<&str> = leading.iter().map(|(id, _)| id.as_str()).collect();
+    let mut anchor: Option<usize> = None;
+    for (id, _) in following {
+        let id = id.as_str();
+        match ids.iter().position(|existing| *existing == id) {
+            Some(index) => anchor = Some(index),
+            None => {
+                let index = anchor.map_or(0, |index| index + 1);
+                ids.insert(index, id);
+                anchor = Some(index);
+            }
+        }
+    }
+
+    let blocks = ids
+        .into_iter()
+        .filter_map(|id| {
+            let base = base_blocks.get(id).copied();
+            let local = local_blocks.get(id).copied();
+            let remote = remote_blocks.get(id).copied();
+
+            match (base, local, remote) {
+                (_, local, remote) if local == remote => local,
+                (Some(base), local, remote) if local == Some(base) => remote,
+                (Some(base), local, remote) if remote == Some(base) => local,
+                (_, Some(local), _) => Some(local),
+                (_, None, remote) => remote,
+            }
+        })
+        .cloned()
+        .collect();
+
+    nest(blocks)
+}
+
+/// Blocks in document order, keyed by node id
+fn flatten(content: &Value) -> Vec<(String, Block)> {
+    fn collect(nodes: &[Value], depth: usize, blocks: &mut Vec<(String, Block)>) {
+        for node in nodes {
+            let mut block = node.clone();
+            let children = block
+                .as_object_mut()
+                .and_then(|object| object.remove("children"));
+
+            // Blocks without an id can only be matched by their position
+            let id = match node["id"].as_str() {
+                Some(id) => id.to_string(),
+                None => format!("#{}", blocks.len()),
+            };
+            blocks.push((id, Block { depth, node: block }));
+
+            if let Some(Value::Array(children)) = children {
+                collect(&children, depth + 1, blocks);
+            }
+        }
+    }
+
+    let mut blocks = Vec::new();
+    if let Some(nodes) = content.as_array() {
+        collect(nodes, 0, &mut blocks);
+    }
+
+    blocks
+}
+
+fn blocks_by_id(blocks: &[(String, Block)]) -> HashMap<&str, &Block> {
+    blocks
+        .iter()
+        .map(|(id, block)| (id.as_str(), block))
+        .collect()
+}
+
+/// Whether the blocks `side` shares with `base` are in another order
+fn reordered(side: &[(String, Block)], base: &[(String, Block)]) -> bool {
+    let side_ids: HashSet<&str> = side.iter().map(|(id, _)| id.as_str()).collect();
+    let base_ids: HashSet<&str> = base.iter().map(|(id, _)| id.as_str()).collect();
+
+    let side_order = side
+        .iter()
+        .map(|(id, _)| id.as_str())
+        .filter(|id| base_ids.contains(id));
+    let base_order = base
+        .iter()
+        .map(|(id, _)| id.as_str())
+        .filter(|id| side_ids.contains(id));
+
+    !side_order.eq(base_order)
+}
+
+/// Nest the blocks back under their parent's `children`, a block being at most one level
+/// deeper than the one before it
+fn nest(blocks: Vec<Block>) -> Value {
+    let mut roots = Vec::new();
+    let mut stack: Vec<(usize, Value)> = Vec::new();
+
+    fn attach(roots: &mut Vec<Value>, stack: &mut [(usize, Value)], node: Value) {
+        match stack.last_mut() {
+            Some((_, parent)) => {
+                let children = parent.as_object_mut().and_then(|parent| {
+                    parent
+                        .entry("children")
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                        .as_array_mut()
+                });
+                if let Some(children) = children {
+                    children.push(node);
+                }
+            }
+            None => roots.push(node),
+        }
+    }
+
+    for block in blocks {
+        let depth = stack
+            .last()
+            .map_or(0, |(depth, _)| block.depth.min(depth + 1));
+
+        while stack.last().is_some_and(|(last, _)| *last >= depth) {
+            let (_, node) = stack.pop().unwrap();
+            attach(&mut roots, &mut stack, node);
+        }
+        stack.push((depth, block.node));
+    }
+
+    while let Some((_, node)) = stack.pop() {
+        attach(&mut roots, &mut stack, node);
+    }
+
+    Value::Array(roots)
+}
@@ -0,0 +1,699 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use anyhow::Error;
+use serde_json::Value;
+use sqlx::{
+    FromRow, PgPool, Sqlite, Transaction, migrate, postgres::PgPoolOptions, query, query_as,
+    query_scalar,
+};
+use tokio::{
+    sync::{Notify, broadcast, watch},
+    task::JoinHandle,
+    time::sleep,
+};
+
+use crate::{
+    domain::database::{
+        change::DocumentChange,
+        document::DocumentModel,
+        revision::{DocumentRevisionModel, TombstoneModel},
+    },
+    infrastructure::{
+        repositories::{
+            document_repository::DocumentRepository,
+            postgres_document_repository::PostgresDocumentRepository,
+        },
+        sync::merge::merge_blocks,
+    },
+};
+
+pub mod merge;
+
+/// Wait after a local or remote change before syncing, so that typing does not sync every key
+const SYNC_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Sync even without changes, for the remote ones made while offline
+const SYNC_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Time given to the remote database to answer before being considered offline
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Remote revisions read again on each pull, a write taking its revision before committing
+/// and possibly committing after a later one
+const PULL_OVERLAP: i64 = 100;
+
+/// Where an offline copy stands compared to its remote database
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    /// Changes are being exchanged with the remote database
+    Syncing,
+    /// Every local change was pushed and the remote ones pulled
+    Synced,
+    /// The remote database cannot be reached, `pending` documents wait to be pushed
+    Offline { pending: usize },
+    /// The last sync failed, it is tried again later
+    Failed(String),
+}
+
+/// Keeps a local SQLite copy of a remote database in sync. Local changes are queued in an
+/// outbox by the database itself and pushed once the remote database can be reached, the
+/// remote ones being pulled from the last revision seen. Documents edited on both sides are
+/// merged block by block with [`merge_blocks`].
+///
+/// The sync runs until the last repository holding the engine is dropped.
+pub struct SyncEngine {
+    status: watch::Receiver<SyncStatus>,
+    wake: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+impl Drop for SyncEngine {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl SyncEngine {
+    /// Sync `local` with the Postgres database at `url`, which does not need to be reachable yet
+    pub(crate) async fn start(local: DocumentRepository, url: &str) -> Result<Self, Error> {
+        // Marks the database as an offline copy, its changes being queued from now on
+        query("INSERT OR IGNORE INTO sync_state (id) VALUES (1)")
+            .execute(local.pool())
+            .await?;
+
+        let pool = PgPoolOptions::new()
+            .acquire_timeout(CONNECT_TIMEOUT)
+            .connect_lazy(url)?;
+
+        let (status_sender, status) = watch::channel(SyncStatus::Syncing);
+        let wake = Arc::new(Notify::new());
+        let task = tokio::spawn(Self::run(local, pool, status_sender, wake.clone()));
+
+        Ok(Self { status, wake, task })
+    }
+
+    /// Status of the sync, updated after each attempt
+    pub fn status(&self) -> watch::Receiver<SyncStatus> {
+        self.status.clone()
+    }
+
+    /// Sync right away instead of waiting for the next change
+    pub fn sync_now(&self) {
+        self.wake.notify_one();
+    }
+
+    async fn run(
+        local: DocumentRepository,
+        pool: PgPool,
+        status: watch::Sender<SyncStatus>,
+        wake: Arc<Notify>,
+    ) {
+        let mut local_changes = local.subscribe();
+        let mut remote: Option<(
+            PostgresDocumentRepository,
+            broadcast::Receiver<DocumentChange>,
+        )> = None;
+
+        loop {
+            let result = match &remote {
+                Some((repository, _)) => {
+                    let _ = status.send(SyncStatus::Syncing);
+                    SyncRound {
+                        local: &local,
+                        remote: repository,
+                    }
+                    .run()
+                    .await
+                }
+                None => match Self::connect(&pool).await {
+                    Ok(repository) => {
+                        // Synced on the next turn, the changes being listened to from now on
+                        let changes = repository.subscribe();
+                        remote = Some((repository, changes));
+                        continue;
+                    }
+                    Err(error) => Err(error),
+                },
+            };
+
+            let next_status = match result {
+                Ok(()) => SyncStatus::Synced,
+                Err(error) if is_offline(&error) => SyncStatus::Offline {
+                    pending: pending_documents(&local).await.unwrap_or_default(),
+                },
+                Err(error) => SyncStatus::Failed(format!("{:#}", error)),
+            };
+            let _ = status.send(next_status);
+
+            let remote_change = async {
+                match &mut remote {
+                    Some((_, changes)) => {
+                        let _ = changes.recv().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            };
+
+            tokio::select! {
+                _ = wake.notified() => {}
+                _ = sleep(SYNC_INTERVAL) => {}
+                _ = local_changes.recv() => sleep(SYNC_DEBOUNCE).await,
+                _ = remote_change => sleep(SYNC_DEBOUNCE).await,
+            }
+        }
+    }
+
+    async fn connect(pool: &PgPool) -> Result<PostgresDocumentRepository, Error> {
+        // Tells an unreachable database apart from a failing migration
+        pool.acquire().await?;
+
+        migrate!("./migrations/postgres")
+            .run(pool)
+            .await
+            .map_err(|err| Error::msg(err.to_string()))?;
+
+        Ok(PostgresDocumentRepository::new(pool.clone()))
+    }
+}
+
+/// Whether `error` comes from the remote database being out of reach
+fn is_offline(error: &Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<sqlx::Error>(),
+            Some(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut)
+        )
+    })
+}
+
+/// Number of documents with local changes waiting to be pushed
+async fn pending_documents(local: &DocumentRepository) -> Result<usize, Error> {
+    let pending = query_scalar::<_, i64>("SELECT COUNT(DISTINCT document_id) FROM sync_outbox")
+        .fetch_one(local.pool())
+        .await?;
+
+    Ok(pending as usize)
+}
+
+/// What the sync compares of a document, its parent being a local id
+#[derive(Debug, Clone, PartialEq, FromRow)]
+struct SyncedDocument {
+    parent_id: Option<i32>,
+    position: i32,
+    title: String,
+    content: Value,
+    deleted: bool,
+}
+
+impl SyncedDocument {
+    /// Keep what changed on each side since `base`, the local side winning when both did
+    fn merge(base: &Self, local: &Self, remote: &Self) -> Self {
+        fn pick<T: PartialEq + Clone>(base: &T, local: &T, remote: &T) -> T {
+            if local != base {
+                local.clone()
+            } else {
+                remote.clone()
+            }
+        }
+
+        let (parent_id, position) = pick(
+            &(base.parent_id, base.position),
+            &(local.parent_id, local.position),
+            &(remote.parent_id, remote.position),
+        );
+
+        Self {
+            parent_id,
+            position,
+            title: pick(&base.title, &local.title, &remote.title),
+            content: merge_blocks(&base.content, &local.content, &remote.content),
+            deleted: pick(&base.deleted, &local.deleted, &remote.deleted),
+        }
+    }
+
+    fn model(&self, id: i32, parent_id: Option<i32>) -> DocumentModel {
+        DocumentModel {
+            id,
+            parent_id,
+            position: self.position,
+            title: self.title.clone(),
+            content: self.content.clone(),
+        }
+    }
+}
+
+/// State of a document when it was last synced
+#[derive(Debug, FromRow)]
+struct SyncBase {
+    remote_id: i32,
+    #[sqlx(flatten)]
+    document: SyncedDocument,
+}
+
+/// A push of the local changes followed by a pull of the remote ones, the attachments
+/// missing on either side being copied over last
+struct SyncRound<'a> {
+    local: &'a DocumentRepository,
+    remote: &'a PostgresDocumentRepository,
+}
+
+impl SyncRound<'_> {
+    async fn run(&self) -> Result<(), Error> {
+        self.push().await?;
+        self.pull().await?;
+        self.exchange_attachments().await
+    }
+
+    /// Attachments never change once stored, so each side only needs the ones it lacks
+    async fn exchange_attachments(&self) -> Result<(), Error> {
+        let local: HashSet<String> = self
+            .local
+            .get_attachment_hashes()
+            .await?
+            .into_iter()
+            .collect();
+        let remote: HashSet<String> = self
+            .remote
+            .get_attachment_hashes()
+            .await?
+            .into_iter()
+            .collect();
+
+        for hash in local.difference(&remote) {
+            if let Some(attachment) = self.local.get_attachment(hash).await? {
+                self.remote.insert_attachment(attachment).await?;
+            }
+        }
+
+        for hash in remote.difference(&local) {
+            if let Some(attachment) = self.remote.get_attachment(hash).await? {
+                self.local.insert_attachment(attachment).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn push(&self) -> Result<(), Error> {
+        let documents = query_as::<_, (i32, i64)>(
+            "SELECT document_id, MAX(id) FROM sync_outbox GROUP BY document_id ORDER BY MIN(id)",
+        )
+        .fetch_all(self.local.pool())
+        .await?;
+
+        for (id, last_entry) in documents {
+            self.push_document(id).await?;
+
+            // Entries queued while pushing stay for the next round
+            query("DELETE FROM sync_outbox WHERE document_id = ? AND id <= ?")
+                .bind(id)
+                .bind(last_entry)
+                .execute(self.local.pool())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn push_document(&self, id: i32) -> Result<(), Error> {
+        let local = self.local_document(id).await?;
+        let base = self.base(id).await?;
+
+        match (local, base) {
+            // Created and deleted for good while offline
+            (None, None) => {}
+            (None, Some(base)) => {
+                self.remote.delete_document_forever(base.remote_id).await?;
+                self.forget(id).await?;
+            }
+            (Some(local), None) => {
+                if !local.deleted {
+                    self.create_remote(id, &local).await?;
+                }
+            }
+            (Some(local), Some(base)) => {
+                match self.remote.get_document_revision(base.remote_id).await? {
+                    Some(revision) => {
+                        let remote = self.synced_document(&revision).await?;
+                        let merged = SyncedDocument::merge(&base.document, &local, &remote);
+
+                        self.update_remote(base.remote_id, &remote, &merged).await?;
+                        if merged != local {
+                            self.write_local(id, &merged).await?;
+                        }
+                        self.record_base(id, base.remote_id, &merged).await?;
+                    }
+                    // Deleted for good on the remote side while edited here, the edits win
+                    None => {
+                        self.forget(id).await?;
+                        if !local.deleted {
+                            self.create_remote(id, &local).await?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn pull(&self) -> Result<(), Error> {
+        let cursor = query_scalar::<_, i64>("SELECT cursor FROM sync_state WHERE id = 1")
+            .fetch_one(self.local.pool())
+            .await?;
+        let from = (cursor - PULL_OVERLAP).max(0);
+        let mut latest = cursor;
+
+        for revision in self.remote.get_revisions_since(from).await? {
+            latest = latest.max(revision.revision);
+            self.pull_document(revision).await?;
+        }
+
+        for tombstone in self.remote.get_tombstones_since(from).await? {
+            latest = latest.max(tombstone.revision);
+            self.pull_tombstone(tombstone).await?;
+        }
+
+        query("UPDATE sync_state SET cursor = ? WHERE id = 1")
+            .bind(latest)
+            .execute(self.local.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn pull_document(&self, revision: DocumentRevisionModel) -> Result<(), Error> {
+        let remote_id = revision.document.id;
+        let remote = self.synced_document(&revision).await?;
+
+        let id =
+            query_scalar::<_, i32>("SELECT document_id FROM sync_documents WHERE remote_id = ?")
+                .bind(remote_id)
+                .fetch_optional(self.local.pool())
+                .await?;
+
+        let Some(id) = id else {
+            if !remote.deleted {
+                let id = self.insert_local(&remote).await?;
+                self.record_base(id, remote_id, &remote).await?;
+            }
+            return Ok(());
+        };
+
+        let Some(base) = self.base(id).await? else {
+            return Ok(());
+        };
+        if base.document == remote {
+            return Ok(());
+        }
+
+        // Deleted for good locally, the push removes it from the remote database
+        let Some(local) = self.local_document(id).await? else {
+            return Ok(());
+        };
+
+        // Local changes still to push are merged in, the push sending the result
+        let merged = if self.has_pending_changes(id).await? {
+            SyncedDocument::merge(&base.document, &local, &remote)
+        } else {
+            remote.clone()
+        };
+
+        if merged != local {
+            self.write_local(id, &merged).await?;
+        }
+        self.record_base(id, remote_id, &remote).await
+    }
+
+    async fn pull_tombstone(&self, tombstone: TombstoneModel) -> Result<(), Error> {
+        let id =
+            query_scalar::<_, i32>("SELECT document_id FROM sync_documents WHERE remote_id = ?")
+                .bind(tombstone.document_id)
+                .fetch_optional(self.local.pool())
+                .await?;
+
+        let Some(id) = id else {
+            return Ok(());
+        };
+
+        // Edited while deleted on the remote side, the push creates it again
+        if !self.has_pending_changes(id).await? {
+            self.delete_local(id).await?;
+        }
+        self.forget(id).await
+    }
+
+    async fn create_remote(&self, id: i32, local: &SyncedDocument) -> Result<(), Error> {
+        let parent_id = self.remote_id(local.parent_id).await?;
+        let remote_id = self
+            .remote
+            .insert_document(local.model(0, parent_id))
+            .await?;
+        // New documents are added after their siblings, wherever they were put locally
+        self.remote
+            .move_document(remote_id, parent_id, local.position.max(0) as usize)
+            .await?;
+
+        self.record_base(id, remote_id, local).await
+    }
+
+    async fn update_remote(
+        &self,
+        remote_id: i32,
+        remote: &SyncedDocument,
+        merged: &SyncedDocument,
+    ) -> Result<(), Error> {
+        let parent_id = self.remote_id(merged.parent_id).await?;
+
+        if merged.title != remote.title || merged.content != remote.content {
+            self.remote
+                .update_document(merged.model(remote_id, parent_id))
+                .await?;
+        }
+
+        if (merged.parent_id, merged.position) != (remote.parent_id, remote.position) {
+            self.remote
+                .move_document(remote_id, parent_id, merged.position.max(0) as usize)
+                .await?;
+        }
+
+        if merged.deleted != remote.deleted {
+            if merged.deleted {
+                self.remote.delete_document(remote_id).await?;
+            } else {
+                self.remote.restore_document(remote_id).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn local_document(&self, id: i32) -> Result<Option<SyncedDocument>, Error> {
+        query_as::<_, SyncedDocument>(
+            "SELECT parent_id, position, title, content, deleted_at IS NOT NULL AS deleted \
+            FROM documents WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(self.local.pool())
+        .await
+        .map_err(Error::from)
+    }
+
+    async fn base(&self, id: i32) -> Result<Option<SyncBase>, Error> {
+        query_as::<_, SyncBase>(
+            "SELECT remote_id, parent_id, position, title, content, deleted \
+            FROM sync_documents WHERE document_id = ?",
+        )
+        .bind(id)
+        .fetch_optional(self.local.pool())
+        .await
+        .map_err(Error::from)
+    }
+
+    async fn record_base(
+        &self,
+        id: i32,
+        remote_id: i32,
+        document: &SyncedDocument,
+    ) -> Result<(), Error> {
+        query(
+            "INSERT INTO sync_documents \
+                (document_id, remote_id, parent_id, position, title, content, deleted) \
+            VALUES (?, ?, ?, ?, ?, ?, ?) \
+            ON CONFLICT (document_id) DO UPDATE SET remote_id = excluded.remote_id, \
+                parent_id = excluded.parent_id, position = excluded.position, \
+                title = excluded.title, content = excluded.content, deleted = excluded.deleted",
+        )
+        .bind(id)
+        .bind(remote_id)
+        .bind(document.parent_id)
+        .bind(document.position)
+        .bind(&document.title)
+        .bind(&document.content)
+        .bind(document.deleted)
+        .execute(self.local.pool())
+        .await?;
+
+        Ok(())
+    }
+
+    async fn forget(&self, id: i32) -> Result<(), Error> {
+        query("DELETE FROM sync_documents WHERE document_id = ?")
+            .bind(id)
+            .execute(self.local.pool())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn has_pending_changes(&self, id: i32) -> Result<bool, Error> {
+        query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM sync_outbox WHERE document_id = ?)")
+            .bind(id)
+            .fetch_one(self.local.pool())
+            .await
+            .map_err(Error::from)
+    }
+
+    /// Remote id of the local document `id`, `None` when it was not pushed yet
+    async fn remote_id(&self, id: Option<i32>) -> Result<Option<i32>, Error> {
+        let Some(id) = id else {
+            return Ok(None);
+        };
+
+        query_scalar::<_, i32>("SELECT remote_id FROM sync_documents WHERE document_id = ?")
+            .bind(id)
+            .fetch_optional(self.local.pool())
+            .await
+            .map_err(Error::from)
+    }
+
+    /// A remote document as the sync compares it, its parent being mapped to its local id
+    async fn synced_document(
+        &self,
+        revision: &DocumentRevisionModel,
+    ) -> Result<SyncedDocument, Error> {
+        let parent_id = match revision.document.parent_id {
+            Some(parent_id) => {
+                query_scalar::<_, i32>("SELECT document_id FROM sync_documents WHERE remote_id = ?")
+                    .bind(parent_id)
+                    .fetch_optional(self.local.pool())
+                    .await?
+            }
+            None => None,
+        };
+
+        Ok(SyncedDocument {
+            parent_id,
+            position: revision.document.position,
+            title: revision.document.title.clone(),
+            content: revision.document.content.clone(),
+            deleted: revision.deleted,
+        })
+    }
+
+    async fn insert_local(&self, document: &SyncedDocument) -> Result<i32, Error> {
+        let mut tx = self.begin_applying().await?;
+
+        let id = query(
+            "INSERT INTO documents (parent_id, position, title, content) VALUES (?, ?, ?, ?)",
+        )
+        .bind(document.parent_id)
+        .bind(document.position)
+        .bind(&document.title)
+        .bind(&document.content)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid() as i32;
+
+        DocumentRepository::index_document(&mut tx, id, &document.model(id, document.parent_id))
+            .await?;
+        Self::commit_applying(tx).await?;
+
+        self.local.publish(DocumentChange::Inserted { id });
+        Ok(id)
+    }
+
+    async fn write_local(&self, id: i32, document: &SyncedDocument) -> Result<(), Error> {
+        let mut tx = self.begin_applying().await?;
+
+        let previous = query_as::<_, (String, bool)>(
+            "SELECT title, deleted_at IS NOT NULL FROM documents WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        query(
+            "UPDATE documents SET parent_id = ?, position = ?, title = ?, content = ?, \
+                deleted_at = CASE WHEN ? THEN COALESCE(deleted_at, CURRENT_TIMESTAMP) END \
+            WHERE id = ?",
+        )
+        .bind(document.parent_id)
+        .bind(document.position)
+        .bind(&document.title)
+        .bind(&document.content)
+        .bind(document.deleted)
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+
+        DocumentRepository::index_document(&mut tx, id, &document.model(id, document.parent_id))
+            .await?;
+        Self::commit_applying(tx).await?;
+
+        let change = match previous {
+            Some((_, deleted)) if deleted != document.deleted => {
+                if document.deleted {
+                    DocumentChange::Deleted { id }
+                } else {
+                    DocumentChange::Inserted { id }
+                }
+            }
+            Some((title, _)) if title != document.title => DocumentChange::Renamed {
+                id,
+                title: document.title.clone(),
+            },
+            _ => DocumentChange::Updated { id },
+        };
+        self.local.publish(change);
+
+        Ok(())
+    }
+
+    async fn delete_local(&self, id: i32) -> Result<(), Error> {
+        let mut tx = self.begin_applying().await?;
+
+        query("DELETE FROM documents WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        query("DELETE FROM document_search WHERE document_id = ?")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        Self::commit_applying(tx).await?;
+
+        self.local.publish(DocumentChange::Deleted { id });
+        Ok(())
+    }
+
+    /// A transaction whose writes are not queued in the outbox, coming from the remote side
+    async fn begin_applying(&self) -> Result<Transaction<'static, Sqlite>, Error> {
+        let mut tx = self.local.pool().begin().await?;
+        query("UPDATE sync_state SET applying = TRUE WHERE id = 1")
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(tx)
+    }
+
+    async fn commit_applying(mut tx: Transaction<'static, Sqlite>) -> Result<(), Error> {
+        query("UPDATE sync_state SET applying = FALSE WHERE id = 1")
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+use remindr_core::domain::database::attachment::AttachmentModel;
+
+#[test]
+fn attachments_are_keyed_by_their_content() {
+    let attachment = AttachmentModel::new("text/plain", b"hello".to_vec());
+    assert_eq!(
+        attachment.hash,
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+    assert!(!attachment.is_image());
+    assert!(AttachmentModel::new("image/png", vec![]).is_image());
+}
@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use remindr_core::collab::{
+    document::{CollabBlock, TextDelta, TextMark, TextUpdate},
+    peer::{CollabEvent, CollabPeer},
+};
+use serde_json::json;
+use tokio::{io::AsyncWriteExt, sync::broadcast::Receiver, time::timeout};
+
+fn text_block(id: &str, content: &str) -> CollabBlock {
+    CollabBlock {
+        id: id.to_string(),
+        depth: 0,
+        node: json!({
+            "id": id,
+            "type": "text",
+            "metadata": { "content": content, "spans": [] },
+        }),
+    }
+}
+
+const TOKEN: &str = "pairing-token";
+
+/// Two peers connected in process, the second one having received the first one's blocks
+async fn connected_peers(blocks: &[CollabBlock]) -> (CollabPeer, CollabPeer) {
+    let host = CollabPeer::new("Host", 0xe11d48).unwrap();
+    host.document().sync_blocks(blocks);
+
+    let guest = CollabPeer::new("Guest", 0x2563eb).unwrap();
+    let mut events = guest.subscribe();
+
+    let (host_stream, guest_stream) = tokio::io::duplex(64 * 1024);
+    host.accept(host_stream, TOKEN);
+    guest.connect(guest_stream, TOKEN);
+
+    wait_for(&mut events, CollabEvent::Synced).await;
+    (host, guest)
+}
+
+async fn wait_for(events: &mut Receiver<CollabEvent>, expected: CollabEvent) {
+    timeout(Duration::from_secs(5), async {
+        while events.recv().await.unwrap() != expected {}
+    })
+    .await
+    .expect("event not received in time");
+}
+
+#[tokio::test]
+async fn guest_receives_the_document() {
+    let blocks = vec![text_block("a", "Hello"), text_block("b", "World")];
+    let (_host, guest) = connected_peers(&blocks).await;
+
+    assert_eq!(guest.document().blocks(), blocks);
+}
+
+#[tokio::test]
+async fn links_are_shared_with_their_target() {
+    let mut block = text_block("a", "Read the docs");
+    block.node["metadata"]["spans"] = json!([
+        { "start": 0, "end": 4, "style": "Bold" },
+        { "start": 9, "end": 13, "style": { "Link": { "href": "https://example.com" } } },
+    ]);
+    let (_host, guest) = connected_peers(&[block.clone()]).await;
+
+    assert_eq!(guest.document().blocks(), vec![block]);
+}
+
+#[tokio::test]
+async fn concurrent_text_edits_are_merged() {
+    let (host, guest) = connected_peers(&[text_block("a", "Hello world")]).await;
+    let mut host_events = host.subscribe();
+    let mut guest_events = guest.subscribe();
+
+    // Both peers edit the same block before seeing the other's change
+    host.document().sync_text("a", "Hello big world", &[]);
+    guest.document().sync_text(
+        "a",
+        "Hello world!",
+        &[TextMark {
+            start: 0,
+            end: 5,
+            style: "Bold".to_string(),
+        }],
+    );
+
+    wait_for(&mut host_events, CollabEvent::RemoteChange).await;
+    wait_for(&mut guest_events, CollabEvent::RemoteChange).await;
+
+    let expected = (
+        "Hello big world!".to_string(),
+        vec![TextMark {
+            start: 0,
+            end: 5,
+            style: "Bold".to_string(),
+        }],
+    );
+    assert_eq!(host.document().text("a"), Some(expected.clone()));
+    assert_eq!(guest.document().text("a"), Some(expected));
+}
+
+#[tokio::test]
+async fn concurrent_block_insertions_are_kept() {
+    let (host, guest) = connected_peers(&[text_block("a", "First")]).await;
+    let mut host_events = host.subscribe();
+    let mut guest_events = guest.subscribe();
+
+    host.document()
+        .sync_blocks(&[text_block("a", "First"), text_block("b", "From the host")]);
+    guest
+        .document()
+        .sync_blocks(&[text_block("c", "From the guest"), text_block("a", "First")]);
+
+    wait_for(&mut host_events, CollabEvent::RemoteChange).await;
+    wait_for(&mut guest_events, CollabEvent::RemoteChange).await;
+
+    let ids = |peer: &CollabPeer| -> Vec<String> {
+        peer.document()
+            .blocks()
+            .into_iter()
+            .map(|block| block.id)
+            .collect()
+    };
+    assert_eq!(ids(&host), vec!["c", "a", "b"]);
+    assert_eq!(ids(&guest), ids(&host));
+}
+
+#[tokio::test]
+async fn cursors_are_shared() {
+    let (host, guest) = connected_peers(&[text_block("a", "Hello")]).await;
+    let mut guest_events = guest.subscribe();
+
+    host.set_cursor(Some("a".to_string()), 2, 4);
+    wait_for(&mut guest_events, CollabEvent::CursorsChanged).await;
+
+    let cursors = guest.cursors();
+    assert_eq!(cursors.len(), 1);
+    assert_eq!(cursors[0].name, "Host");
+    assert_eq!(cursors[0].block_id.as_deref(), Some("a"));
+    assert_eq!((cursors[0].anchor, cursors[0].head), (2, 4));
+}
+
+#[tokio::test]
+async fn deferred_updates_wait_to_be_applied() {
+    let (host, guest) = connected_peers(&[text_block("a", "Hello")]).await;
+    guest.defer_updates();
+    let mut guest_events = guest.subscribe();
+
+    host.document().sync_text("a", "Hello world", &[]);
+    wait_for(&mut guest_events, CollabEvent::RemoteChange).await;
+
+    assert_eq!(
+        guest.document().text("a"),
+        Some(("Hello".to_string(), vec![]))
+    );
+
+    let updates = guest.apply_pending().unwrap();
+    assert_eq!(
+        guest.document().text("a"),
+        Some(("Hello world".to_string(), vec![]))
+    );
+    assert_eq!(
+        updates,
+        vec![TextUpdate {
+            block_id: "a".to_string(),
+            delta: vec![
+                TextDelta::Retain {
+                    len: 5,
+                    styles: vec![]
+                },
+                TextDelta::Insert {
+                    text: " world".to_string(),
+                    styles: vec![]
+                },
+            ],
+        }]
+    );
+
+    // Formatting is sent as the styles set or removed on the retained text
+    host.document().sync_text(
+        "a",
+        "Hello world",
+        &[TextMark {
+            start: 6,
+            end: 11,
+            style: "Bold".to_string(),
+        }],
+    );
+    wait_for(&mut guest_events, CollabEvent::RemoteChange).await;
+    let updates = guest.apply_pending().unwrap();
+    assert_eq!(
+        updates[0].delta,
+        vec![
+            TextDelta::Retain {
+                len: 6,
+                styles: vec![]
+            },
+            TextDelta::Retain {
+                len: 5,
+                styles: vec![(json!("Bold"), true)]
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn peers_without_the_token_are_refused() {
+    let host = CollabPeer::new("Host", 0xe11d48).unwrap();
+    host.document().sync_blocks(&[text_block("a", "Secret")]);
+    let mut host_events = host.subscribe();
+
+    let guest = CollabPeer::new("Guest", 0x2563eb).unwrap();
+    let mut guest_events = guest.subscribe();
+
+    let (host_stream, guest_stream) = tokio::io::duplex(64 * 1024);
+    host.accept(host_stream, TOKEN);
+    guest.connect(guest_stream, "guessed");
+
+    wait_for(
+        &mut host_events,
+        CollabEvent::Disconnected {
+            error: Some("A peer tried to join without the pairing token".to_string()),
+        },
+    )
+    .await;
+    // The guest may see the connection closed or reset, depending on when it writes
+    let error = timeout(Duration::from_secs(5), async {
+        loop {
+            if let CollabEvent::Disconnected { error } = guest_events.recv().await.unwrap() {
+                return error;
+            }
+        }
+    })
+    .await
+    .expect("event not received in time");
+    assert!(
+        error
+            .unwrap()
+            .starts_with("The host refused the pairing token")
+    );
+    assert!(guest.document().blocks().is_empty());
+}
+
+#[tokio::test]
+async fn large_frames_are_refused_before_pairing() {
+    let host = CollabPeer::new("Host", 0xe11d48).unwrap();
+    let mut host_events = host.subscribe();
+
+    let (host_stream, mut stranger) = tokio::io::duplex(64 * 1024);
+    host.accept(host_stream, TOKEN);
+
+    // Only the length is sent, the host has to give up without waiting for the frame
+    stranger.write_u32(64 * 1024 * 1024).await.unwrap();
+    wait_for(
+        &mut host_events,
+        CollabEvent::Disconnected {
+            error: Some("frame of 67108864 bytes is too large".to_string()),
+        },
+    )
+    .await;
+}
@@ -0,0 +1,457 @@
+//! Behaviour every document repository must share, run against SQLite by
+//! `tests/repository.rs` and against Postgres by `tests/postgres.rs`, each check on a
+//! database of its own.
+
+use std::time::Duration;
+
+use remindr_core::{
+    Utils,
+    domain::database::{
+        attachment::AttachmentModel, change::DocumentChange, document::DocumentModel,
+    },
+    infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+use serde_json::{Value, json};
+use tokio::{sync::broadcast::Receiver, time::timeout};
+
+/// Longest wait for a change to be published, notifications going through the database
+const CHANGE_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn document(title: &str, parent_id: Option<i32>, content: Value) -> DocumentModel {
+    DocumentModel {
+        id: 0,
+        parent_id,
+        position: 0,
+        title: title.to_string(),
+        content,
+    }
+}
+
+fn text(id: &str, content: &str) -> Value {
+    json!([{ "id": id, "type": "text", "metadata": { "content": content, "spans": [] } }])
+}
+
+fn linking_to(id: i32) -> Value {
+    json!([{
+        "id": Utils::generate_uuid().to_string(),
+        "type": "text",
+        "metadata": {
+            "content": "See Target",
+            "spans": [{ "start": 4, "end": 10, "style": { "DocumentLink": { "id": id } } }],
+        },
+    }])
+}
+
+/// A word found in no other document
+fn unique_word() -> String {
+    format!("marker{}", Utils::generate_uuid().simple())
+}
+
+fn ids(documents: &[DocumentModel]) -> Vec<i32> {
+    documents.iter().map(|document| document.id).collect()
+}
+
+pub async fn documents_are_saved(repository: &AnyDocumentRepository) {
+    let id = repository
+        .insert_document(document("Draft", None, text("a", "First")))
+        .await
+        .unwrap();
+
+    let mut saved = repository.find_document(id).await.unwrap().unwrap();
+    assert_eq!(saved.title, "Draft");
+    assert_eq!(saved.content, text("a", "First"));
+
+    saved.title = "Final".to_string();
+    saved.content = text("a", "Second");
+    repository.update_document(saved).await.unwrap();
+    let updated = repository.get_document_by_id(id).await.unwrap();
+    assert_eq!(updated.title, "Final");
+    assert_eq!(updated.content, text("a", "Second"));
+}
+
+pub async fn trash_follows_sub_pages(repository: &AnyDocumentRepository) {
+    let parent = repository
+        .insert_document(document("Parent", None, json!([])))
+        .await
+        .unwrap();
+    let child = repository
+        .insert_document(document("Child", Some(parent), json!([])))
+        .await
+        .unwrap();
+    let grandchild = repository
+        .insert_document(document("Grandchild", Some(child), json!([])))
+        .await
+        .unwrap();
+
+    // Sub-pages go to the trash along with their parent, which alone is listed there
+    repository.delete_document(parent).await.unwrap();
+    for id in [parent, child, grandchild] {
+        assert!(repository.find_document(id).await.unwrap().is_none());
+    }
+    let trashed = ids(&repository.get_trashed_documents().await.unwrap());
+    assert!(trashed.contains(&parent));
+    assert!(!trashed.contains(&child));
+
+    let mut restored = repository.restore_document(parent).await.unwrap();
+    restored.sort();
+    assert_eq!(restored, vec![parent, child, grandchild]);
+    assert!(!ids(&repository.get_trashed_documents().await.unwrap()).contains(&parent));
+
+    // A page restored without its parent comes back at the top level
+    repository.delete_document(child).await.unwrap();
+    repository.delete_document(parent).await.unwrap();
+    let mut restored = repository.restore_document(child).await.unwrap();
+    restored.sort();
+    assert_eq!(restored, vec![child, grandchild]);
+    let child_page = repository.find_document(child).await.unwrap().unwrap();
+    assert_eq!(child_page.parent_id, None);
+    assert_eq!(
+        repository
+            .find_document(grandchild)
+            .await
+            .unwrap()
+            .unwrap()
+            .parent_id,
+        Some(child)
+    );
+
+    repository.delete_document_forever(parent).await.unwrap();
+    assert!(repository.get_document_by_id(parent).await.is_err());
+    assert!(!ids(&repository.get_trashed_documents().await.unwrap()).contains(&parent));
+}
+
+pub async fn search_matches_word_prefixes(repository: &AnyDocumentRepository) {
+    let word = unique_word();
+    let node_id = Utils::generate_uuid();
+    let id = repository
+        .insert_document(document(
+            "Meeting notes",
+            None,
+            text(
+                &node_id.to_string(),
+                &format!("Remember the {} budget", word),
+            ),
+        ))
+        .await
+        .unwrap();
+
+    // The end of the word is left out, the start being the same for words made together
+    let results = repository
+        .search_documents(&word[..word.len() - 4])
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].document_id, id);
+    assert_eq!(results[0].node_id, Some(node_id));
+    assert!(results[0].snippet.contains(&word));
+
+    // Every word has to match, titles included
+    let results = repository
+        .search_documents(&format!("{} meet", word))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 0);
+    let mut renamed = repository.get_document_by_id(id).await.unwrap();
+    renamed.title = format!("Meeting {}", word);
+    repository.update_document(renamed).await.unwrap();
+    let results = repository
+        .search_documents(&format!("{} meet", word))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].node_id, None);
+
+    // Nor edited out blocks, nor trashed documents are found
+    let mut edited = repository.get_document_by_id(id).await.unwrap();
+    edited.title = "Meeting notes".to_string();
+    edited.content = text(&node_id.to_string(), "Nothing left");
+    repository.update_document(edited).await.unwrap();
+    assert!(repository.search_documents(&word).await.unwrap().is_empty());
+
+    let trashed = repository
+        .insert_document(document(&word, None, json!([])))
+        .await
+        .unwrap();
+    assert_eq!(repository.search_documents(&word).await.unwrap().len(), 1);
+    repository.delete_document(trashed).await.unwrap();
+    assert!(repository.search_documents(&word).await.unwrap().is_empty());
+}
+
+pub async fn search_results_are_paged(repository: &AnyDocumentRepository) {
+    let word = unique_word();
+    for title in ["One", "Two", "Three"] {
+        repository
+            .insert_document(document(&format!("{} {}", title, word), None, json!([])))
+            .await
+            .unwrap();
+    }
+
+    let (first, total) = repository.search_documents_page(&word, 0, 2).await.unwrap();
+    assert_eq!(total, 3);
+    assert_eq!(first.len(), 2);
+
+    let (last, total) = repository.search_documents_page(&word, 2, 2).await.unwrap();
+    assert_eq!(total, 3);
+    assert_eq!(last.len(), 1);
+    assert!(
+        first
+            .iter()
+            .all(|result| result.document_id != last[0].document_id)
+    );
+}
+
+pub async fn pages_cannot_move_inside_themselves(repository: &AnyDocumentRepository) {
+    let root = repository
+        .insert_document(document("Root", None, json!([])))
+        .await
+        .unwrap();
+    let first = repository
+        .insert_document(document("First", Some(root), json!([])))
+        .await
+        .unwrap();
+    let second = repository
+        .insert_document(document("Second", Some(root), json!([])))
+        .await
+        .unwrap();
+    let nested = repository
+        .insert_document(document("Nested", Some(first), json!([])))
+        .await
+        .unwrap();
+
+    assert!(
+        repository
+            .move_document(root, Some(nested), 0)
+            .await
+            .is_err()
+    );
+    assert!(
+        repository
+            .move_document(first, Some(first), 0)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        repository
+            .find_document(root)
+            .await
+            .unwrap()
+            .unwrap()
+            .parent_id,
+        None
+    );
+
+    // Siblings are renumbered around the moved page
+    repository
+        .move_document(nested, Some(root), 0)
+        .await
+        .unwrap();
+    let children = |documents: Vec<DocumentModel>| {
+        let mut children: Vec<DocumentModel> = documents
+            .into_iter()
+            .filter(|document| document.parent_id == Some(root))
+            .collect();
+        children.sort_by_key(|document| document.position);
+        ids(&children)
+    };
+    assert_eq!(
+        children(repository.get_documents().await.unwrap()),
+        vec![nested, first, second]
+    );
+
+    repository
+        .move_document(nested, Some(second), 0)
+        .await
+        .unwrap();
+    assert_eq!(
+        children(repository.get_documents().await.unwrap()),
+        vec![first, second]
+    );
+    assert_eq!(
+        repository
+            .find_document(nested)
+            .await
+            .unwrap()
+            .unwrap()
+            .parent_id,
+        Some(second)
+    );
+}
+
+pub async fn backlinks_follow_saves(repository: &AnyDocumentRepository) {
+    let target = repository
+        .insert_document(document("Target", None, json!([])))
+        .await
+        .unwrap();
+    let source = repository
+        .insert_document(document("Source", None, linking_to(target)))
+        .await
+        .unwrap();
+    assert_eq!(
+        ids(&repository.get_backlinks(target).await.unwrap()),
+        vec![source]
+    );
+
+    let mut unlinked = document("Source", None, json!([]));
+    unlinked.id = source;
+    repository.update_document(unlinked).await.unwrap();
+    assert!(repository.get_backlinks(target).await.unwrap().is_empty());
+
+    let mut relinked = document("Source", None, linking_to(target));
+    relinked.id = source;
+    repository.update_document(relinked).await.unwrap();
+    repository.delete_document(source).await.unwrap();
+    assert!(repository.get_backlinks(target).await.unwrap().is_empty());
+}
+
+pub async fn attachments_are_stored_once(repository: &AnyDocumentRepository) {
+    let data = unique_word().into_bytes();
+    let attachment = AttachmentModel::new("text/plain", data);
+
+    let hash = repository
+        .insert_attachment(attachment.clone())
+        .await
+        .unwrap();
+    assert_eq!(
+        repository
+            .insert_attachment(attachment.clone())
+            .await
+            .unwrap(),
+        hash
+    );
+    assert_eq!(
+        repository.get_attachment_hashes().await.unwrap(),
+        vec![hash.clone()]
+    );
+    assert_eq!(
+        repository.get_attachment(&hash).await.unwrap(),
+        Some(attachment)
+    );
+    assert_eq!(repository.get_attachment("missing").await.unwrap(), None);
+}
+
+fn embedding(hashes: &[&str]) -> Value {
+    let images: Vec<Value> = hashes
+        .iter()
+        .map(|hash| {
+            json!({
+                "id": Utils::generate_uuid().to_string(),
+                "type": "image",
+                "metadata": { "hash": hash, "name": "picture.png", "mime_type": "image/png" },
+            })
+        })
+        .collect();
+
+    // Nested blocks embed attachments too
+    json!([{ "id": Utils::generate_uuid().to_string(), "type": "text", "metadata": { "content": "", "spans": [] }, "children": images }])
+}
+
+pub async fn unused_attachments_are_deleted(repository: &AnyDocumentRepository) {
+    let shared = AttachmentModel::new("image/png", unique_word().into_bytes());
+    let owned = AttachmentModel::new("image/png", unique_word().into_bytes());
+    let unsaved = AttachmentModel::new("image/png", unique_word().into_bytes());
+    for attachment in [&shared, &owned, &unsaved] {
+        repository
+            .insert_attachment(attachment.clone())
+            .await
+            .unwrap();
+    }
+
+    let parent = repository
+        .insert_document(document("Parent", None, json!([])))
+        .await
+        .unwrap();
+    repository
+        .insert_document(document(
+            "Child",
+            Some(parent),
+            embedding(&[&shared.hash, &owned.hash]),
+        ))
+        .await
+        .unwrap();
+    let other = repository
+        .insert_document(document("Other", None, embedding(&[&shared.hash])))
+        .await
+        .unwrap();
+
+    // Attachments still embedded elsewhere, or not embedded yet, are kept
+    repository.delete_document_forever(parent).await.unwrap();
+    assert_eq!(repository.get_attachment(&owned.hash).await.unwrap(), None);
+    assert!(
+        repository
+            .get_attachment(&shared.hash)
+            .await
+            .unwrap()
+            .is_some()
+    );
+    assert!(
+        repository
+            .get_attachment(&unsaved.hash)
+            .await
+            .unwrap()
+            .is_some()
+    );
+
+    repository.delete_document_forever(other).await.unwrap();
+    assert_eq!(repository.get_attachment(&shared.hash).await.unwrap(), None);
+}
+
+/// Next change published for the document `id`, the ones of other documents being skipped
+async fn next_change(changes: &mut Receiver<DocumentChange>, id: i32) -> DocumentChange {
+    timeout(CHANGE_TIMEOUT, async {
+        loop {
+            let change = changes.recv().await.unwrap();
+            if change.document_id() == id {
+                return change;
+            }
+        }
+    })
+    .await
+    .expect("no change was published")
+}
+
+pub async fn changes_are_published(repository: &AnyDocumentRepository) {
+    let mut changes = repository.subscribe();
+
+    // Postgres starts listening in the background, the first insert may not be seen
+    let id = loop {
+        let id = repository
+            .insert_document(document("Draft", None, json!([])))
+            .await
+            .unwrap();
+        let published = next_change(&mut changes, id);
+        if let Ok(change) = timeout(Duration::from_millis(500), published).await {
+            assert_eq!(change, DocumentChange::Inserted { id });
+            break id;
+        }
+    };
+
+    let mut renamed = document("Final", None, json!([]));
+    renamed.id = id;
+    repository.update_document(renamed.clone()).await.unwrap();
+    assert_eq!(
+        next_change(&mut changes, id).await,
+        DocumentChange::Renamed {
+            id,
+            title: "Final".to_string()
+        }
+    );
+
+    renamed.content = text("a", "Edited");
+    repository.update_document(renamed).await.unwrap();
+    assert_eq!(
+        next_change(&mut changes, id).await,
+        DocumentChange::Updated { id }
+    );
+
+    repository.delete_document(id).await.unwrap();
+    assert_eq!(
+        next_change(&mut changes, id).await,
+        DocumentChange::Deleted { id }
+    );
+
+    repository.restore_document(id).await.unwrap();
+    assert_eq!(
+        next_change(&mut changes, id).await,
+        DocumentChange::Inserted { id }
+    );
+}
@@ -0,0 +1,64 @@
+use remindr_core::{
+    Utils, domain::database::document::DocumentModel, infrastructure::markdown::MarkdownConverter,
+};
+use serde_json::{Value, json};
+
+fn document(title: &str, content: Value) -> DocumentModel {
+    DocumentModel {
+        id: 0,
+        parent_id: None,
+        position: 0,
+        title: title.to_string(),
+        content,
+    }
+}
+
+fn linking_to(id: i32) -> Value {
+    json!([{
+        "id": Utils::generate_uuid().to_string(),
+        "type": "text",
+        "metadata": {
+            "content": "See Target",
+            "spans": [{ "start": 4, "end": 10, "style": { "DocumentLink": { "id": id } } }],
+        },
+    }])
+}
+
+#[test]
+fn links_are_read_from_nested_blocks() {
+    let mut parent = linking_to(3)[0].clone();
+    parent["children"] = linking_to(1);
+
+    let document = document("Source", json!([parent, linking_to(3)[0]]));
+    assert_eq!(document.linked_documents(), vec![1, 3]);
+}
+
+#[test]
+fn hyperlinks_round_trip_through_markdown() {
+    let content = json!([{
+        "id": Utils::generate_uuid().to_string(),
+        "type": "text",
+        "metadata": {
+            "content": "Read the docs today",
+            "spans": [
+                { "start": 5, "end": 13, "style": { "Link": { "href": "https://example.com/a b" } } },
+                { "start": 9, "end": 13, "style": "Bold" },
+            ],
+        },
+    }]);
+
+    let markdown = MarkdownConverter::export(&document("Notes", content));
+    assert_eq!(
+        markdown,
+        "# Notes\n\nRead [the **docs**](<https://example.com/a b>) today\n"
+    );
+
+    let imported = MarkdownConverter::import(&markdown, "Untitled");
+    let spans = &imported.content[0]["metadata"]["spans"];
+    assert!(
+        spans
+            .as_array()
+            .unwrap()
+            .contains(&json!({ "start": 5, "end": 13, "style": { "Link": { "href": "https://example.com/a b" } } }))
+    );
+}
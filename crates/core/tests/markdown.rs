@@ -0,0 +1,270 @@
+use remindr_core::{
+    Utils, domain::database::document::DocumentModel, infrastructure::markdown::MarkdownConverter,
+};
+use serde_json::{Value, json};
+
+fn document(title: &str, content: Value) -> DocumentModel {
+    DocumentModel {
+        id: 0,
+        parent_id: None,
+        position: 0,
+        title: title.to_string(),
+        content,
+    }
+}
+
+fn node(node_type: &str, metadata: Value) -> Value {
+    json!({
+        "id": Utils::generate_uuid().to_string(),
+        "type": node_type,
+        "metadata": metadata,
+    })
+}
+
+fn text(content: &str, spans: Value) -> Value {
+    node("text", json!({ "content": content, "spans": spans }))
+}
+
+fn item(node_type: &str, content: &str, checked: bool) -> Value {
+    node(
+        node_type,
+        json!({ "content": content, "spans": [], "checked": checked }),
+    )
+}
+
+/// Export `content`, import the Markdown back and export it again. Both exports must match,
+/// the imported document being returned for the checks on its nodes.
+fn round_trip(content: Value) -> (String, DocumentModel) {
+    let markdown = MarkdownConverter::export(&document("Notes", content));
+    let imported = MarkdownConverter::import(&markdown, "Untitled");
+    assert_eq!(imported.title, "Notes");
+    assert_eq!(MarkdownConverter::export(&imported), markdown);
+
+    (markdown, imported)
+}
+
+/// Spans of a node, sorted so they can be compared whatever order they were read in
+fn spans(node: &Value) -> Vec<Value> {
+    let mut spans = node["metadata"]["spans"].as_array().unwrap().clone();
+    spans.sort_by_key(|span| (span["start"].as_u64(), span["end"].as_u64()));
+    spans
+}
+
+#[test]
+fn inline_styles_round_trip() {
+    let spans_in = json!([
+        { "start": 0, "end": 4, "style": "Bold" },
+        { "start": 5, "end": 11, "style": "Italic" },
+        { "start": 12, "end": 16, "style": "Code" },
+        { "start": 17, "end": 21, "style": { "Link": { "href": "https://example.com" } } },
+    ]);
+    let (markdown, imported) = round_trip(json!([text("bold italic code link", spans_in.clone())]));
+
+    assert_eq!(
+        markdown,
+        "# Notes\n\n**bold** _italic_ `code` [link](https://example.com)\n"
+    );
+    assert_eq!(
+        imported.content[0]["metadata"]["content"],
+        "bold italic code link"
+    );
+    assert_eq!(
+        spans(&imported.content[0]),
+        spans_in.as_array().unwrap().clone()
+    );
+}
+
+#[test]
+fn overlapping_styles_round_trip() {
+    let spans_in = json!([
+        { "start": 0, "end": 9, "style": "Bold" },
+        { "start": 5, "end": 14, "style": "Italic" },
+        { "start": 5, "end": 9, "style": { "Link": { "href": "https://example.com/a b" } } },
+    ]);
+    let (markdown, imported) = round_trip(json!([text("some text here", spans_in)]));
+
+    // Emphasis cannot start on a space, which is left out of the reopened style
+    assert_eq!(
+        markdown,
+        "# Notes\n\n**some [_text_](<https://example.com/a b>)** _here_\n"
+    );
+    let spans = spans(&imported.content[0]);
+    assert!(spans.contains(&json!({ "start": 0, "end": 9, "style": "Bold" })));
+    assert!(spans.contains(&json!({ "start": 10, "end": 14, "style": "Italic" })));
+    assert!(spans.contains(
+        &json!({ "start": 5, "end": 9, "style": { "Link": { "href": "https://example.com/a b" } } })
+    ));
+}
+
+#[test]
+fn headings_keep_their_level() {
+    let headings: Vec<Value> = (1..=6)
+        .map(|level| {
+            node(
+                "heading",
+                json!({ "content": format!("Level {}", level), "level": level }),
+            )
+        })
+        .collect();
+    let (markdown, imported) = round_trip(Value::from(headings));
+
+    assert!(markdown.contains("\n\n# Level 1\n\n## Level 2\n"));
+    assert!(markdown.contains("\n\n###### Level 6\n"));
+    let levels: Vec<u64> = imported
+        .content
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|node| node["metadata"]["level"].as_u64().unwrap())
+        .collect();
+    assert_eq!(levels, vec![1, 2, 3, 4, 5, 6]);
+}
+
+#[test]
+fn dividers_round_trip() {
+    let content = json!([
+        text("Before", json!([])),
+        { "id": Utils::generate_uuid().to_string(), "type": "divider" },
+        text("After", json!([])),
+    ]);
+    let (markdown, imported) = round_trip(content);
+
+    assert_eq!(markdown, "# Notes\n\nBefore\n\n---\n\nAfter\n");
+    assert_eq!(imported.content[1]["type"], "divider");
+}
+
+#[test]
+fn nested_lists_round_trip() {
+    let mut first = item("numbered_list", "First", false);
+    let mut nested = item("bulleted_list", "Nested", false);
+    nested["children"] = json!([item("bulleted_list", "Deeper", false)]);
+    first["children"] = json!([nested, item("bulleted_list", "Sibling", false)]);
+    let content = json!([first, item("numbered_list", "Second", false)]);
+
+    let (markdown, imported) = round_trip(content);
+
+    assert_eq!(
+        markdown,
+        "# Notes\n\n1. First\n\n   - Nested\n\n     - Deeper\n\n   - Sibling\n\n2. Second\n"
+    );
+    let first = &imported.content[0];
+    assert_eq!(first["type"], "numbered_list");
+    assert_eq!(first["children"][0]["type"], "bulleted_list");
+    assert_eq!(
+        first["children"][0]["children"][0]["metadata"]["content"],
+        "Deeper"
+    );
+    assert_eq!(first["children"][1]["metadata"]["content"], "Sibling");
+    assert_eq!(imported.content[1]["metadata"]["content"], "Second");
+}
+
+#[test]
+fn todos_keep_their_state() {
+    let content = json!([item("todo", "Done", true), item("todo", "Pending", false),]);
+    let (markdown, imported) = round_trip(content);
+
+    assert_eq!(markdown, "# Notes\n\n- [x] Done\n- [ ] Pending\n");
+    assert_eq!(imported.content[0]["type"], "todo");
+    assert_eq!(imported.content[0]["metadata"]["checked"], true);
+    assert_eq!(imported.content[1]["metadata"]["checked"], false);
+}
+
+#[test]
+fn code_blocks_round_trip() {
+    let content = json!([
+        node(
+            "code",
+            json!({ "content": "fn main() {\n    println!(\"hi\");\n}", "language": "rust" })
+        ),
+        node(
+            "code",
+            json!({ "content": "a ``` fence", "language": "text" })
+        ),
+    ]);
+    let (markdown, imported) = round_trip(content);
+
+    assert!(markdown.contains("```rust\nfn main() {\n    println!(\"hi\");\n}\n```"));
+    assert!(markdown.contains("````\na ``` fence\n````"));
+    assert_eq!(imported.content[0]["metadata"]["language"], "rust");
+    assert_eq!(
+        imported.content[0]["metadata"]["content"],
+        "fn main() {\n    println!(\"hi\");\n}"
+    );
+    assert_eq!(imported.content[1]["metadata"]["language"], "text");
+    assert_eq!(imported.content[1]["metadata"]["content"], "a ``` fence");
+}
+
+#[test]
+fn file_names_are_valid_paths() {
+    assert_eq!(MarkdownConverter::file_name("Notes"), "Notes.md");
+    assert_eq!(
+        MarkdownConverter::file_name("2026/10/17: Standup"),
+        "2026-10-17- Standup.md"
+    );
+    assert_eq!(MarkdownConverter::file_name("../secret"), "-secret.md");
+    assert_eq!(MarkdownConverter::file_name(" .. "), "Untitled.md");
+    assert_eq!(MarkdownConverter::file_name("a\nb"), "a b.md");
+}
+
+#[test]
+fn ordered_list_markers_are_escaped() {
+    let (markdown, imported) = round_trip(json!([
+        text("1. Not a list", json!([])),
+        text("2024) Nor this", json!([])),
+    ]));
+    assert_eq!(markdown, "# Notes\n\n1\\. Not a list\n\n2024\\) Nor this\n");
+
+    let nodes = imported.content.as_array().unwrap();
+    assert_eq!(nodes[0]["type"], "text");
+    assert_eq!(nodes[0]["metadata"]["content"], "1. Not a list");
+    assert_eq!(nodes[1]["metadata"]["content"], "2024) Nor this");
+}
+
+#[test]
+fn attachments_are_exported_as_links() {
+    let hash = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+    let image = node(
+        "image",
+        json!({ "hash": hash, "name": "photo.png", "mime_type": "image/png" }),
+    );
+    let mut parent = text("Report", json!([]));
+    parent["children"] = json!([node(
+        "file",
+        json!({ "hash": hash, "name": "a:b.pdf", "mime_type": "application/pdf", "size": 5 }),
+    )]);
+
+    let document = document("Notes", json!([image, parent]));
+    assert_eq!(
+        MarkdownConverter::export(&document),
+        "# Notes\n\n![photo.png](attachments/2cf24dba5fb0-photo.png)\n\nReport\n\n\
+         [a:b.pdf](attachments/2cf24dba5fb0-a-b.pdf)\n"
+    );
+    assert_eq!(
+        MarkdownConverter::attachments(&document),
+        vec![
+            (
+                hash.to_string(),
+                "attachments/2cf24dba5fb0-a-b.pdf".to_string()
+            ),
+            (
+                hash.to_string(),
+                "attachments/2cf24dba5fb0-photo.png".to_string()
+            ),
+        ]
+    );
+}
+
+#[test]
+fn unknown_blocks_are_not_lost() {
+    let document = document(
+        "Notes",
+        json!([
+            node("callout", json!({ "content": "Keep this", "spans": [] })),
+            node("embed", json!({})),
+        ]),
+    );
+    assert_eq!(
+        MarkdownConverter::export(&document),
+        "# Notes\n\nKeep this\n\n<!-- embed block -->\n"
+    );
+}
@@ -0,0 +1,165 @@
+//! Runs the repository contract against the Postgres server at `DATABASE_URL`, each test on a
+//! database of its own. The tests are ignored unless asked for, e.g.
+//! `DATABASE_URL=postgres://localhost/postgres cargo test --test postgres -- --ignored`,
+//! and fail when the variable is not set.
+
+mod contract;
+
+use std::str::FromStr;
+
+use remindr_core::{
+    Utils, domain::database::document::DocumentModel,
+    infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+use serde_json::json;
+use sqlx::{ConnectOptions, Connection, Executor, PgConnection, postgres::PgConnectOptions};
+
+/// Fresh database on the server at `DATABASE_URL`, dropped once the check is done
+struct Database {
+    server_url: String,
+    name: String,
+    url: String,
+    repository: AnyDocumentRepository,
+}
+
+impl Database {
+    async fn open() -> Self {
+        let server_url = std::env::var("DATABASE_URL")
+            .expect("DATABASE_URL has to point at the Postgres server to test against");
+        let name = format!("remindr_test_{}", Utils::generate_uuid().simple());
+
+        let mut server = PgConnection::connect(&server_url).await.unwrap();
+        server
+            .execute(format!("CREATE DATABASE {}", name).as_str())
+            .await
+            .unwrap();
+
+        let url = PgConnectOptions::from_str(&server_url)
+            .unwrap()
+            .database(&name)
+            .to_url_lossy()
+            .to_string();
+        let repository = AnyDocumentRepository::connect_postgres(&url).await.unwrap();
+
+        Self {
+            server_url,
+            name,
+            url,
+            repository,
+        }
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        let server_url = self.server_url.clone();
+        let drop_database = format!("DROP DATABASE IF EXISTS {} WITH (FORCE)", self.name);
+
+        // The test runtime may be shutting down, the database is dropped from a runtime of its own
+        let _ = std::thread::spawn(move || {
+            tokio::runtime::Runtime::new().unwrap().block_on(async {
+                if let Ok(mut server) = PgConnection::connect(&server_url).await {
+                    let _ = server.execute(drop_database.as_str()).await;
+                }
+            })
+        })
+        .join();
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn documents_are_saved() {
+    let database = Database::open().await;
+    contract::documents_are_saved(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn trash_follows_sub_pages() {
+    let database = Database::open().await;
+    contract::trash_follows_sub_pages(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn search_matches_word_prefixes() {
+    let database = Database::open().await;
+    contract::search_matches_word_prefixes(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn search_results_are_paged() {
+    let database = Database::open().await;
+    contract::search_results_are_paged(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn pages_cannot_move_inside_themselves() {
+    let database = Database::open().await;
+    contract::pages_cannot_move_inside_themselves(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn backlinks_follow_saves() {
+    let database = Database::open().await;
+    contract::backlinks_follow_saves(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn attachments_are_stored_once() {
+    let database = Database::open().await;
+    contract::attachments_are_stored_once(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn unused_attachments_are_deleted() {
+    let database = Database::open().await;
+    contract::unused_attachments_are_deleted(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn changes_are_published() {
+    let database = Database::open().await;
+    contract::changes_are_published(&database.repository).await;
+}
+
+#[tokio::test]
+#[ignore = "needs DATABASE_URL"]
+async fn shared_trash_is_not_purged() {
+    let database = Database::open().await;
+    let repository = &database.repository;
+    let id = repository
+        .insert_document(DocumentModel {
+            id: 0,
+            parent_id: None,
+            position: 0,
+            title: "Someone else's".to_string(),
+            content: json!([]),
+        })
+        .await
+        .unwrap();
+    repository.delete_document(id).await.unwrap();
+
+    let mut connection = PgConnection::connect(&database.url).await.unwrap();
+    connection
+        .execute(
+            format!(
+                "UPDATE documents SET deleted_at = now() - interval '30 days' WHERE id = {}",
+                id
+            )
+            .as_str(),
+        )
+        .await
+        .unwrap();
+
+    repository.purge_trash(1).await.unwrap();
+    let trashed = repository.get_trashed_documents().await.unwrap();
+    assert!(trashed.iter().any(|document| document.id == id));
+}
@@ -0,0 +1,85 @@
+mod contract;
+
+use std::path::PathBuf;
+
+use remindr_core::{
+    Utils, infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+
+/// Fresh SQLite database, removed once the check is done
+struct Database {
+    path: PathBuf,
+    repository: AnyDocumentRepository,
+}
+
+impl Database {
+    async fn open() -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "remindr-repository-{}.sqlite",
+            Utils::generate_uuid()
+        ));
+        let repository = AnyDocumentRepository::connect_sqlite(&path).await.unwrap();
+
+        Self { path, repository }
+    }
+}
+
+impl Drop for Database {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[tokio::test]
+async fn documents_are_saved() {
+    let database = Database::open().await;
+    contract::documents_are_saved(&database.repository).await;
+}
+
+#[tokio::test]
+async fn trash_follows_sub_pages() {
+    let database = Database::open().await;
+    contract::trash_follows_sub_pages(&database.repository).await;
+}
+
+#[tokio::test]
+async fn search_matches_word_prefixes() {
+    let database = Database::open().await;
+    contract::search_matches_word_prefixes(&database.repository).await;
+}
+
+#[tokio::test]
+async fn search_results_are_paged() {
+    let database = Database::open().await;
+    contract::search_results_are_paged(&database.repository).await;
+}
+
+#[tokio::test]
+async fn pages_cannot_move_inside_themselves() {
+    let database = Database::open().await;
+    contract::pages_cannot_move_inside_themselves(&database.repository).await;
+}
+
+#[tokio::test]
+async fn backlinks_follow_saves() {
+    let database = Database::open().await;
+    contract::backlinks_follow_saves(&database.repository).await;
+}
+
+#[tokio::test]
+async fn attachments_are_stored_once() {
+    let database = Database::open().await;
+    contract::attachments_are_stored_once(&database.repository).await;
+}
+
+#[tokio::test]
+async fn unused_attachments_are_deleted() {
+    let database = Database::open().await;
+    contract::unused_attachments_are_deleted(&database.repository).await;
+}
+
+#[tokio::test]
+async fn changes_are_published() {
+    let database = Database::open().await;
+    contract::changes_are_published(&database.repository).await;
+}
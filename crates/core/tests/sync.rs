@@ -0,0 +1,129 @@
+use std::time::Duration;
+
+use remindr_core::{
+    Utils,
+    domain::database::document::DocumentModel,
+    infrastructure::{
+        repositories::any_document_repository::AnyDocumentRepository,
+        sync::{SyncStatus, merge::merge_blocks},
+    },
+};
+use serde_json::{Value, json};
+use tokio::time::timeout;
+
+fn text(id: &str, content: &str) -> Value {
+    json!({
+        "id": id,
+        "type": "text",
+        "metadata": { "content": content, "spans": [] },
+    })
+}
+
+fn contents(blocks: &Value) -> Vec<&str> {
+    blocks
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|block| block["metadata"]["content"].as_str().unwrap())
+        .collect()
+}
+
+#[test]
+fn edits_of_different_blocks_are_both_kept() {
+    let base = json!([text("a", "One"), text("b", "Two")]);
+    let local = json!([text("a", "One, edited here"), text("b", "Two")]);
+    let remote = json!([text("a", "One"), text("b", "Two, edited there")]);
+
+    let merged = merge_blocks(&base, &local, &remote);
+    assert_eq!(
+        contents(&merged),
+        vec!["One, edited here", "Two, edited there"]
+    );
+}
+
+#[test]
+fn local_edit_wins_on_the_same_block() {
+    let base = json!([text("a", "One")]);
+    let local = json!([text("a", "Local")]);
+    let remote = json!([text("a", "Remote")]);
+
+    let merged = merge_blocks(&base, &local, &remote);
+    assert_eq!(contents(&merged), vec!["Local"]);
+}
+
+#[test]
+fn blocks_added_on_both_sides_follow_their_anchor() {
+    let base = json!([text("a", "One"), text("b", "Two")]);
+    let local = json!([text("a", "One"), text("l", "Local"), text("b", "Two")]);
+    let remote = json!([text("a", "One"), text("b", "Two"), text("r", "Remote")]);
+
+    let merged = merge_blocks(&base, &local, &remote);
+    assert_eq!(contents(&merged), vec!["One", "Local", "Two", "Remote"]);
+}
+
+#[test]
+fn edited_block_survives_a_removal() {
+    let base = json!([text("a", "One"), text("b", "Two")]);
+    let local = json!([text("a", "One")]);
+    let remote = json!([text("a", "One"), text("b", "Two, edited there")]);
+
+    let merged = merge_blocks(&base, &local, &remote);
+    assert_eq!(contents(&merged), vec!["One", "Two, edited there"]);
+
+    let unchanged = json!([text("a", "One"), text("b", "Two")]);
+    let merged = merge_blocks(&base, &local, &unchanged);
+    assert_eq!(contents(&merged), vec!["One"]);
+}
+
+#[test]
+fn children_keep_their_parent() {
+    let mut parent = text("a", "Parent");
+    parent["children"] = json!([text("c", "Child")]);
+    let base = json!([parent.clone(), text("b", "Two")]);
+
+    let mut edited_parent = parent.clone();
+    edited_parent["children"] = json!([text("c", "Child, edited there")]);
+    let remote = json!([edited_parent, text("b", "Two")]);
+    let local = json!([parent, text("b", "Two, edited here")]);
+
+    let merged = merge_blocks(&base, &local, &remote);
+    assert_eq!(contents(&merged), vec!["Parent", "Two, edited here"]);
+    assert_eq!(
+        merged[0]["children"][0]["metadata"]["content"],
+        "Child, edited there"
+    );
+}
+
+#[tokio::test]
+async fn offline_copy_queues_local_changes() {
+    let path = std::env::temp_dir().join(format!("remindr-sync-{}.sqlite", Utils::generate_uuid()));
+    // Nothing listens on port 1, the remote database is out of reach
+    let repository =
+        AnyDocumentRepository::connect_replica(&path, "postgres://remindr@127.0.0.1:1/remindr")
+            .await
+            .unwrap();
+
+    let id = repository
+        .insert_document(DocumentModel {
+            id: 0,
+            parent_id: None,
+            position: 0,
+            title: "Written offline".to_string(),
+            content: json!([text("a", "One")]),
+        })
+        .await
+        .unwrap();
+    assert!(repository.find_document(id).await.unwrap().is_some());
+
+    let mut status = repository.sync().unwrap().status();
+    timeout(Duration::from_secs(30), async {
+        while *status.borrow_and_update() != (SyncStatus::Offline { pending: 1 }) {
+            status.changed().await.unwrap();
+        }
+    })
+    .await
+    .expect("offline status not reported in time");
+
+    drop(repository);
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,216 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Error, bail};
+use gpui::{App, Entity, Image, ImageFormat, PathPromptOptions, Window};
+use gpui_component::WindowExt;
+use uuid::Uuid;
+
+use crate::{
+    app::{
+        components::nodes::{
+            element::{NodePayload, RemindrElement},
+            file::data::FileMetadata,
+            image::data::ImageMetadata,
+        },
+        states::{
+            document_state::DocumentState, node_state::NodeState, repository_state::RepositoryState,
+        },
+    },
+    domain::database::attachment::AttachmentModel,
+    infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+
+/// Largest file that can be attached, the whole workspace living in a single database
+const MAX_ATTACHMENT_SIZE: usize = 100 * 1024 * 1024;
+
+/// Asks for files and inserts them after the block `after`, or at the end of the document
+pub fn pick_files(
+    after: Option<Uuid>,
+    state: &Entity<NodeState>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let paths = cx.prompt_for_paths(PathPromptOptions {
+        files: true,
+        directories: false,
+        multiple: true,
+        prompt: Some("Insert".into()),
+    });
+
+    let state = state.clone();
+    window
+        .spawn(cx, async move |cx| {
+            let Ok(Ok(Some(paths))) = paths.await else {
+                return;
+            };
+
+            let _ = cx.update(|window, cx| insert_files(paths, after, &state, window, cx));
+        })
+        .detach();
+}
+
+/// Stores the files at `paths` in the workspace and inserts a block for each of them after
+/// `after`, the images being shown inline
+pub fn insert_files(
+    paths: Vec<PathBuf>,
+    after: Option<Uuid>,
+    state: &Entity<NodeState>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let repository = cx.global::<RepositoryState>().documents.clone();
+    let state = state.clone();
+
+    window
+        .spawn(cx, async move |cx| {
+            let mut after = after;
+            for path in paths {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "Untitled".to_string());
+
+                let result = async {
+                    let data = smol::fs::read(&path).await?;
+                    store(&repository, name.clone(), mime_type(&path), data).await
+                }
+                .await;
+
+                let inserted = cx.update(|window, cx| match result {
+                    Ok(payload) => Some(insert_block(payload, after, &state, window, cx)),
+                    Err(error) => {
+                        window.push_notification(
+                            format!("Could not attach \"{}\": {}", name, error),
+                            cx,
+                        );
+                        None
+                    }
+                });
+
+                // The next files follow the one just inserted
+                if let Ok(Some(id)) = inserted {
+                    after = Some(id);
+                }
+            }
+        })
+        .detach();
+}
+
+/// Stores an image pasted from the clipboard and inserts it after the block `after`
+pub fn insert_image(
+    image: Image,
+    after: Uuid,
+    state: &Entity<NodeState>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    let repository = cx.global::<RepositoryState>().documents.clone();
+    let state = state.clone();
+
+    window
+        .spawn(cx, async move |cx| {
+            let result = store(
+                &repository,
+                "Pasted image".to_string(),
+                image.format.mime_type(),
+                image.bytes,
+            )
+            .await;
+
+            let _ = cx.update(|window, cx| match result {
+                Ok(payload) => {
+                    insert_block(payload, Some(after), &state, window, cx);
+                }
+                Err(error) => {
+                    window.push_notification(format!("Could not paste the image: {}", error), cx)
+                }
+            });
+        })
+        .detach();
+}
+
+/// Writes the file in the attachments of the workspace, returning the block showing it
+async fn store(
+    repository: &AnyDocumentRepository,
+    name: String,
+    mime_type: &str,
+    data: Vec<u8>,
+) -> Result<NodePayload, Error> {
+    if data.len() > MAX_ATTACHMENT_SIZE {
+        bail!(
+            "files are limited to {} MB",
+            MAX_ATTACHMENT_SIZE / 1024 / 1024
+        );
+    }
+
+    let size = data.len() as u64;
+    let attachment = AttachmentModel::new(mime_type, data);
+    // Pictures in a format that cannot be drawn are kept as plain files
+    let is_image = attachment.is_image() && ImageFormat::from_mime_type(mime_type).is_some();
+    let hash = repository.insert_attachment(attachment).await?;
+
+    let mime_type = mime_type.to_string();
+    Ok(if is_image {
+        NodePayload::Image(ImageMetadata {
+            hash,
+            name,
+            mime_type,
+            width: None,
+        })
+    } else {
+        NodePayload::File(FileMetadata {
+            hash,
+            name,
+            mime_type,
+            size,
+        })
+    })
+}
+
+/// Inserts the block of an attachment, at the end of the document when `after` is gone
+fn insert_block(
+    payload: NodePayload,
+    after: Option<Uuid>,
+    state: &Entity<NodeState>,
+    window: &mut Window,
+    cx: &mut App,
+) -> Uuid {
+    let node = RemindrElement::create_node(payload, state, window, cx);
+
+    state.update(cx, |state, _| {
+        match after.filter(|id| state.get_current_nodes(*id).is_some()) {
+            Some(id) => state.insert_node_after(id, &node),
+            None => state.push_node(&node),
+        }
+    });
+    cx.update_global::<DocumentState, _>(|state, cx| {
+        state.mark_changed(window, cx);
+    });
+
+    node.id
+}
+
+/// Media type of a file from its extension
+fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        _ => "application/octet-stream",
+    }
+}
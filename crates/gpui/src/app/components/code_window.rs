@@ -1,69 +1,195 @@
-use gpui::*;
+use std::{collections::HashSet, time::Duration};
+
+use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    Root,
-    input::{Input, InputState, TabSize},
+    ActiveTheme, Disableable, Root, Sizable,
+    button::{Button, ButtonVariants},
+    h_flex,
+    input::{Input, InputEvent, InputState, Position, TabSize},
+    scroll::ScrollableElement,
+    v_flex,
 };
-use serde_json::to_string_pretty;
+use serde_json::{Deserializer, Value, to_string_pretty};
 
 use crate::{
     LoadingState,
-    app::{
-        components::nodes::{element::RemindrElement, node::RemindrNode},
-        states::document_state::DocumentState,
+    app::states::{
+        document_state::DocumentState, node_state::NodeState, repository_state::RepositoryState,
     },
+    domain::database::change::DocumentChange,
 };
 
+/// Error found in the JSON source, positions starting at 0
+struct SourceError {
+    line: u32,
+    column: u32,
+    message: String,
+}
+
+/// JSON source of a document, edited alongside the main window
 pub struct CodeWindow {
     editor_state: Entity<InputState>,
     document_id: i32,
-    last_buffer: String,
+    /// Window displaying the document, its nodes are created there
+    main_window: AnyWindowHandle,
+    /// Source as last loaded from or applied to the document
+    base_buffer: String,
+    errors: Vec<SourceError>,
+    /// The document changed in the main window while the source had edits of its own
+    outdated: bool,
+    _validate_task: Task<()>,
 }
 
 impl CodeWindow {
-    fn new(editor_state: Entity<InputState>, document_id: i32, initial_buffer: String) -> Self {
+    fn new(
+        editor_state: Entity<InputState>,
+        document_id: i32,
+        main_window: AnyWindowHandle,
+        initial_buffer: String,
+    ) -> Self {
         Self {
             editor_state,
             document_id,
-            last_buffer: initial_buffer,
+            main_window,
+            base_buffer: initial_buffer,
+            errors: Vec::new(),
+            outdated: false,
+            _validate_task: Task::ready(()),
         }
     }
 
-    fn build_code_buffer(nodes: &[RemindrNode], cx: &App) -> String {
+    fn build_code_buffer(nodes: &[Value]) -> String {
         let mut buffer = String::new();
 
         for node in nodes {
-            let node_json = match &node.element {
-                RemindrElement::Text(node) => to_string_pretty(&node.read(cx).data).unwrap(),
-                RemindrElement::Heading(node) => to_string_pretty(&node.read(cx).data).unwrap(),
-                RemindrElement::Divider(node) => to_string_pretty(&node.read(cx).data).unwrap(),
-            };
-            buffer.push_str(&node_json);
+            buffer.push_str(&to_string_pretty(node).unwrap());
             buffer.push('\n');
         }
 
         buffer
     }
 
-    fn get_current_buffer(&self, cx: &App) -> Option<String> {
+    fn get_node_state(&self, cx: &App) -> Option<Entity<NodeState>> {
         cx.read_global::<DocumentState, _>(|state, cx| {
             state
                 .documents
                 .iter()
                 .find(|d| d.uid == self.document_id)
-                .and_then(|doc| {
-                    if let LoadingState::Loaded(content) = &doc.state {
-                        Some(Self::build_code_buffer(
-                            &content.renderer.read(cx).state.read(cx).get_nodes(),
-                            cx,
-                        ))
-                    } else {
-                        None
-                    }
+                .and_then(|doc| match &doc.state {
+                    LoadingState::Loaded(content) => Some(content.renderer.read(cx).state.clone()),
+                    _ => None,
                 })
         })
     }
 
-    pub fn open(title: String, document_id: i32, nodes: Vec<RemindrNode>, cx: &mut App) {
+    fn get_current_buffer(&self, cx: &App) -> Option<String> {
+        self.get_node_state(cx)
+            .map(|state| Self::build_code_buffer(&state.read(cx).serialize_nodes(cx)))
+    }
+
+    fn has_pending_edits(&self, cx: &App) -> bool {
+        self.editor_state.read(cx).value().as_ref() != self.base_buffer
+    }
+
+    fn load_buffer(&mut self, buffer: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.base_buffer = buffer.clone();
+        self.outdated = false;
+        self.errors.clear();
+        self.editor_state.update(cx, |state, cx| {
+            state.set_value(buffer, window, cx);
+        });
+        cx.notify();
+    }
+
+    /// Follow the document as edited in the main window, unless the source has edits of its own
+    fn refresh(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(buffer) = self.get_current_buffer(cx) else {
+            return;
+        };
+        if buffer == self.base_buffer {
+            return;
+        }
+
+        if self.has_pending_edits(cx) {
+            self.outdated = true;
+            cx.notify();
+        } else {
+            self.load_buffer(buffer, window, cx);
+        }
+    }
+
+    /// Drop the edits of the source and load the document as it is now
+    fn reload(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(buffer) = self.get_current_buffer(cx) {
+            self.load_buffer(buffer, window, cx);
+        }
+    }
+
+    fn validate(&mut self, cx: &mut Context<Self>) {
+        let source = self.editor_state.read(cx).value().to_string();
+
+        // Replacing the task cancels the previous validation, which debounces typing
+        self._validate_task = cx.spawn(async move |this, cx| {
+            smol::Timer::after(Duration::from_millis(300)).await;
+
+            let errors = parse_source(&source).err().unwrap_or_default();
+            let _ = this.update(cx, |this, cx| {
+                this.errors = errors;
+                cx.notify();
+            });
+        });
+    }
+
+    /// Write the source back into the document. Nothing is applied while the source is
+    /// invalid, or when the document changed in the main window since the source was loaded.
+    fn apply(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let source = self.editor_state.read(cx).value().to_string();
+        let nodes = match parse_source(&source) {
+            Ok(nodes) => nodes,
+            Err(errors) => {
+                self.errors = errors;
+                cx.notify();
+                return;
+            }
+        };
+
+        let Some(state) = self.get_node_state(cx) else {
+            return;
+        };
+        if self.get_current_buffer(cx).as_ref() != Some(&self.base_buffer) {
+            self.outdated = true;
+            cx.notify();
+            return;
+        }
+
+        let document_id = self.document_id;
+        let _ = cx.update_window(self.main_window, |_, window, cx| {
+            state.update(cx, |node_state, cx| {
+                node_state.replace_nodes(&nodes, &state, window, cx);
+                cx.notify();
+            });
+            cx.update_global::<DocumentState, _>(|doc_state, cx| {
+                doc_state.mark_document_changed(document_id, window, cx);
+            });
+        });
+
+        self.reload(window, cx);
+    }
+
+    fn go_to_error(&self, line: u32, column: u32, window: &mut Window, cx: &mut Context<Self>) {
+        self.editor_state.update(cx, |state, cx| {
+            state.focus(window, cx);
+            state.set_cursor_position(Position::new(line, column), window, cx);
+        });
+    }
+
+    pub fn open(
+        title: String,
+        document_id: i32,
+        nodes: Vec<Value>,
+        main_window: AnyWindowHandle,
+        cx: &mut App,
+    ) {
         let window_size = size(px(600.), px(500.));
         let window_bounds = Bounds::centered(None, window_size, cx);
 
@@ -89,7 +215,7 @@ impl CodeWindow {
 
             let window = cx
                 .open_window(options, |window, cx| {
-                    let editor_buffer = Self::build_code_buffer(&nodes, cx);
+                    let editor_buffer = Self::build_code_buffer(&nodes);
                     let editor_state = cx.new(|cx| {
                         InputState::new(window, cx)
                             .code_editor("json")
@@ -101,13 +227,40 @@ impl CodeWindow {
                             })
                             .default_value(editor_buffer.clone())
                     });
+                    let changes = cx.global::<RepositoryState>().changes.clone();
                     let code_window = cx.new(|cx| {
-                        cx.observe_global::<DocumentState>(|_this: &mut CodeWindow, cx| {
-                            cx.notify();
-                        })
+                        // Refreshed each time the document is saved
+                        cx.subscribe_in(
+                            &changes,
+                            window,
+                            move |this: &mut CodeWindow, _, change: &DocumentChange, window, cx| {
+                                if change.document_id() != document_id {
+                                    return;
+                                }
+
+                                match change {
+                                    DocumentChange::Renamed { title, .. } => {
+                                        window.set_window_title(&format!("Code - {}", title));
+                                        this.refresh(window, cx);
+                                    }
+                                    DocumentChange::Deleted { .. } => window.remove_window(),
+                                    _ => this.refresh(window, cx),
+                                }
+                            },
+                        )
                         .detach();
 
-                        CodeWindow::new(editor_state, document_id, editor_buffer)
+                        cx.subscribe(
+                            &editor_state,
+                            |this: &mut CodeWindow, _, event: &InputEvent, cx| {
+                                if let InputEvent::Change = event {
+                                    this.validate(cx);
+                                }
+                            },
+                        )
+                        .detach();
+
+                        CodeWindow::new(editor_state, document_id, main_window, editor_buffer)
                     });
                     cx.new(|cx| Root::new(code_window, window, cx))
                 })
@@ -124,25 +277,189 @@ impl CodeWindow {
         })
         .detach();
     }
+
+    fn render_errors(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let errors: Vec<AnyElement> = self
+            .errors
+            .iter()
+            .enumerate()
+            .map(|(index, error)| {
+                let (line, column) = (error.line, error.column);
+
+                div()
+                    .id(("source-error", index))
+                    .px_2()
+                    .py_0p5()
+                    .rounded_sm()
+                    .cursor_pointer()
+                    .text_xs()
+                    .text_color(cx.theme().danger)
+                    .hover(|this| this.bg(cx.theme().accent))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        this.go_to_error(line, column, window, cx);
+                    }))
+                    .child(format!("Line {}: {}", line + 1, error.message))
+                    .into_any_element()
+            })
+            .collect();
+
+        div()
+            .id("source-errors")
+            .max_h(px(120.))
+            .overflow_y_scrollbar()
+            .child(v_flex().children(errors))
+    }
 }
 
 impl Render for CodeWindow {
-    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        // Only update if content has changed
-        if let Some(new_buffer) = self.get_current_buffer(cx) {
-            if new_buffer != self.last_buffer {
-                self.last_buffer = new_buffer.clone();
-                self.editor_state.update(cx, |state, cx| {
-                    state.set_value(new_buffer, window, cx);
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_pending_edits = self.has_pending_edits(cx);
+        let can_apply = has_pending_edits && self.errors.is_empty() && !self.outdated;
+
+        let status = if self.outdated {
+            "The document changed in the main window, reload it to continue".to_string()
+        } else if !self.errors.is_empty() {
+            format!(
+                "{} error(s), fix them to apply the changes",
+                self.errors.len()
+            )
+        } else if has_pending_edits {
+            "Unapplied changes".to_string()
+        } else {
+            "Up to date".to_string()
+        };
+
+        v_flex()
+            .pt_8()
+            .size_full()
+            .child(
+                div()
+                    .flex_1()
+                    .min_h_0()
+                    .child(Input::new(&self.editor_state).appearance(false).size_full()),
+            )
+            .when(!self.errors.is_empty(), |this| {
+                this.child(
+                    div()
+                        .border_t_1()
+                        .border_color(cx.theme().border)
+                        .p_1()
+                        .child(self.render_errors(cx)),
+                )
+            })
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_2()
+                    .border_t_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(status),
+                    )
+                    .child(
+                        Button::new("reload-source")
+                            .xsmall()
+                            .ghost()
+                            .label("Reload")
+                            .disabled(!has_pending_edits && !self.outdated)
+                            .on_click(cx.listener(|this, _, window, cx| this.reload(window, cx))),
+                    )
+                    .child(
+                        Button::new("apply-source")
+                            .xsmall()
+                            .primary()
+                            .label("Apply")
+                            .disabled(!can_apply)
+                            .on_click(cx.listener(|this, _, window, cx| this.apply(window, cx))),
+                    ),
+            )
+    }
+}
+
+/// Parse the source as a sequence of nodes, arrays of nodes being accepted as well.
+/// Parsing stops at the first syntax error, schema errors are collected for every node.
+fn parse_source(source: &str) -> Result<Vec<Value>, Vec<SourceError>> {
+    let mut nodes = Vec::new();
+    let mut errors = Vec::new();
+    let mut ids = HashSet::new();
+
+    let mut stream = Deserializer::from_str(source).into_iter::<Value>();
+    let mut offset = 0;
+    while let Some(result) = stream.next() {
+        let value = match result {
+            Ok(value) => value,
+            Err(error) => {
+                let message = error.to_string();
+                errors.push(SourceError {
+                    line: error.line().saturating_sub(1) as u32,
+                    column: error.column().saturating_sub(1) as u32,
+                    // The position is shown apart
+                    message: message
+                        .split(" at line ")
+                        .next()
+                        .unwrap_or(&message)
+                        .to_string(),
                 });
+                break;
+            }
+        };
+
+        let rest = &source[offset..];
+        let (line, column) = position_of(source, offset + rest.len() - rest.trim_start().len());
+        offset = stream.byte_offset();
+
+        let values = match value {
+            Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let error = match NodeState::validate_node(&value) {
+                Err(error) => Some(error.to_string()),
+                Ok(()) => duplicate_id(&value, &mut ids).map(|id| format!("duplicate id {}", id)),
+            };
+
+            match error {
+                Some(message) => errors.push(SourceError {
+                    line,
+                    column,
+                    message,
+                }),
+                None => nodes.push(value),
             }
         }
+    }
 
-        div().pt_8().size_full().child(
-            Input::new(&self.editor_state)
-                .disabled(true)
-                .appearance(false)
-                .size_full(),
-        )
+    if errors.is_empty() {
+        Ok(nodes)
+    } else {
+        Err(errors)
     }
 }
+
+/// First id of a node, or of its children, already seen in the source
+fn duplicate_id(value: &Value, ids: &mut HashSet<String>) -> Option<String> {
+    let id = value["id"].as_str().unwrap_or_default().to_string();
+    if !ids.insert(id.clone()) {
+        return Some(id);
+    }
+
+    value["children"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|child| duplicate_id(child, ids))
+}
+
+/// Line and column of a byte offset
+fn position_of(source: &str, offset: usize) -> (u32, u32) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let column = before.len() - before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+
+    (line as u32, column as u32)
+}
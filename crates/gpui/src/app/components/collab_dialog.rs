@@ -0,0 +1,289 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    h_flex,
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::app::states::{
+    collab_state::{CollabRole, CollabState, DEFAULT_COLLAB_PORT},
+    node_state::NodeState,
+};
+
+/// Hosting or joining the collaboration session of a document, and its collaborators
+pub struct CollabPanel {
+    uid: i32,
+    node_state: Entity<NodeState>,
+    port_input: Entity<InputState>,
+    /// Whether the other computers of the network may join, not only this one
+    expose: bool,
+    address_input: Entity<InputState>,
+    token_input: Entity<InputState>,
+    connecting: bool,
+    _session_observer: Option<Subscription>,
+    _state_observer: Subscription,
+}
+
+impl CollabPanel {
+    pub fn new(
+        uid: i32,
+        node_state: Entity<NodeState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let port_input =
+            cx.new(|cx| InputState::new(window, cx).default_value(DEFAULT_COLLAB_PORT.to_string()));
+        let address_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("192.168.1.20:7410"));
+        let token_input = cx.new(|cx| InputState::new(window, cx).placeholder("Pairing token"));
+
+        let state_observer = cx.observe_global::<CollabState>(|this, cx| {
+            this.observe_session(cx);
+            cx.notify();
+        });
+
+        let mut panel = Self {
+            uid,
+            node_state,
+            port_input,
+            expose: false,
+            address_input,
+            token_input,
+            connecting: false,
+            _session_observer: None,
+            _state_observer: state_observer,
+        };
+        panel.observe_session(cx);
+        panel
+    }
+
+    fn observe_session(&mut self, cx: &mut Context<Self>) {
+        self._session_observer = cx
+            .global::<CollabState>()
+            .session(self.uid)
+            .map(|session| cx.observe(&session, |_, _, cx| cx.notify()));
+    }
+
+    fn host(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let port = self.port_input.read(cx).value().trim().to_string();
+        let Ok(port) = port.parse::<u16>() else {
+            window.push_notification(format!("\"{}\" is not a valid port", port), cx);
+            return;
+        };
+
+        let task = CollabState::host(
+            self.uid,
+            self.node_state.clone(),
+            port,
+            self.expose,
+            window,
+            cx,
+        );
+        self.connect(task, window, cx);
+    }
+
+    fn join(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let address = self.address_input.read(cx).value().trim().to_string();
+        if address.is_empty() {
+            window.push_notification("The address of the host is required", cx);
+            return;
+        }
+
+        let token = self.token_input.read(cx).value().trim().to_string();
+        if token.is_empty() {
+            window.push_notification("The pairing token of the host is required", cx);
+            return;
+        }
+
+        let task = CollabState::join(
+            self.uid,
+            self.node_state.clone(),
+            address,
+            token,
+            window,
+            cx,
+        );
+        self.connect(task, window, cx);
+    }
+
+    fn connect<T: 'static>(
+        &mut self,
+        task: Task<anyhow::Result<T>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.connecting = true;
+        cx.notify();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = task.await;
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.connecting = false;
+                if let Err(error) = result {
+                    window.push_notification(format!("Could not connect: {:#}", error), cx);
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn render_session(&self, cx: &mut Context<Self>) -> Option<AnyElement> {
+        let session = cx.global::<CollabState>().session(self.uid)?;
+        let session = session.read(cx);
+        let muted = cx.theme().muted_foreground;
+
+        let (status, token) = match &session.role {
+            CollabRole::Host { address, token } if address.ip().is_loopback() => (
+                format!("Shared on port {}, on this computer only", address.port()),
+                Some(token.clone()),
+            ),
+            CollabRole::Host { address, token } => (
+                format!(
+                    "Shared on port {}, join with this computer's address",
+                    address.port()
+                ),
+                Some(token.clone()),
+            ),
+            CollabRole::Guest(address) => (format!("Connected to {}", address), None),
+        };
+        let collaborators = session.collaborators();
+
+        Some(
+            v_flex()
+                .gap_2()
+                .child(div().child(status))
+                .children(token.map(|token| {
+                    div()
+                        .text_color(muted)
+                        .child(format!("Pairing token: {}", token))
+                }))
+                .child(div().text_color(muted).child(if collaborators.is_empty() {
+                    "Nobody else is editing yet".to_string()
+                } else {
+                    format!("{} editing", collaborators.len() + 1)
+                }))
+                .children(collaborators.into_iter().map(|collaborator| {
+                    h_flex()
+                        .gap_2()
+                        .items_center()
+                        .child(
+                            div()
+                                .size(px(8.))
+                                .rounded_full()
+                                .bg(rgb(collaborator.color)),
+                        )
+                        .child(collaborator.name)
+                }))
+                .child(
+                    h_flex().justify_end().child(
+                        Button::new("collab-stop")
+                            .small()
+                            .danger()
+                            .label("Stop")
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                CollabState::stop(this.uid, cx);
+                            })),
+                    ),
+                )
+                .into_any_element(),
+        )
+    }
+}
+
+impl Render for CollabPanel {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if let Some(session) = self.render_session(cx) {
+            return v_flex().w_full().text_xs().child(session);
+        }
+
+        let label_color = cx.theme().muted_foreground;
+
+        v_flex()
+            .w_full()
+            .gap_2()
+            .text_xs()
+            .child(div().text_color(label_color).child("Host on port"))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(Input::new(&self.port_input).small())
+                    .child(
+                        Button::new("collab-host")
+                            .small()
+                            .primary()
+                            .label("Host")
+                            .disabled(self.connecting)
+                            .on_click(cx.listener(|this, _, window, cx| this.host(window, cx))),
+                    ),
+            )
+            .child(
+                Checkbox::new("collab-expose")
+                    .label("Let other computers of the network join")
+                    .checked(self.expose)
+                    .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                        this.expose = *checked;
+                        cx.notify();
+                    })),
+            )
+            .child(div().text_color(label_color).child("Join a host"))
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(Input::new(&self.address_input).small())
+                    .child(
+                        Button::new("collab-join")
+                            .small()
+                            .label("Join")
+                            .disabled(self.connecting)
+                            .on_click(cx.listener(|this, _, window, cx| this.join(window, cx))),
+                    ),
+            )
+            .child(Input::new(&self.token_input).small())
+            .child(
+                div()
+                    .text_color(label_color)
+                    .child("Joining replaces this document with the one of the host"),
+            )
+            .when(self.connecting, |this| {
+                this.child(div().text_color(label_color).child("Connecting…"))
+            })
+    }
+}
+
+/// Dialog sharing a document with collaborators on the local network
+pub struct CollabDialog;
+
+impl CollabDialog {
+    pub fn open(uid: i32, node_state: Entity<NodeState>, window: &mut Window, cx: &mut App) {
+        let panel = cx.new(|cx| CollabPanel::new(uid, node_state, window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .w(px(340.))
+                .pt(px(12.))
+                .pb(px(12.))
+                .px(px(14.))
+                .title(v_flex().text_sm().font_semibold().child("Collaborate"))
+                .close_button(false)
+                .overlay_closable(true)
+                .footer(|_ok_btn, _cancel_btn, _window, _cx| {
+                    vec![
+                        Button::new("close")
+                            .small()
+                            .ghost()
+                            .label("Close")
+                            .on_click(|_: &ClickEvent, window: &mut Window, cx: &mut App| {
+                                window.close_dialog(cx);
+                            })
+                            .into_element()
+                            .into_any(),
+                    ]
+                })
+                .child(h_flex().w_full().child(panel.clone()))
+        });
+    }
+}
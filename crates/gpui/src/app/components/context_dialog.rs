@@ -0,0 +1,183 @@
+use std::rc::Rc;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Sizable, StyledExt, WindowExt,
+    button::{Button, ButtonVariants},
+    checkbox::Checkbox,
+    h_flex,
+    input::{Input, InputState},
+    v_flex,
+};
+
+use crate::domain::entities::settings::{DbContext, LocalDatabase, RemoteDatabase};
+
+/// Form used to describe a new database context (local SQLite file or remote Postgres).
+pub struct ContextForm {
+    name_input: Entity<InputState>,
+    location_input: Entity<InputState>,
+    remote: bool,
+    offline: bool,
+}
+
+impl ContextForm {
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let name_input = cx.new(|cx| InputState::new(window, cx).placeholder("Team notes"));
+        let location_input = cx.new(|cx| {
+            InputState::new(window, cx).placeholder("~/notes.sqlite or postgres://host/db")
+        });
+
+        Self {
+            name_input,
+            location_input,
+            remote: false,
+            offline: false,
+        }
+    }
+
+    fn set_remote(&mut self, remote: bool, cx: &mut Context<Self>) {
+        self.remote = remote;
+        cx.notify();
+    }
+
+    /// Build the context from the form, `None` while a field is still empty.
+    pub fn context(&self, cx: &App) -> Option<DbContext> {
+        let name = self.name_input.read(cx).value().trim().to_string();
+        let location = self.location_input.read(cx).value().trim().to_string();
+
+        if name.is_empty() || location.is_empty() {
+            return None;
+        }
+
+        if self.remote {
+            Some(DbContext::Remote(RemoteDatabase {
+                name,
+                url: location,
+                offline: self.offline,
+            }))
+        } else {
+            let path = match location.strip_prefix("~/") {
+                Some(relative) => dirs::home_dir()
+                    .map(|home| home.join(relative).to_string_lossy().to_string())
+                    .unwrap_or(location),
+                None => location,
+            };
+
+            Some(DbContext::Local(LocalDatabase { name, path }))
+        }
+    }
+}
+
+impl Render for ContextForm {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let label_color = cx.theme().muted_foreground;
+        let location_label = if self.remote {
+            "Postgres connection URL"
+        } else {
+            "SQLite file path"
+        };
+
+        v_flex()
+            .gap_2()
+            .text_xs()
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Button::new("context-local")
+                            .small()
+                            .label("Local")
+                            .when(!self.remote, |button| button.primary())
+                            .when(self.remote, |button| button.ghost())
+                            .on_click(cx.listener(|this, _, _, cx| this.set_remote(false, cx))),
+                    )
+                    .child(
+                        Button::new("context-remote")
+                            .small()
+                            .label("Remote")
+                            .when(self.remote, |button| button.primary())
+                            .when(!self.remote, |button| button.ghost())
+                            .on_click(cx.listener(|this, _, _, cx| this.set_remote(true, cx))),
+                    ),
+            )
+            .child(div().text_color(label_color).child("Name"))
+            .child(Input::new(&self.name_input).small())
+            .child(div().text_color(label_color).child(location_label))
+            .child(Input::new(&self.location_input).small())
+            .when(self.remote, |this| {
+                this.child(
+                    Checkbox::new("context-offline")
+                        .label("Keep an offline copy")
+                        .checked(self.offline)
+                        .on_click(cx.listener(|this, checked: &bool, _, cx| {
+                            this.offline = *checked;
+                            cx.notify();
+                        })),
+                )
+            })
+    }
+}
+
+/// Dialog asking for a new database context.
+pub struct ContextDialog;
+
+impl ContextDialog {
+    /// Open the dialog, `on_add` is called with the context once the form is valid.
+    pub fn open<F>(on_add: F, window: &mut Window, cx: &mut App)
+    where
+        F: Fn(DbContext, &mut Window, &mut App) + 'static,
+    {
+        let form = cx.new(|cx| ContextForm::new(window, cx));
+        let on_add = Rc::new(on_add);
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let form = form.clone();
+            let on_add = on_add.clone();
+
+            dialog
+                .w(px(340.))
+                .pt(px(12.))
+                .pb(px(12.))
+                .px(px(14.))
+                .title(v_flex().text_sm().font_semibold().child("Add workspace"))
+                .close_button(false)
+                .overlay_closable(true)
+                .footer(move |_ok_btn, _cancel_btn, _window, _cx| {
+                    let form = form.clone();
+                    let on_add = on_add.clone();
+
+                    vec![
+                        Button::new("cancel")
+                            .small()
+                            .ghost()
+                            .label("Cancel")
+                            .on_click(|_: &ClickEvent, window: &mut Window, cx: &mut App| {
+                                window.close_dialog(cx);
+                            })
+                            .into_element()
+                            .into_any(),
+                        Button::new("confirm")
+                            .small()
+                            .primary()
+                            .label("Add")
+                            .on_click(move |_: &ClickEvent, window: &mut Window, cx: &mut App| {
+                                let context = form.read(cx).context(cx);
+                                match context {
+                                    Some(context) => {
+                                        window.close_dialog(cx);
+                                        on_add(context, window, cx);
+                                    }
+                                    None => window.push_notification(
+                                        "A name and a location are required",
+                                        cx,
+                                    ),
+                                }
+                            })
+                            .into_element()
+                            .into_any(),
+                    ]
+                })
+                .child(h_flex().w_full().child(form.clone()))
+        });
+    }
+}
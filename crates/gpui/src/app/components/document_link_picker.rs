@@ -0,0 +1,211 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, Sizable, WindowExt, h_flex,
+    input::{Input, InputEvent, InputState, MoveDown, MoveUp},
+    scroll::ScrollableElement,
+    v_flex,
+};
+
+use crate::app::{components::rich_text::RichTextState, states::document_state::DocumentState};
+
+/// Number of documents listed at once, the search narrowing them down
+const MAX_RESULTS: usize = 50;
+
+/// Picks the document a link typed with `[[` points to
+pub struct DocumentLinkPicker {
+    rich_text: Entity<RichTextState>,
+    search_input: Entity<InputState>,
+    selected_index: usize,
+}
+
+impl DocumentLinkPicker {
+    pub fn new(
+        rich_text: Entity<RichTextState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Link to..."));
+
+        cx.subscribe_in(
+            &search_input,
+            window,
+            |this, _, event: &InputEvent, window, cx| match event {
+                InputEvent::Change => {
+                    this.selected_index = 0;
+                    cx.notify();
+                }
+                InputEvent::PressEnter { .. } => this.confirm_selection(window, cx),
+                _ => {}
+            },
+        )
+        .detach();
+
+        Self {
+            rich_text,
+            search_input,
+            selected_index: 0,
+        }
+    }
+
+    /// Open the picker in a dialog, the link being inserted in `rich_text`
+    pub fn open(rich_text: Entity<RichTextState>, window: &mut Window, cx: &mut App) {
+        let picker = cx.new(|cx| DocumentLinkPicker::new(rich_text, window, cx));
+        let search_input = picker.read(cx).search_input.clone();
+        search_input.update(cx, |input, cx| input.focus(window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .w(px(420.))
+                .p(px(8.))
+                .close_button(false)
+                .overlay_closable(true)
+                .child(picker.clone())
+        });
+    }
+
+    /// Documents of the workspace whose title matches the search, the current one left out
+    fn documents(&self, cx: &App) -> Vec<(i32, String)> {
+        let search = self.search_input.read(cx).value().trim().to_lowercase();
+        let state = cx.global::<DocumentState>();
+
+        let mut documents: Vec<(i32, String)> = state
+            .outline
+            .iter()
+            .filter(|(id, _)| state.current_opened_document != Some(**id))
+            .filter(|(_, document)| document.title.to_lowercase().contains(search.as_str()))
+            .map(|(id, document)| (*id, document.title.clone()))
+            .collect();
+        documents.sort_by(|a, b| {
+            a.1.to_lowercase()
+                .cmp(&b.1.to_lowercase())
+                .then(a.0.cmp(&b.0))
+        });
+        documents.truncate(MAX_RESULTS);
+
+        documents
+    }
+
+    fn move_selection(&mut self, down: bool, cx: &mut Context<Self>) {
+        let count = self.documents(cx).len();
+        if count == 0 {
+            return;
+        }
+
+        let last = count - 1;
+        self.selected_index = match (down, self.selected_index) {
+            (true, index) if index >= last => 0,
+            (true, index) => index + 1,
+            (false, 0) => last,
+            (false, index) => index - 1,
+        };
+        cx.notify();
+    }
+
+    fn confirm_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some((id, title)) = self.documents(cx).get(self.selected_index).cloned() {
+            self.insert_link(id, title, window, cx);
+        }
+    }
+
+    fn insert_link(&mut self, id: i32, title: String, window: &mut Window, cx: &mut Context<Self>) {
+        window.close_dialog(cx);
+
+        let title = if title.trim().is_empty() {
+            "Untitled".to_string()
+        } else {
+            title
+        };
+        self.rich_text.update(cx, |rich_text, cx| {
+            rich_text.insert_document_link(id, &title, cx);
+            rich_text.focus(window, cx);
+        });
+    }
+
+    fn render_document(
+        &self,
+        index: usize,
+        id: i32,
+        title: String,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_selected = self.selected_index == index;
+
+        h_flex()
+            .id(("link-document", index))
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .items_center()
+            .rounded_md()
+            .cursor_pointer()
+            .when(is_selected, |this| this.bg(cx.theme().accent.opacity(0.5)))
+            .hover(|this| this.bg(cx.theme().accent.opacity(0.5)))
+            .child(
+                Icon::default()
+                    .path("icons/file-text.svg")
+                    .size_4()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .min_w_0()
+                    .text_sm()
+                    .text_ellipsis()
+                    .overflow_hidden()
+                    .child(title.clone()),
+            )
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.insert_link(id, title.clone(), window, cx);
+            }))
+    }
+}
+
+impl Render for DocumentLinkPicker {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let documents: Vec<AnyElement> = self
+            .documents(cx)
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, title))| {
+                self.render_document(index, id, title, cx)
+                    .into_any_element()
+            })
+            .collect();
+
+        v_flex()
+            .w_full()
+            .gap_2()
+            .on_action(cx.listener(|this, _: &MoveUp, _, cx| {
+                this.move_selection(false, cx);
+            }))
+            .on_action(cx.listener(|this, _: &MoveDown, _, cx| {
+                this.move_selection(true, cx);
+            }))
+            .child(
+                Input::new(&self.search_input)
+                    .prefix(Icon::default().path("icons/link.svg").small())
+                    .cleanable(true),
+            )
+            .when(documents.is_empty(), |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No documents"),
+                )
+            })
+            .when(!documents.is_empty(), |this| {
+                this.child(
+                    div()
+                        .id("link-documents")
+                        .max_h(px(320.))
+                        .overflow_y_scrollbar()
+                        .child(v_flex().children(documents)),
+                )
+            })
+    }
+}
@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    v_flex,
+};
+
+use crate::app::components::rich_text::RichTextState;
+
+/// Edits the hyperlink of the selected text, opened with cmd-k
+pub struct LinkEditor {
+    rich_text: Entity<RichTextState>,
+    /// Text being linked, `None` when the link is inserted at the cursor
+    range: Option<Range<usize>>,
+    is_linked: bool,
+    href_input: Entity<InputState>,
+}
+
+impl LinkEditor {
+    pub fn new(
+        rich_text: Entity<RichTextState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let (range, href) = {
+            let state = rich_text.read(cx);
+            let (start, end) = state.selection().normalized();
+            let link = state.link_at(start);
+
+            match (start == end, link) {
+                // A cursor within a link edits the whole link
+                (true, Some((range, href))) => (Some(range), Some(href)),
+                (true, None) => (None, None),
+                (false, link) => (Some(start..end), link.map(|(_, href)| href)),
+            }
+        };
+
+        let href_input = cx.new(|cx| {
+            InputState::new(window, cx)
+                .placeholder("Paste or type a link...")
+                .default_value(href.clone().unwrap_or_default())
+        });
+
+        cx.subscribe_in(
+            &href_input,
+            window,
+            |this, _, event: &InputEvent, window, cx| {
+                if let InputEvent::PressEnter { .. } = event {
+                    this.confirm(window, cx);
+                }
+            },
+        )
+        .detach();
+
+        Self {
+            rich_text,
+            range,
+            is_linked: href.is_some(),
+            href_input,
+        }
+    }
+
+    /// Open the editor in a dialog for the selection of `rich_text`
+    pub fn open(rich_text: Entity<RichTextState>, window: &mut Window, cx: &mut App) {
+        let editor = cx.new(|cx| LinkEditor::new(rich_text, window, cx));
+        let href_input = editor.read(cx).href_input.clone();
+        href_input.update(cx, |input, cx| input.focus(window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .w(px(420.))
+                .p(px(8.))
+                .close_button(false)
+                .overlay_closable(true)
+                .child(editor.clone())
+        });
+    }
+
+    fn confirm(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let href = self.href_input.read(cx).value().trim().to_string();
+        let href = match href.as_str() {
+            "" => None,
+            // Addresses typed without a scheme are taken as web pages
+            href if href.contains("://") || href.starts_with("mailto:") => Some(href.to_string()),
+            href => Some(format!("https://{}", href)),
+        };
+
+        self.apply(href, window, cx);
+    }
+
+    fn apply(&mut self, href: Option<String>, window: &mut Window, cx: &mut Context<Self>) {
+        window.close_dialog(cx);
+
+        let range = self.range.clone();
+        self.rich_text.update(cx, |rich_text, cx| {
+            match (range, href) {
+                (Some(range), href) => rich_text.set_link(range, href, cx),
+                (None, Some(href)) => rich_text.insert_link(&href, cx),
+                (None, None) => {}
+            }
+            rich_text.focus(window, cx);
+        });
+    }
+}
+
+impl Render for LinkEditor {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex().w_full().gap_2().child(
+            h_flex()
+                .gap_1()
+                .child(
+                    div().flex_1().child(
+                        Input::new(&self.href_input)
+                            .prefix(
+                                Icon::default()
+                                    .path("icons/link.svg")
+                                    .small()
+                                    .text_color(cx.theme().muted_foreground),
+                            )
+                            .cleanable(true),
+                    ),
+                )
+                .when(self.is_linked, |this| {
+                    this.child(
+                        Button::new("link-remove")
+                            .ghost()
+                            .small()
+                            .label("Remove")
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.apply(None, window, cx);
+                            })),
+                    )
+                })
+                .child(
+                    Button::new("link-apply")
+                        .primary()
+                        .small()
+                        .label("Apply")
+                        .on_click(cx.listener(|this, _, window, cx| this.confirm(window, cx))),
+                ),
+        )
+    }
+}
@@ -1,9 +1,17 @@
+pub mod attachments;
 pub mod code_window;
+pub mod collab_dialog;
 pub mod confirm_dialog;
+pub mod context_dialog;
+pub mod document_link_picker;
+pub mod link_editor;
 pub mod node_config_menu;
 pub mod node_renderer;
 pub mod nodes;
 pub mod rich_text;
+pub mod search_palette;
 pub mod sidebar;
 pub mod slash_menu;
+pub mod style_toolbar;
 pub mod title_bar;
+pub mod version_history;
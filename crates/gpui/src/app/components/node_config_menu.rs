@@ -4,7 +4,7 @@ use uuid::Uuid;
 
 use crate::app::{
     components::{node_renderer::DraggableInfo, nodes::menu_provider::NodeMenuItem},
-    states::node_state::NodeState,
+    states::{document_state::DocumentState, node_state::NodeState},
 };
 
 const DESTRUCTIVE_COLOR: Hsla = Hsla {
@@ -43,10 +43,23 @@ impl NodeConfigMenu {
         cx.notify();
     }
 
-    fn delete_node(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
-        self.state.update(cx, |state, _| {
+    fn delete_node(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let previous = self.state.update(cx, |state, cx| {
+            let previous = state.get_previous_node(self.related_id);
             state.remove_node(self.related_id);
+            cx.notify();
+            previous
         });
+
+        // Keep the focus inside the document so the deletion can be undone right away
+        if let Some(previous) = previous {
+            previous.element.focus_end(window, cx);
+        }
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+
         self.open = false;
         cx.notify();
     }
@@ -5,6 +5,7 @@ use uuid::Uuid;
 
 use crate::app::{
     components::{
+        attachments,
         node_config_menu::NodeConfigMenu,
         nodes::{
             element::{NodePayload, RemindrElement},
@@ -15,10 +16,35 @@ use crate::app::{
     states::node_state::{MovingElement, NodeState},
 };
 
+/// Horizontal offset applied for each nesting level
+const INDENT_WIDTH: f32 = 24.0;
+
+actions!(node_renderer, [Undo, Redo]);
+
+const CONTEXT: &str = "NodeRenderer";
+
+/// Document-wide undo, used when the focus is not inside a text block
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-z", Undo, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-z", Undo, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-z", Redo, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-y", Redo, Some(CONTEXT)),
+    ]);
+}
+
 pub struct NodeRenderer {
     pub state: Entity<NodeState>,
     insert_menu: Entity<SlashMenu>,
     config_menus: Vec<Entity<NodeConfigMenu>>,
+    focus_handle: FocusHandle,
+    /// Scroll state of the document view displaying the renderer
+    pub scroll_handle: ScrollHandle,
+    scroll_target: Option<Uuid>,
 }
 
 #[derive(Clone)]
@@ -31,10 +57,7 @@ impl NodeRenderer {
         let state = cx.new(|_| NodeState::default());
 
         state.update(cx, |this, cx| {
-            for value in nodes.into_iter() {
-                let node = this.parse_node(&value, &state, window, cx);
-                this.push_node(&node);
-            }
+            this.load_nodes(&nodes, 0, &state, window, cx);
         });
 
         let insert_menu = cx.new(|cx| {
@@ -45,6 +68,9 @@ impl NodeRenderer {
             state,
             insert_menu,
             config_menus: Vec::new(),
+            focus_handle: cx.focus_handle(),
+            scroll_handle: ScrollHandle::new(),
+            scroll_target: None,
         }
     }
 
@@ -66,6 +92,12 @@ impl NodeRenderer {
         menu
     }
 
+    /// Bring a block into view on the next frame
+    pub fn scroll_to_node(&mut self, node_id: Uuid, cx: &mut Context<Self>) {
+        self.scroll_target = Some(node_id);
+        cx.notify();
+    }
+
     fn open_insert_menu(&mut self, node_id: Uuid, window: &mut Window, cx: &mut Context<Self>) {
         self.insert_menu.update(cx, |menu, cx| {
             menu.set_related_id(node_id);
@@ -76,20 +108,14 @@ impl NodeRenderer {
     fn on_drop(this: &mut Self, node_id: Uuid, direction: MovingElement, cx: &mut Context<Self>) {
         this.state.update(cx, |state, _| {
             if let Some(dragging_id) = state.dragging_id {
-                let elements = state.get_nodes();
-                let from_index = elements
-                    .iter()
-                    .position(|e| e.id == dragging_id.clone())
-                    .unwrap();
-
-                let target_index = elements.iter().position(|e| e.id == node_id).unwrap();
-                state.drop_element_by_index(from_index, target_index, direction);
+                state.move_node(dragging_id, node_id, direction);
             }
         });
     }
 
     fn on_drag_move(
         node_id: Uuid,
+        depth: usize,
         this: &mut Self,
         event: &DragMoveEvent<DraggableInfo>,
         cx: &mut Context<Self>,
@@ -103,8 +129,14 @@ impl NodeRenderer {
                 mouse_y >= bounds.origin.y && mouse_y <= bounds.origin.y + bounds.size.height;
 
             if is_in_bounds {
+                // Dropping on the right side of the lower half nests the element
+                let child_threshold =
+                    bounds.origin.x + px(48.0 + INDENT_WIDTH * (depth + 1) as f32);
+
                 let zone = if mouse_y < middle_y {
                     MovingElement::After
+                } else if event.event.position.x > child_threshold {
+                    MovingElement::Child
                 } else {
                     MovingElement::Before
                 };
@@ -149,23 +181,40 @@ impl Render for NodeRenderer {
             (state.is_dragging.clone(), state.hovered_drop_zone.clone())
         };
 
+        let scroll_target = self.scroll_target.take();
+
         let children = nodes.into_iter().map(|node| {
+            let indent = px(INDENT_WIDTH * node.depth as f32);
+
             div()
                 .group("drag_element")
                 .on_drag_move(cx.listener(
                     move |this: &mut Self, event: &DragMoveEvent<DraggableInfo>, _, cx| {
-                        Self::on_drag_move(node.id.clone(), this, event, cx);
+                        Self::on_drag_move(node.id.clone(), node.depth, this, event, cx);
                     },
                 ))
                 .relative()
                 .flex()
                 .items_start()
+                // Files dropped from the system are attached after the block
+                .drag_over::<ExternalPaths>(|style, _, _, cx| {
+                    style.bg(cx.theme().accent.opacity(0.3))
+                })
+                .on_drop(cx.listener(move |this, paths: &ExternalPaths, window, cx| {
+                    attachments::insert_files(
+                        paths.paths().to_vec(),
+                        Some(node.id),
+                        &this.state,
+                        window,
+                        cx,
+                    );
+                }))
                 .child(
                     div()
                         .invisible()
                         .group_hover("drag_element", |this| this.visible())
                         .absolute()
-                        .left_0()
+                        .left(indent)
                         .top_3()
                         .h_6()
                         .flex()
@@ -202,6 +251,7 @@ impl Render for NodeRenderer {
                     div()
                         .relative()
                         .ml_12()
+                        .pl(indent)
                         .w_full()
                         .child(node.element.clone())
                         .tab_index(0)
@@ -225,11 +275,39 @@ impl Render for NodeRenderer {
                                         .bg(cx.theme().accent_foreground.opacity(0.5))
                                         .tab_index(10),
                                 ),
+                                Some((i, MovingElement::Child)) if i == node.id => Some(
+                                    div()
+                                        .absolute()
+                                        .bottom(px(-2.0))
+                                        .left(indent + px(INDENT_WIDTH))
+                                        .right_0()
+                                        .h(px(4.0))
+                                        .bg(cx.theme().accent_foreground.opacity(0.5))
+                                        .tab_index(10),
+                                ),
                                 _ => None,
                             },
                             |this, bar| this.child(bar),
                         ),
                 )
+                .when(scroll_target == Some(node.id), |this| {
+                    let scroll_handle = self.scroll_handle.clone();
+                    this.child(
+                        canvas(
+                            move |bounds, window, _| {
+                                // Leave a bit of room above the block
+                                let viewport = scroll_handle.bounds();
+                                let offset = scroll_handle.offset();
+                                let distance = bounds.top() - viewport.top() - px(48.0);
+                                scroll_handle.set_offset(point(offset.x, offset.y - distance));
+                                window.refresh();
+                            },
+                            |_, _, _, _| {},
+                        )
+                        .absolute()
+                        .size_full(),
+                    )
+                })
                 .when(is_dragging, |this| {
                     let top_dropable_zone_element = div()
                         .absolute()
@@ -251,7 +329,13 @@ impl Render for NodeRenderer {
                         .bottom_0()
                         .on_drop(
                             cx.listener(move |this: &mut Self, _: &DraggableInfo, _, cx| {
-                                Self::on_drop(this, node.id.clone(), MovingElement::Before, cx)
+                                let direction = match this.state.read(cx).hovered_drop_zone {
+                                    Some((id, MovingElement::Child)) if id == node.id => {
+                                        MovingElement::Child
+                                    }
+                                    _ => MovingElement::Before,
+                                };
+                                Self::on_drop(this, node.id.clone(), direction, cx)
                             }),
                         );
 
@@ -260,14 +344,37 @@ impl Render for NodeRenderer {
                 })
         });
 
-        div().w_full().children(children).child(
-            div()
-                .id("add_element")
-                .cursor_pointer()
-                .ml_12()
-                .h_20()
-                .w_full()
-                .on_click(cx.listener(Self::on_create_text_zone)),
-        )
+        div()
+            .key_context(CONTEXT)
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(|this, _: &Undo, window, cx| {
+                this.state.update(cx, |state, cx| state.undo(window, cx));
+            }))
+            .on_action(cx.listener(|this, _: &Redo, window, cx| {
+                this.state.update(cx, |state, cx| state.redo(window, cx));
+            }))
+            .w_full()
+            .children(children)
+            .child(
+                div()
+                    .id("add_element")
+                    .cursor_pointer()
+                    .ml_12()
+                    .h_20()
+                    .w_full()
+                    .drag_over::<ExternalPaths>(|style, _, _, cx| {
+                        style.bg(cx.theme().accent.opacity(0.3))
+                    })
+                    .on_drop(cx.listener(|this, paths: &ExternalPaths, window, cx| {
+                        attachments::insert_files(
+                            paths.paths().to_vec(),
+                            None,
+                            &this.state,
+                            window,
+                            cx,
+                        );
+                    }))
+                    .on_click(cx.listener(Self::on_create_text_zone)),
+            )
     }
 }
@@ -0,0 +1,181 @@
+use gpui::*;
+use gpui_component::{
+    ActiveTheme,
+    input::{Input, InputEvent, InputState, TabSize},
+};
+
+use crate::app::{
+    components::nodes::{
+        code::data::{CODE_LANGUAGES, CodeNodeData},
+        element::{NodePayload, RemindrElement},
+        menu_provider::{NodeMenuItem, NodeMenuProvider},
+        text::data::TextMetadata,
+    },
+    states::{document_state::DocumentState, node_state::NodeState},
+};
+
+/// Multi-line code snippet edited with the syntax highlighted code editor
+pub struct CodeNode {
+    pub state: Entity<NodeState>,
+    pub data: CodeNodeData,
+    pub input_state: Entity<InputState>,
+}
+
+impl CodeNode {
+    pub fn new(
+        data: CodeNodeData,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let input_state = cx.new(|cx| {
+            InputState::new(window, cx)
+                .code_editor(data.metadata.language.clone())
+                .line_number(false)
+                .tab_size(TabSize {
+                    tab_size: 2,
+                    hard_tabs: false,
+                })
+                .auto_grow(1, 40)
+                .default_value(data.metadata.content.clone())
+        });
+
+        cx.subscribe_in(&input_state, window, {
+            move |this, _, ev: &InputEvent, window, cx| {
+                if let InputEvent::Change = ev {
+                    this.handle_input_change(window, cx);
+                }
+            }
+        })
+        .detach();
+
+        Self {
+            state: state.clone(),
+            data,
+            input_state,
+        }
+    }
+
+    pub fn focus(&self, window: &mut Window, cx: &mut App) {
+        self.input_state.update(cx, |state, cx| {
+            state.focus(window, cx);
+        });
+    }
+
+    fn handle_input_change(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.data.metadata.content = self.input_state.read(cx).value();
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
+    pub fn set_language(&mut self, language: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.input_state.update(cx, |state, cx| {
+            state.set_highlighter(language.clone(), cx);
+        });
+        self.data.metadata.language = language;
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+        cx.notify();
+    }
+}
+
+impl NodeMenuProvider for CodeNode {
+    fn menu_items(&self, _cx: &App) -> Vec<NodeMenuItem> {
+        let node_id = self.data.id;
+        let content = self.data.metadata.content.clone();
+
+        let mut items = vec![NodeMenuItem::new(
+            "transform-to-text",
+            "Text",
+            "icons/pilcrow.svg",
+            move |state, window, cx| {
+                let content = content.clone();
+                let state_clone = state.clone();
+                state.update(cx, |state, cx| {
+                    let node = RemindrElement::create_node_with_id(
+                        node_id,
+                        NodePayload::Text((
+                            TextMetadata {
+                                content,
+                                spans: Vec::new(),
+                            },
+                            true,
+                        )),
+                        &state_clone,
+                        window,
+                        cx,
+                    );
+                    state.replace_node(node_id, &node);
+                });
+            },
+        )];
+
+        // Language picker
+        for (language, label) in CODE_LANGUAGES {
+            if language == self.data.metadata.language {
+                continue;
+            }
+
+            items.push(NodeMenuItem::new(
+                format!("code-language-{}", language),
+                label,
+                "icons/code.svg",
+                move |state, window, cx| {
+                    let code_entity = {
+                        let node = state.read(cx).get_current_nodes(node_id);
+                        node.and_then(|n| {
+                            if let RemindrElement::Code(code) = &n.element {
+                                Some(code.clone())
+                            } else {
+                                None
+                            }
+                        })
+                    };
+
+                    if let Some(code) = code_entity {
+                        code.update(cx, |this, cx| {
+                            this.set_language(language.to_string(), window, cx);
+                        });
+                    }
+                },
+            ));
+        }
+
+        items
+    }
+}
+
+impl Render for CodeNode {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .min_w(px(820.0))
+            .w_full()
+            .my_2()
+            .ml_3()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().muted)
+            .child(
+                div()
+                    .px_3()
+                    .pt_1()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(SharedString::from(
+                        self.data.metadata.language_label().to_string(),
+                    )),
+            )
+            .child(
+                div().px_1().pb_1().child(
+                    Input::new(&self.input_state)
+                        .bordered(false)
+                        .bg(transparent_white())
+                        .text_sm(),
+                ),
+            )
+    }
+}
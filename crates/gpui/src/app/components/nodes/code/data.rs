@@ -0,0 +1,70 @@
+use gpui::SharedString;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Languages offered by the code block picker, as understood by the code editor highlighter
+pub const CODE_LANGUAGES: [(&str, &str); 12] = [
+    ("text", "Plain text"),
+    ("rust", "Rust"),
+    ("javascript", "JavaScript"),
+    ("typescript", "TypeScript"),
+    ("python", "Python"),
+    ("go", "Go"),
+    ("json", "JSON"),
+    ("html", "HTML"),
+    ("css", "CSS"),
+    ("sql", "SQL"),
+    ("bash", "Bash"),
+    ("markdown", "Markdown"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeNodeData {
+    pub id: Uuid,
+
+    #[serde(rename = "type")]
+    pub node_type: String,
+
+    pub metadata: CodeMetadata,
+}
+
+impl CodeNodeData {
+    pub fn new(id: Uuid, node_type: String, metadata: CodeMetadata) -> Self {
+        Self {
+            id,
+            node_type,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeMetadata {
+    pub content: SharedString,
+    #[serde(default = "default_language")]
+    pub language: String,
+}
+
+impl CodeMetadata {
+    /// Label shown for the language, the raw identifier when it is not in the picker
+    pub fn language_label(&self) -> &str {
+        CODE_LANGUAGES
+            .iter()
+            .find(|(language, _)| *language == self.language)
+            .map(|(_, label)| *label)
+            .unwrap_or(&self.language)
+    }
+}
+
+impl Default for CodeMetadata {
+    fn default() -> Self {
+        Self {
+            content: SharedString::new(""),
+            language: default_language(),
+        }
+    }
+}
+
+fn default_language() -> String {
+    "text".to_string()
+}
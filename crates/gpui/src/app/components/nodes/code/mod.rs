@@ -0,0 +1,2 @@
+pub mod code_node;
+pub mod data;
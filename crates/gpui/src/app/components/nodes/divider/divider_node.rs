@@ -1,7 +1,5 @@
-use anyhow::{Error, Ok};
 use gpui::{App, Context, Entity, IntoElement, ParentElement, Render, Styled, Window, div};
 use gpui_component::divider::Divider;
-use serde_json::{Value, from_value};
 use uuid::Uuid;
 
 use crate::app::{
@@ -19,14 +17,12 @@ pub struct DividerNode {
 }
 
 impl DividerNode {
-    pub fn parse(data: &Value, _: &mut Window, _: &mut Context<Self>) -> Result<Self, Error> {
-        let data = from_value::<DividerNodeData>(data.clone())?;
-
-        Ok(Self {
+    pub fn new(data: DividerNodeData, _: &mut Window, _: &mut Context<Self>) -> Self {
+        Self {
             id: data.id,
             data,
             state: None,
-        })
+        }
     }
 }
 
@@ -1,30 +1,68 @@
 use crate::{
     Utils,
     app::{
-        components::nodes::{
-            divider::{data::DividerNodeData, divider_node::DividerNode},
-            heading::{
-                data::{HeadingMetadata, HeadingNodeData},
-                heading_node::HeadingNode,
+        components::{
+            nodes::{
+                code::{
+                    code_node::CodeNode,
+                    data::{CodeMetadata, CodeNodeData},
+                },
+                divider::{data::DividerNodeData, divider_node::DividerNode},
+                file::{
+                    data::{FileMetadata, FileNodeData},
+                    file_node::FileNode,
+                },
+                heading::{
+                    data::{HeadingMetadata, HeadingNodeData},
+                    heading_node::HeadingNode,
+                },
+                image::{
+                    data::{ImageMetadata, ImageNodeData},
+                    image_node::ImageNode,
+                },
+                list::{
+                    data::{ListKind, ListMetadata, ListNodeData},
+                    list_node::ListNode,
+                },
+                menu_provider::{NodeMenuItem, NodeMenuProvider},
+                node::RemindrNode,
+                text::{
+                    data::{TextMetadata, TextNodeData},
+                    text_node::TextNode,
+                },
+                textual_node::TextualNode,
+                unknown::unknown_node::UnknownNode,
             },
-            menu_provider::{NodeMenuItem, NodeMenuProvider},
-            node::RemindrNode,
-            text::{
-                data::{TextMetadata, TextNodeData},
-                text_node::TextNode,
-            },
-            textual_node::TextualNode,
+            rich_text::RichTextState,
         },
         states::node_state::NodeState,
     },
 };
-use gpui::{AnyElement, App, AppContext, Context, Entity, IntoElement, Render, RenderOnce, Window};
+use gpui::{
+    AnyElement, App, AppContext, Context, Entity, EntityId, IntoElement, Render, RenderOnce, Window,
+};
 use serde_json::{Value, to_value};
 
 pub enum NodePayload {
     Text((TextMetadata, bool)),
     Heading((HeadingMetadata, bool)),
     Divider,
+    BulletedList((ListMetadata, bool)),
+    NumberedList((ListMetadata, bool)),
+    Todo((ListMetadata, bool)),
+    Code((CodeMetadata, bool)),
+    Image(ImageMetadata),
+    File(FileMetadata),
+}
+
+impl NodePayload {
+    pub fn list(kind: ListKind, metadata: ListMetadata, is_focus: bool) -> Self {
+        match kind {
+            ListKind::Bulleted => NodePayload::BulletedList((metadata, is_focus)),
+            ListKind::Numbered => NodePayload::NumberedList((metadata, is_focus)),
+            ListKind::Todo => NodePayload::Todo((metadata, is_focus)),
+        }
+    }
 }
 
 #[derive(Clone, Debug, IntoElement)]
@@ -32,14 +70,72 @@ pub enum RemindrElement {
     Text(Entity<TextNode>),
     Divider(Entity<DividerNode>),
     Heading(Entity<HeadingNode>),
+    BulletedList(Entity<ListNode>),
+    NumberedList(Entity<ListNode>),
+    Todo(Entity<ListNode>),
+    Code(Entity<CodeNode>),
+    Image(Entity<ImageNode>),
+    File(Entity<FileNode>),
+    /// Node kept as stored because it could not be parsed
+    Unknown(Entity<UnknownNode>),
 }
 
 impl RemindrElement {
-    pub fn get_data(&self, cx: &mut App) -> Value {
+    pub fn list(kind: ListKind, element: Entity<ListNode>) -> Self {
+        match kind {
+            ListKind::Bulleted => RemindrElement::BulletedList(element),
+            ListKind::Numbered => RemindrElement::NumberedList(element),
+            ListKind::Todo => RemindrElement::Todo(element),
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&Entity<ListNode>> {
+        match self {
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => Some(element),
+            _ => None,
+        }
+    }
+
+    /// Rich text edited by the element, for the blocks made of formatted text
+    pub fn rich_text(&self, cx: &App) -> Option<Entity<RichTextState>> {
+        match self {
+            RemindrElement::Text(element) => Some(element.read(cx).rich_text_state().clone()),
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => Some(element.read(cx).rich_text_state().clone()),
+            _ => None,
+        }
+    }
+
+    pub fn entity_id(&self) -> EntityId {
+        match self {
+            RemindrElement::Text(element) => element.entity_id(),
+            RemindrElement::Divider(element) => element.entity_id(),
+            RemindrElement::Heading(element) => element.entity_id(),
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => element.entity_id(),
+            RemindrElement::Code(element) => element.entity_id(),
+            RemindrElement::Image(element) => element.entity_id(),
+            RemindrElement::File(element) => element.entity_id(),
+            RemindrElement::Unknown(element) => element.entity_id(),
+        }
+    }
+
+    pub fn get_data(&self, cx: &App) -> Value {
         match self {
             RemindrElement::Text(text) => to_value(text.read(cx).data.clone()).unwrap(),
             RemindrElement::Divider(divider) => to_value(divider.read(cx).data.clone()).unwrap(),
             RemindrElement::Heading(heading) => to_value(heading.read(cx).data.clone()).unwrap(),
+            RemindrElement::BulletedList(list)
+            | RemindrElement::NumberedList(list)
+            | RemindrElement::Todo(list) => to_value(list.read(cx).data.clone()).unwrap(),
+            RemindrElement::Code(code) => to_value(code.read(cx).data.clone()).unwrap(),
+            RemindrElement::Image(image) => to_value(image.read(cx).data.clone()).unwrap(),
+            RemindrElement::File(file) => to_value(file.read(cx).data.clone()).unwrap(),
+            RemindrElement::Unknown(unknown) => unknown.read(cx).data.clone(),
         }
     }
 
@@ -48,6 +144,61 @@ impl RemindrElement {
             RemindrElement::Text(text) => text.read(cx).menu_items(cx),
             RemindrElement::Divider(divider) => divider.read(cx).menu_items(cx),
             RemindrElement::Heading(heading) => heading.read(cx).menu_items(cx),
+            RemindrElement::BulletedList(list)
+            | RemindrElement::NumberedList(list)
+            | RemindrElement::Todo(list) => list.read(cx).menu_items(cx),
+            RemindrElement::Code(code) => code.read(cx).menu_items(cx),
+            RemindrElement::Image(image) => image.read(cx).menu_items(cx),
+            RemindrElement::File(file) => file.read(cx).menu_items(cx),
+            RemindrElement::Unknown(unknown) => unknown.read(cx).menu_items(cx),
+        }
+    }
+
+    /// Focus the element and put the cursor at the end of its content
+    pub fn focus_end(&self, window: &mut Window, cx: &mut App) {
+        match self {
+            RemindrElement::Text(element) => {
+                let rich_text = element.read(cx).rich_text_state().clone();
+                rich_text.update(cx, |state, cx| {
+                    state.focus(window, cx);
+                    state.move_to_end(cx);
+                });
+            }
+            RemindrElement::Heading(element) => {
+                let input = element.read(cx).input_state().clone();
+                input.update(cx, |input, cx| {
+                    input.focus(window, cx);
+                    input.set_cursor_position(
+                        gpui_component::input::Position::new(u32::MAX, u32::MAX),
+                        window,
+                        cx,
+                    );
+                });
+            }
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => {
+                let rich_text = element.read(cx).rich_text_state().clone();
+                rich_text.update(cx, |state, cx| {
+                    state.focus(window, cx);
+                    state.move_to_end(cx);
+                });
+            }
+            RemindrElement::Code(element) => {
+                let input = element.read(cx).input_state.clone();
+                input.update(cx, |input, cx| {
+                    input.focus(window, cx);
+                    input.set_cursor_position(
+                        gpui_component::input::Position::new(u32::MAX, u32::MAX),
+                        window,
+                        cx,
+                    );
+                });
+            }
+            RemindrElement::Divider(_)
+            | RemindrElement::Image(_)
+            | RemindrElement::File(_)
+            | RemindrElement::Unknown(_) => {}
         }
     }
 
@@ -69,10 +220,9 @@ impl RemindrElement {
     ) -> RemindrNode {
         let node = match payload {
             NodePayload::Heading((payload, is_focus)) => {
-                let data =
-                    to_value(HeadingNodeData::new(id, "heading".to_string(), payload)).unwrap();
+                let data = HeadingNodeData::new(id, "heading".to_string(), payload);
 
-                let element = cx.new(|cx| HeadingNode::parse(&data, &state, window, cx).unwrap());
+                let element = cx.new(|cx| HeadingNode::new(data, state, window, cx));
                 if is_focus {
                     element.update(cx, |this, cx| {
                         this.focus(window, cx);
@@ -82,9 +232,9 @@ impl RemindrElement {
                 RemindrElement::Heading(element)
             }
             NodePayload::Text((payload, is_focus)) => {
-                let data = to_value(TextNodeData::new(id, "text".to_string(), payload)).unwrap();
+                let data = TextNodeData::new(id, "text".to_string(), payload);
 
-                let element = cx.new(|cx| TextNode::parse(&data, &state, window, cx).unwrap());
+                let element = cx.new(|cx| TextNode::new(data, state, window, cx));
                 if is_focus {
                     element.update(cx, |this, cx| {
                         this.focus(window, cx);
@@ -94,15 +244,69 @@ impl RemindrElement {
                 RemindrElement::Text(element)
             }
             NodePayload::Divider => {
-                let data = to_value(DividerNodeData::new(id, "divider".to_string())).unwrap();
-                let element = cx.new(|cx| DividerNode::parse(&data, window, cx).unwrap());
+                let data = DividerNodeData::new(id, "divider".to_string());
+                let element = cx.new(|cx| DividerNode::new(data, window, cx));
 
                 RemindrElement::Divider(element)
             }
+            NodePayload::BulletedList((payload, is_focus)) => {
+                Self::create_list(id, ListKind::Bulleted, payload, is_focus, state, window, cx)
+            }
+            NodePayload::NumberedList((payload, is_focus)) => {
+                Self::create_list(id, ListKind::Numbered, payload, is_focus, state, window, cx)
+            }
+            NodePayload::Todo((payload, is_focus)) => {
+                Self::create_list(id, ListKind::Todo, payload, is_focus, state, window, cx)
+            }
+            NodePayload::Code((payload, is_focus)) => {
+                let data = CodeNodeData::new(id, "code".to_string(), payload);
+
+                let element = cx.new(|cx| CodeNode::new(data, state, window, cx));
+                if is_focus {
+                    element.update(cx, |this, cx| {
+                        this.focus(window, cx);
+                    });
+                }
+
+                RemindrElement::Code(element)
+            }
+            NodePayload::Image(payload) => {
+                let data = ImageNodeData::new(id, "image".to_string(), payload);
+                let element = cx.new(|cx| ImageNode::new(data, state, window, cx));
+
+                RemindrElement::Image(element)
+            }
+            NodePayload::File(payload) => {
+                let data = FileNodeData::new(id, "file".to_string(), payload);
+                let element = cx.new(|cx| FileNode::new(data, state, window, cx));
+
+                RemindrElement::File(element)
+            }
         };
 
         RemindrNode::new(id, node)
     }
+
+    fn create_list(
+        id: uuid::Uuid,
+        kind: ListKind,
+        payload: ListMetadata,
+        is_focus: bool,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> RemindrElement {
+        let data = ListNodeData::new(id, kind.node_type().to_string(), payload);
+
+        let element = cx.new(|cx| ListNode::new(data, kind, state, window, cx));
+        if is_focus {
+            element.update(cx, |this, cx| {
+                this.focus(window, cx);
+            });
+        }
+
+        RemindrElement::list(kind, element)
+    }
 }
 
 impl RenderOnce for RemindrElement {
@@ -112,6 +316,13 @@ impl RenderOnce for RemindrElement {
             RemindrElement::Text(element) => element.clone().into_any_element(),
             RemindrElement::Divider(element) => element.clone().into_any_element(),
             RemindrElement::Heading(element) => element.clone().into_any_element(),
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => element.clone().into_any_element(),
+            RemindrElement::Code(element) => element.clone().into_any_element(),
+            RemindrElement::Image(element) => element.clone().into_any_element(),
+            RemindrElement::File(element) => element.clone().into_any_element(),
+            RemindrElement::Unknown(element) => element.clone().into_any_element(),
         }
     }
 }
@@ -123,6 +334,13 @@ impl Render for RemindrElement {
             RemindrElement::Text(element) => element.clone().into_any_element(),
             RemindrElement::Divider(element) => element.clone().into_any_element(),
             RemindrElement::Heading(element) => element.clone().into_any_element(),
+            RemindrElement::BulletedList(element)
+            | RemindrElement::NumberedList(element)
+            | RemindrElement::Todo(element) => element.clone().into_any_element(),
+            RemindrElement::Code(element) => element.clone().into_any_element(),
+            RemindrElement::Image(element) => element.clone().into_any_element(),
+            RemindrElement::File(element) => element.clone().into_any_element(),
+            RemindrElement::Unknown(element) => element.clone().into_any_element(),
         }
     }
 }
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileNodeData {
+    pub id: Uuid,
+
+    #[serde(rename = "type")]
+    pub node_type: String,
+
+    pub metadata: FileMetadata,
+}
+
+impl FileNodeData {
+    pub fn new(id: Uuid, node_type: String, metadata: FileMetadata) -> Self {
+        Self {
+            id,
+            node_type,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileMetadata {
+    /// Attachment holding the file, stored in the workspace database
+    pub hash: String,
+    pub name: String,
+    pub mime_type: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+impl FileMetadata {
+    /// Size of the file in the largest unit it fills, e.g. "1.4 MB"
+    pub fn size_label(&self) -> String {
+        const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+
+        if self.size < 1024 {
+            return format!("{} B", self.size);
+        }
+
+        let mut size = self.size as f64 / 1024.0;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
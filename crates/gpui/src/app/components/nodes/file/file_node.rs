@@ -0,0 +1,123 @@
+use anyhow::anyhow;
+use gpui::*;
+use gpui_component::{
+    ActiveTheme, Icon, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+
+use crate::app::{
+    components::nodes::{
+        file::data::FileNodeData,
+        menu_provider::{NodeMenuItem, NodeMenuProvider},
+    },
+    states::{node_state::NodeState, repository_state::RepositoryState},
+};
+
+/// File stored as an attachment of the workspace, saved back to the disk on demand
+pub struct FileNode {
+    pub state: Entity<NodeState>,
+    pub data: FileNodeData,
+}
+
+impl FileNode {
+    pub fn new(
+        data: FileNodeData,
+        state: &Entity<NodeState>,
+        _: &mut Window,
+        _: &mut Context<Self>,
+    ) -> Self {
+        Self {
+            state: state.clone(),
+            data,
+        }
+    }
+
+    /// Asks where to write the file and copies it there from the database
+    fn save_as(&self, window: &mut Window, cx: &mut Context<Self>) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let hash = self.data.metadata.hash.clone();
+        let name = self.data.metadata.name.clone();
+
+        let directory = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory, Some(&name));
+
+        window
+            .spawn(cx, async move |cx| {
+                let Ok(Ok(Some(path))) = path.await else {
+                    return;
+                };
+
+                let result = async {
+                    let attachment = repository
+                        .get_attachment(&hash)
+                        .await?
+                        .ok_or_else(|| anyhow!("the file is not available in this workspace"))?;
+                    smol::fs::write(&path, attachment.data).await?;
+                    Ok::<_, anyhow::Error>(())
+                }
+                .await;
+
+                let _ = cx.update(|window, cx| match result {
+                    Ok(_) => window.push_notification(format!("\"{}\" has been saved", name), cx),
+                    Err(error) => window.push_notification(format!("Save failed: {}", error), cx),
+                });
+            })
+            .detach();
+    }
+}
+
+impl NodeMenuProvider for FileNode {
+    fn menu_items(&self, _cx: &App) -> Vec<NodeMenuItem> {
+        vec![]
+    }
+}
+
+impl Render for FileNode {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .my_1()
+            .px_3()
+            .py_2()
+            .gap_3()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().muted.opacity(0.4))
+            .child(
+                Icon::default()
+                    .path("icons/paperclip.svg")
+                    .size_4()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .min_w_0()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_ellipsis()
+                            .overflow_hidden()
+                            .child(self.data.metadata.name.clone()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(self.data.metadata.size_label()),
+                    ),
+            )
+            .child(
+                Button::new("save-file")
+                    .xsmall()
+                    .ghost()
+                    .icon(Icon::default().path("icons/download.svg"))
+                    .tooltip("Save as...")
+                    .on_click(cx.listener(|this, _, window, cx| this.save_as(window, cx))),
+            )
+    }
+}
@@ -1,9 +1,7 @@
 use std::f32::INFINITY;
 
-use anyhow::{Error, Ok};
 use gpui::*;
 use gpui_component::input::{Input, InputEvent, InputState};
-use serde_json::{Value, from_value, to_value};
 use uuid::Uuid;
 
 use crate::{
@@ -36,14 +34,12 @@ pub struct HeadingNode {
 }
 
 impl HeadingNode {
-    pub fn parse(
-        data: &Value,
+    pub fn new(
+        data: HeadingNodeData,
         state: &Entity<NodeState>,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> Result<Self, Error> {
-        let data = from_value::<HeadingNodeData>(data.clone())?;
-
+    ) -> Self {
         let input_state = cx.new(|cx| {
             InputState::new(window, cx)
                 .placeholder("New document")
@@ -79,13 +75,13 @@ impl HeadingNode {
         })
         .detach();
 
-        Ok(Self {
+        Self {
             state: state.clone(),
             data,
             input_state,
             menu,
             is_focus: false,
-        })
+        }
     }
 
     /// Handles input changes and emits appropriate events.
@@ -106,10 +102,21 @@ impl HeadingNode {
     }
 
     pub fn set_level(&mut self, level: u32, window: &mut Window, cx: &mut Context<Self>) {
-        self.data.metadata.level = level;
+        let node_id = self.data.id;
+        let previous_level = self.data.metadata.level;
+        self.state.update(cx, |state, _| {
+            state.record_heading_level(node_id, previous_level, level);
+        });
+
+        self.apply_level(level, cx);
         cx.update_global::<DocumentState, _>(|state, app| {
             state.mark_changed(window, app);
         });
+    }
+
+    /// Change the level without recording it in the document history
+    pub fn apply_level(&mut self, level: u32, cx: &mut Context<Self>) {
+        self.data.metadata.level = level;
         cx.notify();
     }
 }
@@ -176,28 +183,7 @@ impl TextualNodeDelegate for HeadingNode {
                         state.remove_node(node_id);
 
                         if let Some(previous_element) = previous_element {
-                            if let RemindrElement::Text(element) = previous_element.element.clone()
-                            {
-                                let rich_text = element.read(inner_cx).rich_text_state().clone();
-                                rich_text.update(inner_cx, |state, cx| {
-                                    state.focus(window, cx);
-                                    state.move_to_end(cx);
-                                });
-                            }
-
-                            if let RemindrElement::Heading(element) =
-                                previous_element.element.clone()
-                            {
-                                let input = element.read(inner_cx).input_state().clone();
-                                input.update(inner_cx, |input, inner_cx| {
-                                    input.focus(window, inner_cx);
-                                    input.set_cursor_position(
-                                        gpui_component::input::Position::new(u32::MAX, u32::MAX),
-                                        window,
-                                        inner_cx,
-                                    );
-                                });
-                            }
+                            previous_element.element.focus_end(window, inner_cx);
                         }
 
                         inner_cx.update_global::<DocumentState, _>(|state, app_cx| {
@@ -224,15 +210,10 @@ impl TextualNodeDelegate for HeadingNode {
 
                 state.update(cx, |state, inner_cx| {
                     let id = Utils::generate_uuid();
-                    let data = to_value(TextNodeData::new(
-                        id,
-                        "text".to_string(),
-                        TextMetadata::default(),
-                    ))
-                    .unwrap();
+                    let data = TextNodeData::new(id, "text".to_string(), TextMetadata::default());
 
-                    let element = inner_cx
-                        .new(|cx| TextNode::parse(&data, &state_for_parse, window, cx).unwrap());
+                    let element =
+                        inner_cx.new(|cx| TextNode::new(data, &state_for_parse, window, cx));
 
                     let rich_text = element.read(inner_cx).rich_text_state().clone();
                     rich_text.update(inner_cx, |state, cx| {
@@ -280,6 +261,7 @@ impl NodeMenuProvider for HeadingNode {
                         NodePayload::Text((
                             TextMetadata {
                                 content: content.clone(),
+                                spans: Vec::new(),
                             },
                             true,
                         )),
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageNodeData {
+    pub id: Uuid,
+
+    #[serde(rename = "type")]
+    pub node_type: String,
+
+    pub metadata: ImageMetadata,
+}
+
+impl ImageNodeData {
+    pub fn new(id: Uuid, node_type: String, metadata: ImageMetadata) -> Self {
+        Self {
+            id,
+            node_type,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    /// Attachment holding the picture, stored in the workspace database
+    pub hash: String,
+    pub name: String,
+    pub mime_type: String,
+    /// Width the image was resized to, in pixels. Without it the image fills the page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<f32>,
+}
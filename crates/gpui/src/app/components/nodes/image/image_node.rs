@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{ActiveTheme, Icon, h_flex};
+use uuid::Uuid;
+
+use crate::app::{
+    components::nodes::{
+        image::data::ImageNodeData,
+        menu_provider::{NodeMenuItem, NodeMenuProvider},
+    },
+    states::{
+        document_state::DocumentState, node_state::NodeState, repository_state::RepositoryState,
+    },
+};
+
+/// Narrowest an image can be resized to, in pixels
+const MIN_WIDTH: f32 = 64.0;
+
+/// Dragged by the handle resizing the image of a node
+#[derive(Clone)]
+struct ResizeImage(Uuid);
+
+enum ImageState {
+    Loading,
+    Loaded(Arc<Image>),
+    /// Not in the database, e.g. not synced yet, or in a format that cannot be shown
+    Missing,
+    /// The database could not be read
+    Failed(SharedString),
+}
+
+/// Picture stored as an attachment of the workspace, shown inline
+pub struct ImageNode {
+    pub state: Entity<NodeState>,
+    pub data: ImageNodeData,
+    image: ImageState,
+    _load_task: Task<()>,
+}
+
+impl ImageNode {
+    pub fn new(
+        data: ImageNodeData,
+        state: &Entity<NodeState>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let hash = data.metadata.hash.clone();
+        let load_task = cx.spawn(async move |this, cx| {
+            let attachment = repository.get_attachment(&hash).await;
+            let _ = this.update(cx, |this, cx| {
+                this.image = match attachment {
+                    Ok(attachment) => attachment
+                        .and_then(|attachment| {
+                            ImageFormat::from_mime_type(&attachment.mime_type)
+                                .map(|format| Image::from_bytes(format, attachment.data))
+                        })
+                        .map(|image| ImageState::Loaded(Arc::new(image)))
+                        .unwrap_or(ImageState::Missing),
+                    Err(error) => {
+                        ImageState::Failed(format!("Could not load the image: {:#}", error).into())
+                    }
+                };
+                cx.notify();
+            });
+        });
+
+        Self {
+            state: state.clone(),
+            data,
+            image: ImageState::Loading,
+            _load_task: load_task,
+        }
+    }
+
+    fn resize(&mut self, width: f32, window: &mut Window, cx: &mut Context<Self>) {
+        self.data.metadata.width = Some(width.round());
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+        cx.notify();
+    }
+
+    fn render_placeholder(&self, label: impl Into<SharedString>, cx: &mut Context<Self>) -> Div {
+        h_flex()
+            .w_full()
+            .h_24()
+            .gap_2()
+            .justify_center()
+            .rounded_md()
+            .border_1()
+            .border_dashed()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().muted.opacity(0.4))
+            .text_sm()
+            .text_color(cx.theme().muted_foreground)
+            .child(Icon::default().path("icons/image.svg").size_4())
+            .child(label.into())
+    }
+}
+
+impl NodeMenuProvider for ImageNode {
+    fn menu_items(&self, _cx: &App) -> Vec<NodeMenuItem> {
+        vec![]
+    }
+}
+
+impl Render for ImageNode {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let node_id = self.data.id;
+
+        let image = match &self.image {
+            ImageState::Loading => self.render_placeholder("Loading image...", cx),
+            ImageState::Missing => self.render_placeholder("Image not available", cx),
+            ImageState::Failed(error) => self
+                .render_placeholder(error.clone(), cx)
+                .border_color(cx.theme().danger)
+                .text_color(cx.theme().danger),
+            ImageState::Loaded(image) => div()
+                .relative()
+                .group("image")
+                .max_w_full()
+                .when_some(self.data.metadata.width, |this, width| this.w(px(width)))
+                .child(
+                    img(image.clone())
+                        .w_full()
+                        .rounded_md()
+                        .object_fit(ObjectFit::Contain),
+                )
+                .child(
+                    div()
+                        .id(SharedString::from(format!("image-resize-{}", node_id)))
+                        .absolute()
+                        .top_0()
+                        .bottom_0()
+                        .right_0()
+                        .w_3()
+                        .flex()
+                        .items_center()
+                        .justify_center()
+                        .cursor_col_resize()
+                        .invisible()
+                        .group_hover("image", |this| this.visible())
+                        .child(
+                            div()
+                                .w_1()
+                                .h_10()
+                                .rounded_full()
+                                .bg(cx.theme().background.opacity(0.8))
+                                .border_1()
+                                .border_color(cx.theme().border),
+                        )
+                        .on_drag(ResizeImage(node_id), |_, _, _, cx| cx.new(|_| EmptyView)),
+                ),
+        };
+
+        div()
+            .w_full()
+            .py_2()
+            // The width follows the pointer, measured from the left of the block
+            .on_drag_move(cx.listener(
+                move |this, event: &DragMoveEvent<ResizeImage>, window, cx| {
+                    if event.drag(cx).0 != node_id {
+                        return;
+                    }
+
+                    let max_width = f32::from(event.bounds.size.width).max(MIN_WIDTH);
+                    let width = f32::from(event.event.position.x - event.bounds.left())
+                        .clamp(MIN_WIDTH, max_width);
+                    this.resize(width, window, cx);
+                },
+            ))
+            .child(image)
+    }
+}
@@ -0,0 +1,70 @@
+use gpui::SharedString;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app::components::rich_text::TextSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListKind {
+    Bulleted,
+    Numbered,
+    Todo,
+}
+
+impl ListKind {
+    pub const ALL: [ListKind; 3] = [ListKind::Bulleted, ListKind::Numbered, ListKind::Todo];
+
+    pub fn node_type(&self) -> &'static str {
+        match self {
+            ListKind::Bulleted => "bulleted_list",
+            ListKind::Numbered => "numbered_list",
+            ListKind::Todo => "todo",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ListKind::Bulleted => "Bulleted list",
+            ListKind::Numbered => "Numbered list",
+            ListKind::Todo => "To-do list",
+        }
+    }
+
+    pub fn icon_path(&self) -> &'static str {
+        match self {
+            ListKind::Bulleted => "icons/list.svg",
+            ListKind::Numbered => "icons/list-ordered.svg",
+            ListKind::Todo => "icons/list-todo.svg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListNodeData {
+    pub id: Uuid,
+
+    #[serde(rename = "type")]
+    pub node_type: String,
+
+    pub metadata: ListMetadata,
+}
+
+impl ListNodeData {
+    pub fn new(id: Uuid, node_type: String, metadata: ListMetadata) -> Self {
+        Self {
+            id,
+            node_type,
+            metadata,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ListMetadata {
+    pub content: SharedString,
+    #[serde(default)]
+    pub spans: Vec<TextSpan>,
+    /// Only used by to-do items
+    #[serde(default)]
+    pub checked: bool,
+}
@@ -0,0 +1,388 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{ActiveTheme, WindowExt, checkbox::Checkbox};
+
+use crate::app::{
+    components::{
+        attachments,
+        document_link_picker::DocumentLinkPicker,
+        nodes::{
+            element::{NodePayload, RemindrElement},
+            list::data::{ListKind, ListMetadata, ListNodeData},
+            menu_provider::{NodeMenuItem, NodeMenuProvider},
+            stylable_node::StylableNode,
+            text::data::TextMetadata,
+        },
+        rich_text::{RichTextEvent, RichTextState, RichTextView, shift_spans_left},
+        slash_menu::{SlashMenu, SlashMenuDismissEvent},
+        style_toolbar::StyleToolbar,
+    },
+    states::{document_state::DocumentState, node_state::NodeState},
+};
+
+/// Bulleted, numbered and to-do list items
+pub struct ListNode {
+    pub state: Entity<NodeState>,
+    pub data: ListNodeData,
+    pub kind: ListKind,
+    pub rich_text_state: Entity<RichTextState>,
+    menu: Entity<SlashMenu>,
+    style_toolbar: Entity<StyleToolbar>,
+    is_focus: bool,
+}
+
+impl ListNode {
+    pub fn new(
+        data: ListNodeData,
+        kind: ListKind,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let rich_text_state = cx.new(|cx| {
+            let mut state = RichTextState::new(window, cx);
+            if !data.metadata.content.is_empty() {
+                state.set_rich_content(
+                    data.metadata.content.to_string(),
+                    data.metadata.spans.clone(),
+                    cx,
+                );
+            }
+            state
+        });
+
+        cx.subscribe_in(&rich_text_state, window, {
+            move |this, _, ev: &RichTextEvent, window, cx| match ev {
+                RichTextEvent::Focus => this.is_focus = true,
+                RichTextEvent::Blur => this.is_focus = false,
+                RichTextEvent::Change(content) => {
+                    this.handle_content_change(content.clone(), window, cx)
+                }
+                RichTextEvent::Enter => this.handle_enter(window, cx),
+                RichTextEvent::Backspace => this.handle_backspace(window, cx),
+                RichTextEvent::Slash => this.handle_slash(window, cx),
+                RichTextEvent::LinkTrigger => {
+                    DocumentLinkPicker::open(this.rich_text_state.clone(), window, cx)
+                }
+                RichTextEvent::OpenDocument(id) => {
+                    let opened =
+                        cx.update_global::<DocumentState, _>(|state, _| state.open_link(*id));
+                    if !opened {
+                        window.push_notification("The linked document no longer exists", cx);
+                    }
+                }
+                RichTextEvent::PasteImage(image) => {
+                    attachments::insert_image(image.clone(), this.data.id, &this.state, window, cx)
+                }
+                RichTextEvent::Tab => this.handle_indent(true, window, cx),
+                RichTextEvent::ShiftTab => this.handle_indent(false, window, cx),
+                RichTextEvent::Delete | RichTextEvent::Space => {}
+                RichTextEvent::HistoryPush(edit) => {
+                    let rich_text = this.rich_text_state.clone();
+                    this.state
+                        .update(cx, |state, _| state.record_text(rich_text, edit.clone()));
+                }
+                RichTextEvent::Undo => this.state.update(cx, |state, cx| state.undo(window, cx)),
+                RichTextEvent::Redo => this.state.update(cx, |state, cx| state.redo(window, cx)),
+            }
+        })
+        .detach();
+
+        let menu = cx.new(|cx| SlashMenu::new(data.id, state, window, cx));
+        let style_toolbar = cx
+            .new(|cx| StyleToolbar::new(rich_text_state.clone(), data.id, state, kind.label(), cx));
+
+        cx.subscribe_in(&menu, window, {
+            move |this, _, event: &SlashMenuDismissEvent, window, cx| {
+                if event.restore_focus {
+                    let rich_text_state = this.rich_text_state.clone();
+                    cx.defer_in(window, move |_, window, cx| {
+                        rich_text_state.update(cx, |state, cx| {
+                            state.focus(window, cx);
+                        });
+                    });
+                }
+            }
+        })
+        .detach();
+
+        Self {
+            state: state.clone(),
+            data,
+            kind,
+            rich_text_state,
+            menu,
+            style_toolbar,
+            is_focus: false,
+        }
+    }
+
+    pub fn rich_text_state(&self) -> &Entity<RichTextState> {
+        &self.rich_text_state
+    }
+
+    pub fn focus(&self, window: &mut Window, cx: &mut App) {
+        self.rich_text_state.update(cx, |state, cx| {
+            state.focus(window, cx);
+        });
+    }
+
+    fn handle_content_change(
+        &mut self,
+        content: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.data.metadata.content = content;
+        self.data.metadata.spans = self.rich_text_state.read(cx).spans().to_vec();
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
+    fn handle_slash(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.is_focus && !self.menu.read(cx).open {
+            self.menu.update(cx, |menu, cx| {
+                menu.set_open(true, window, cx);
+            });
+        }
+    }
+
+    fn set_checked(&mut self, checked: bool, window: &mut Window, cx: &mut Context<Self>) {
+        self.data.metadata.checked = checked;
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+        cx.notify();
+    }
+
+    fn handle_indent(&mut self, indent: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let node_id = self.data.id;
+        let changed = self.state.update(cx, |state, cx| {
+            let changed = if indent {
+                state.indent_node(node_id)
+            } else {
+                state.outdent_node(node_id)
+            };
+            if changed {
+                cx.notify();
+            }
+            changed
+        });
+
+        if changed {
+            cx.update_global::<DocumentState, _>(|state, app_cx| {
+                state.mark_changed(window, app_cx);
+            });
+        }
+    }
+
+    /// An empty item stops being a list item when Backspace is pressed
+    fn handle_backspace(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.rich_text_state.read(cx).content().is_empty() {
+            self.turn_into_text(window, cx);
+        }
+    }
+
+    /// Continue the list, or leave it when the item is empty
+    fn handle_enter(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.menu.read(cx).open {
+            return;
+        }
+
+        let node_id = self.data.id;
+
+        if self.rich_text_state.read(cx).content().trim().is_empty() {
+            let outdented = self
+                .state
+                .update(cx, |state, _| state.outdent_node(node_id));
+
+            if outdented {
+                cx.update_global::<DocumentState, _>(|state, app_cx| {
+                    state.mark_changed(window, app_cx);
+                });
+            } else {
+                self.turn_into_text(window, cx);
+            }
+            return;
+        }
+
+        let (content, spans) = {
+            let rich_text = self.rich_text_state.read(cx);
+            let raw = rich_text.content();
+            let offset = raw.len() - raw.trim_start().len();
+            let content = raw.trim().to_string();
+            let spans = shift_spans_left(rich_text.spans(), offset, content.len());
+            (content, spans)
+        };
+        self.data.metadata.content = SharedString::from(content);
+        self.data.metadata.spans = spans;
+        self.is_focus = false;
+
+        let kind = self.kind;
+        self.state.update(cx, |state, cx| {
+            state.insert_node_after(
+                node_id,
+                &RemindrElement::create_node(
+                    NodePayload::list(kind, ListMetadata::default(), true),
+                    &self.state,
+                    window,
+                    cx,
+                ),
+            );
+        });
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
+    fn turn_into_text(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let node_id = self.data.id;
+        let metadata = TextMetadata {
+            content: self.data.metadata.content.clone(),
+            spans: self.data.metadata.spans.clone(),
+        };
+
+        let state = self.state.clone();
+        state.update(cx, |state, cx| {
+            let node = RemindrElement::create_node_with_id(
+                node_id,
+                NodePayload::Text((metadata, true)),
+                &self.state,
+                window,
+                cx,
+            );
+            state.replace_node(node_id, &node);
+        });
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
+    fn render_marker(&self, cx: &mut Context<Self>) -> AnyElement {
+        let marker = div()
+            .flex_shrink_0()
+            .w_6()
+            .h_6()
+            .flex()
+            .items_center()
+            .justify_center();
+
+        match self.kind {
+            ListKind::Bulleted => marker
+                .child(div().size_1p5().rounded_full().bg(cx.theme().foreground))
+                .into_any_element(),
+            ListKind::Numbered => {
+                let number = self.state.read(cx).list_number(self.data.id);
+                marker.child(format!("{}.", number)).into_any_element()
+            }
+            ListKind::Todo => marker
+                .child(
+                    Checkbox::new(SharedString::from(format!("todo-{}", self.data.id)))
+                        .checked(self.data.metadata.checked)
+                        .on_click(cx.listener(|this, checked: &bool, window, cx| {
+                            this.set_checked(*checked, window, cx);
+                        })),
+                )
+                .into_any_element(),
+        }
+    }
+}
+
+impl NodeMenuProvider for ListNode {
+    fn menu_items(&self, _cx: &App) -> Vec<NodeMenuItem> {
+        let node_id = self.data.id;
+        let metadata = self.data.metadata.clone();
+
+        let mut items = vec![{
+            let metadata = metadata.clone();
+            NodeMenuItem::new(
+                "transform-to-text",
+                "Text",
+                "icons/pilcrow.svg",
+                move |state, window, cx| {
+                    let metadata = TextMetadata {
+                        content: metadata.content.clone(),
+                        spans: metadata.spans.clone(),
+                    };
+                    let state_clone = state.clone();
+                    state.update(cx, |state, cx| {
+                        let node = RemindrElement::create_node_with_id(
+                            node_id,
+                            NodePayload::Text((metadata, true)),
+                            &state_clone,
+                            window,
+                            cx,
+                        );
+                        state.replace_node(node_id, &node);
+                    });
+                },
+            )
+        }];
+
+        for kind in ListKind::ALL {
+            if kind == self.kind {
+                continue;
+            }
+
+            let metadata = metadata.clone();
+            items.push(NodeMenuItem::new(
+                format!("transform-to-{}", kind.node_type()),
+                kind.label(),
+                kind.icon_path(),
+                move |state, window, cx| {
+                    let metadata = metadata.clone();
+                    let state_clone = state.clone();
+                    state.update(cx, |state, cx| {
+                        let node = RemindrElement::create_node_with_id(
+                            node_id,
+                            NodePayload::list(kind, metadata, true),
+                            &state_clone,
+                            window,
+                            cx,
+                        );
+                        state.replace_node(node_id, &node);
+                    });
+                },
+            ));
+        }
+
+        items
+    }
+}
+
+impl StylableNode for ListNode {
+    fn rich_text(&self) -> &Entity<RichTextState> {
+        &self.rich_text_state
+    }
+
+    fn style_toolbar(&self) -> &Entity<StyleToolbar> {
+        &self.style_toolbar
+    }
+}
+
+impl Render for ListNode {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let checked = self.kind == ListKind::Todo && self.data.metadata.checked;
+        let muted = cx.theme().muted_foreground;
+
+        div()
+            .min_w(px(820.0))
+            .w_full()
+            .my_2()
+            .flex()
+            .items_start()
+            .gap_1()
+            .ml_3()
+            .child(self.render_marker(cx))
+            .child(
+                div()
+                    .flex_1()
+                    .when(checked, |this| this.text_color(muted).line_through())
+                    .child(RichTextView::new(self.rich_text_state.clone())),
+            )
+            .child(self.menu.clone())
+            .children(self.render_style_toolbar(window, cx))
+    }
+}
@@ -1,7 +1,13 @@
+pub mod code;
 pub mod divider;
 pub mod element;
+pub mod file;
 pub mod heading;
+pub mod image;
+pub mod list;
 pub mod menu_provider;
 pub mod node;
+pub mod stylable_node;
 pub mod text;
 pub mod textual_node;
+pub mod unknown;
@@ -7,11 +7,17 @@ use crate::app::components::nodes::element::RemindrElement;
 pub struct RemindrNode {
     pub id: Uuid,
     pub element: RemindrElement,
+    /// Nesting level, a node is a child of the closest previous node with a lower depth
+    pub depth: usize,
 }
 
 impl RemindrNode {
     pub fn new(id: Uuid, element: RemindrElement) -> Self {
-        Self { id, element }
+        Self {
+            id,
+            element,
+            depth: 0,
+        }
     }
 }
 
@@ -29,4 +35,10 @@ pub enum RemindrNodeType {
     Text,
     Divider,
     Heading,
+    BulletedList,
+    NumberedList,
+    Todo,
+    Code,
+    Image,
+    File,
 }
@@ -0,0 +1,51 @@
+use gpui::{
+    AnyElement, App, Corner, Entity, IntoElement, ParentElement, Styled, Window, anchored,
+    deferred, div, point, px,
+};
+
+use crate::app::components::{rich_text::RichTextState, style_toolbar::StyleToolbar};
+
+/// Trait for nodes whose rich text can be formatted with the style toolbar.
+///
+/// The toolbar floats above the selected text, so the styles are reachable without shortcuts.
+pub trait StylableNode {
+    /// Returns a reference to the rich text state entity.
+    fn rich_text(&self) -> &Entity<RichTextState>;
+
+    /// Returns a reference to the style toolbar entity.
+    fn style_toolbar(&self) -> &Entity<StyleToolbar>;
+
+    /// Returns whether the toolbar is shown: the text has a selection that is not being
+    /// dragged anymore, and the focus is in the text or in the toolbar's menus.
+    fn is_styling(&self, window: &Window, cx: &App) -> bool {
+        let rich_text = self.rich_text().read(cx);
+        let is_focused = rich_text.focus_handle.is_focused(window)
+            || self
+                .style_toolbar()
+                .read(cx)
+                .focus_handle
+                .contains_focused(window, cx);
+
+        is_focused && !rich_text.selection().is_empty() && !rich_text.is_selecting()
+    }
+
+    /// Renders the toolbar anchored above the selection.
+    fn render_style_toolbar(&self, window: &Window, cx: &App) -> Option<AnyElement> {
+        if !self.is_styling(window, cx) {
+            return None;
+        }
+        let bounds = self.rich_text().read(cx).selection_bounds(window)?;
+
+        Some(
+            deferred(
+                anchored()
+                    .position(point(bounds.left(), bounds.top()))
+                    .anchor(Corner::BottomLeft)
+                    .snap_to_window_with_margin(px(8.0))
+                    .child(div().mb_1().child(self.style_toolbar().clone())),
+            )
+            .with_priority(1)
+            .into_any_element(),
+        )
+    }
+}
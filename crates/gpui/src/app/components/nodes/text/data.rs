@@ -2,6 +2,8 @@ use gpui::SharedString;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::app::components::rich_text::TextSpan;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TextNodeData {
     pub id: Uuid,
@@ -25,4 +27,6 @@ impl TextNodeData {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct TextMetadata {
     pub content: SharedString,
+    #[serde(default)]
+    pub spans: Vec<TextSpan>,
 }
@@ -1,17 +1,22 @@
-use anyhow::{Error, Ok};
 use gpui::*;
-use serde_json::{Value, from_value};
+use gpui_component::WindowExt;
 
 use crate::app::{
     components::{
+        attachments,
+        document_link_picker::DocumentLinkPicker,
         nodes::{
+            code::data::CodeMetadata,
             element::{NodePayload, RemindrElement},
             heading::data::HeadingMetadata,
+            list::data::{ListKind, ListMetadata},
             menu_provider::{NodeMenuItem, NodeMenuProvider},
+            stylable_node::StylableNode,
             text::data::{TextMetadata, TextNodeData},
         },
-        rich_text::{RichTextEvent, RichTextState, RichTextView},
+        rich_text::{RichTextEvent, RichTextState, RichTextView, shift_spans_left},
         slash_menu::{SlashMenu, SlashMenuDismissEvent},
+        style_toolbar::StyleToolbar,
     },
     states::{document_state::DocumentState, node_state::NodeState},
 };
@@ -21,22 +26,25 @@ pub struct TextNode {
     pub data: TextNodeData,
     pub rich_text_state: Entity<RichTextState>,
     menu: Entity<SlashMenu>,
+    style_toolbar: Entity<StyleToolbar>,
     is_focus: bool,
 }
 
 impl TextNode {
-    pub fn parse(
-        data: &Value,
+    pub fn new(
+        data: TextNodeData,
         state: &Entity<NodeState>,
         window: &mut Window,
         cx: &mut Context<Self>,
-    ) -> Result<Self, Error> {
-        let data = from_value::<TextNodeData>(data.clone())?;
-
+    ) -> Self {
         let rich_text_state = cx.new(|cx| {
             let mut state = RichTextState::new(window, cx);
             if !data.metadata.content.is_empty() {
-                state.set_content(data.metadata.content.to_string(), cx);
+                state.set_rich_content(
+                    data.metadata.content.to_string(),
+                    data.metadata.spans.clone(),
+                    cx,
+                );
             }
             state
         });
@@ -52,12 +60,36 @@ impl TextNode {
                 RichTextEvent::Backspace => this.handle_backspace(window, cx),
                 RichTextEvent::Delete => this.handle_delete(window, cx),
                 RichTextEvent::Slash => this.handle_slash(window, cx),
-                RichTextEvent::Tab | RichTextEvent::Space => {}
+                RichTextEvent::LinkTrigger => {
+                    DocumentLinkPicker::open(this.rich_text_state.clone(), window, cx)
+                }
+                RichTextEvent::OpenDocument(id) => {
+                    let opened =
+                        cx.update_global::<DocumentState, _>(|state, _| state.open_link(*id));
+                    if !opened {
+                        window.push_notification("The linked document no longer exists", cx);
+                    }
+                }
+                RichTextEvent::PasteImage(image) => {
+                    attachments::insert_image(image.clone(), this.data.id, &this.state, window, cx)
+                }
+                RichTextEvent::Tab => this.handle_indent(true, window, cx),
+                RichTextEvent::ShiftTab => this.handle_indent(false, window, cx),
+                RichTextEvent::Space => this.handle_space(window, cx),
+                RichTextEvent::HistoryPush(edit) => {
+                    let rich_text = this.rich_text_state.clone();
+                    this.state
+                        .update(cx, |state, _| state.record_text(rich_text, edit.clone()));
+                }
+                RichTextEvent::Undo => this.state.update(cx, |state, cx| state.undo(window, cx)),
+                RichTextEvent::Redo => this.state.update(cx, |state, cx| state.redo(window, cx)),
             }
         })
         .detach();
 
         let menu = cx.new(|cx| SlashMenu::new(data.id, state, window, cx));
+        let style_toolbar =
+            cx.new(|cx| StyleToolbar::new(rich_text_state.clone(), data.id, state, "Text", cx));
 
         cx.subscribe_in(&menu, window, {
             move |this, _, event: &SlashMenuDismissEvent, window, cx| {
@@ -73,13 +105,14 @@ impl TextNode {
         })
         .detach();
 
-        Ok(Self {
+        Self {
             state: state.clone(),
             data,
             rich_text_state,
             menu,
+            style_toolbar,
             is_focus: false,
-        })
+        }
     }
 
     fn handle_focus(&mut self, _window: &mut Window, _cx: &mut Context<Self>) {
@@ -102,6 +135,7 @@ impl TextNode {
             self.handle_empty(window, cx);
         } else {
             self.data.metadata.content = content;
+            self.data.metadata.spans = self.rich_text_state.read(cx).spans().to_vec();
             cx.update_global::<DocumentState, _>(|state, app_cx| {
                 state.mark_changed(window, app_cx);
             });
@@ -126,6 +160,96 @@ impl TextNode {
         }
     }
 
+    /// Nest the node under the previous one, or move it one level up
+    fn handle_indent(&mut self, indent: bool, window: &mut Window, cx: &mut Context<Self>) {
+        let node_id = self.data.id;
+        let changed = self.state.update(cx, |state, cx| {
+            let changed = if indent {
+                state.indent_node(node_id)
+            } else {
+                state.outdent_node(node_id)
+            };
+            if changed {
+                cx.notify();
+            }
+            changed
+        });
+
+        if changed {
+            cx.update_global::<DocumentState, _>(|state, app_cx| {
+                state.mark_changed(window, app_cx);
+            });
+        }
+    }
+
+    /// Markdown shortcuts typed at the start of the block turn it into another type
+    fn handle_space(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let (content, spans, cursor) = {
+            let rich_text = self.rich_text_state.read(cx);
+            (
+                rich_text.content().to_string(),
+                rich_text.spans().to_vec(),
+                rich_text.selection().head(),
+            )
+        };
+
+        let prefix = &content[..cursor.min(content.len())];
+        let list_kind = match prefix {
+            "- " | "* " => Some(ListKind::Bulleted),
+            "1. " => Some(ListKind::Numbered),
+            "[] " | "[ ] " => Some(ListKind::Todo),
+            _ => None,
+        };
+        let heading_level = match prefix {
+            "## " => Some(2),
+            "### " => Some(3),
+            _ => None,
+        };
+
+        if list_kind.is_none() && heading_level.is_none() {
+            return;
+        }
+
+        let rest = content[prefix.len()..].to_string();
+        let spans = shift_spans_left(&spans, prefix.len(), rest.len());
+        let node_id = self.data.id;
+
+        let payload = match (list_kind, heading_level) {
+            (Some(kind), _) => NodePayload::list(
+                kind,
+                ListMetadata {
+                    content: SharedString::from(rest),
+                    spans,
+                    checked: false,
+                },
+                true,
+            ),
+            (None, Some(level)) => NodePayload::Heading((
+                HeadingMetadata {
+                    level,
+                    content: SharedString::from(rest),
+                },
+                true,
+            )),
+            (None, None) => return,
+        };
+
+        let state = self.state.clone();
+        state.update(cx, |state, cx| {
+            let node =
+                RemindrElement::create_node_with_id(node_id, payload, &self.state, window, cx);
+            if let Some(list) = node.element.as_list() {
+                let rich_text = list.read(cx).rich_text_state().clone();
+                rich_text.update(cx, |state, cx| state.move_to_start(cx));
+            }
+            state.replace_node(node_id, &node);
+        });
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
     fn handle_delete(&mut self, _window: &mut Window, _cx: &mut Context<Self>) {
         // Reserved for future use
     }
@@ -140,26 +264,7 @@ impl TextNode {
                 state.remove_node(node_id);
 
                 if let Some(previous_element) = previous_element {
-                    if let RemindrElement::Text(element) = previous_element.element.clone() {
-                        let rich_text = element.read(inner_cx).rich_text_state.clone();
-                        rich_text.update(inner_cx, |state, cx| {
-                            state.focus(window, cx);
-                            state.move_to_end(cx);
-                        });
-                    }
-
-                    if let RemindrElement::Heading(element) = previous_element.element.clone() {
-                        element.update(inner_cx, |heading, inner_cx| {
-                            heading.input_state.update(inner_cx, |input, inner_cx| {
-                                input.focus(window, inner_cx);
-                                input.set_cursor_position(
-                                    gpui_component::input::Position::new(u32::MAX, u32::MAX),
-                                    window,
-                                    inner_cx,
-                                );
-                            });
-                        });
-                    }
+                    previous_element.element.focus_end(window, inner_cx);
                 }
 
                 inner_cx.update_global::<DocumentState, _>(|state, app_cx| {
@@ -174,9 +279,24 @@ impl TextNode {
             return;
         }
 
-        // Trim the content
-        let content = self.rich_text_state.read(cx).content().trim().to_string();
+        // "```lang" followed by Enter turns the block into a code block
+        let fence = self.rich_text_state.read(cx).content().trim().to_string();
+        if let Some(language) = fence.strip_prefix("```") {
+            self.turn_into_code(language.trim(), window, cx);
+            return;
+        }
+
+        // Trim the content, keeping the spans aligned with the trimmed text
+        let (content, spans) = {
+            let rich_text = self.rich_text_state.read(cx);
+            let raw = rich_text.content();
+            let offset = raw.len() - raw.trim_start().len();
+            let content = raw.trim().to_string();
+            let spans = shift_spans_left(rich_text.spans(), offset, content.len());
+            (content, spans)
+        };
         self.data.metadata.content = SharedString::from(content);
+        self.data.metadata.spans = spans;
 
         self.is_focus = false;
 
@@ -197,6 +317,30 @@ impl TextNode {
         });
     }
 
+    fn turn_into_code(&mut self, language: &str, window: &mut Window, cx: &mut Context<Self>) {
+        let node_id = self.data.id;
+        let mut metadata = CodeMetadata::default();
+        if !language.is_empty() {
+            metadata.language = language.to_lowercase();
+        }
+
+        let state = self.state.clone();
+        state.update(cx, |state, cx| {
+            let node = RemindrElement::create_node_with_id(
+                node_id,
+                NodePayload::Code((metadata, true)),
+                &self.state,
+                window,
+                cx,
+            );
+            state.replace_node(node_id, &node);
+        });
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+
     pub fn rich_text_state(&self) -> &Entity<RichTextState> {
         &self.rich_text_state
     }
@@ -222,7 +366,7 @@ impl NodeMenuProvider for TextNode {
         let levels: Vec<(u32, &'static str)> =
             vec![(2, "icons/heading-2.svg"), (3, "icons/heading-3.svg")];
 
-        levels
+        let mut items: Vec<NodeMenuItem> = levels
             .into_iter()
             .map(|(level, icon)| {
                 let content = content.clone();
@@ -252,17 +396,83 @@ impl NodeMenuProvider for TextNode {
                     },
                 )
             })
-            .collect()
+            .collect();
+
+        for kind in ListKind::ALL {
+            let metadata = ListMetadata {
+                content: content.clone(),
+                spans: self.data.metadata.spans.clone(),
+                checked: false,
+            };
+            items.push(NodeMenuItem::new(
+                format!("transform-to-{}", kind.node_type()),
+                kind.label(),
+                kind.icon_path(),
+                move |state, window, cx| {
+                    let metadata = metadata.clone();
+                    let state_clone = state.clone();
+                    state.update(cx, |state, cx| {
+                        let node = RemindrElement::create_node_with_id(
+                            node_id,
+                            NodePayload::list(kind, metadata, true),
+                            &state_clone,
+                            window,
+                            cx,
+                        );
+                        state.replace_node(node_id, &node);
+                    });
+                },
+            ));
+        }
+
+        items.push(NodeMenuItem::new(
+            "transform-to-code",
+            "Code",
+            "icons/code.svg",
+            move |state, window, cx| {
+                let content = content.clone();
+                let state_clone = state.clone();
+                state.update(cx, |state, cx| {
+                    let node = RemindrElement::create_node_with_id(
+                        node_id,
+                        NodePayload::Code((
+                            CodeMetadata {
+                                content,
+                                ..Default::default()
+                            },
+                            true,
+                        )),
+                        &state_clone,
+                        window,
+                        cx,
+                    );
+                    state.replace_node(node_id, &node);
+                });
+            },
+        ));
+
+        items
+    }
+}
+
+impl StylableNode for TextNode {
+    fn rich_text(&self) -> &Entity<RichTextState> {
+        &self.rich_text_state
+    }
+
+    fn style_toolbar(&self) -> &Entity<StyleToolbar> {
+        &self.style_toolbar
     }
 }
 
 impl Render for TextNode {
-    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         div()
             .min_w(px(820.0))
             .w_full()
             .my_2()
             .child(RichTextView::new(self.rich_text_state.clone()).ml_3())
             .child(self.menu.clone())
+            .children(self.render_style_toolbar(window, cx))
     }
 }
@@ -0,0 +1 @@
+pub mod unknown_node;
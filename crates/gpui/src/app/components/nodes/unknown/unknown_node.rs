@@ -0,0 +1,141 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, Sizable,
+    button::{Button, ButtonVariants},
+    h_flex, v_flex,
+};
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::app::{
+    components::nodes::menu_provider::{NodeMenuItem, NodeMenuProvider},
+    states::{document_state::DocumentState, node_state::NodeState},
+};
+
+/// Node that could not be parsed, either from a newer version or corrupted. Its data is
+/// written back untouched so that nothing is lost by opening the document.
+pub struct UnknownNode {
+    pub id: Uuid,
+    /// The node as stored, without the `children` loaded as nodes of their own
+    pub data: Value,
+    pub error: SharedString,
+    pub state: Entity<NodeState>,
+    show_raw: bool,
+}
+
+impl UnknownNode {
+    pub fn new(id: Uuid, value: &Value, error: String, state: &Entity<NodeState>) -> Self {
+        let mut data = value.clone();
+        if data.get("children").is_some_and(Value::is_array) {
+            if let Some(object) = data.as_object_mut() {
+                object.remove("children");
+            }
+        }
+
+        Self {
+            id,
+            data,
+            error: error.into(),
+            state: state.clone(),
+            show_raw: false,
+        }
+    }
+
+    fn title(&self) -> String {
+        match self.data.get("type").and_then(Value::as_str) {
+            Some(node_type) => format!("Unsupported block \"{}\"", node_type),
+            None => "Invalid block".to_string(),
+        }
+    }
+
+    fn toggle_raw(&mut self, cx: &mut Context<Self>) {
+        self.show_raw = !self.show_raw;
+        cx.notify();
+    }
+
+    fn delete(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.state.update(cx, |state, cx| {
+            state.remove_node(self.id);
+            cx.notify();
+        });
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+    }
+}
+
+impl NodeMenuProvider for UnknownNode {
+    fn menu_items(&self, _cx: &App) -> Vec<NodeMenuItem> {
+        vec![]
+    }
+}
+
+impl Render for UnknownNode {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let raw = serde_json::to_string_pretty(&self.data).unwrap_or_default();
+
+        v_flex()
+            .my_1()
+            .px_3()
+            .py_2()
+            .gap_2()
+            .w_full()
+            .rounded_md()
+            .border_1()
+            .border_dashed()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().muted.opacity(0.4))
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Icon::default()
+                            .path("icons/triangle-alert.svg")
+                            .size_4()
+                            .text_color(cx.theme().warning),
+                    )
+                    .child(
+                        v_flex()
+                            .flex_1()
+                            .min_w_0()
+                            .child(div().text_sm().font_semibold().child(self.title()))
+                            .child(
+                                div()
+                                    .text_xs()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .child(self.error.clone()),
+                            ),
+                    )
+                    .child(
+                        Button::new("view-raw-node")
+                            .xsmall()
+                            .ghost()
+                            .label(if self.show_raw {
+                                "Hide raw"
+                            } else {
+                                "View raw"
+                            })
+                            .on_click(cx.listener(|this, _, _, cx| this.toggle_raw(cx))),
+                    )
+                    .child(
+                        Button::new("delete-unknown-node")
+                            .xsmall()
+                            .ghost()
+                            .label("Delete")
+                            .on_click(cx.listener(|this, _, window, cx| this.delete(window, cx))),
+                    ),
+            )
+            .when(self.show_raw, |this| {
+                this.child(
+                    div()
+                        .p_2()
+                        .rounded_md()
+                        .bg(cx.theme().background)
+                        .font_family("monospace")
+                        .text_xs()
+                        .child(raw),
+                )
+            })
+    }
+}
@@ -1,15 +1,18 @@
 use std::{ops::Range, time::Duration};
 
 use gpui::{
-    App, Bounds, ClipboardItem, Context, ElementInputHandler, Entity, EntityInputHandler,
-    EventEmitter, FocusHandle, Focusable, FontStyle, FontWeight, HighlightStyle,
-    InteractiveElement, IntoElement, KeyBinding, KeyDownEvent, MouseButton, MouseDownEvent,
-    MouseMoveEvent, MouseUpEvent, ParentElement, Pixels, Point, Refineable, RenderOnce,
-    SharedString, StrikethroughStyle, StyleRefinement, Styled, Task, Timer, UTF16Selection,
-    UnderlineStyle, Window, actions, canvas, div, prelude::FluentBuilder, px,
+    App, Bounds, ClipboardEntry, ClipboardItem, Context, ElementInputHandler, Entity,
+    EntityInputHandler, EventEmitter, FocusHandle, Focusable, FontStyle, FontWeight,
+    HighlightStyle, Hsla, Image, InteractiveElement, IntoElement, KeyBinding, KeyDownEvent,
+    MouseButton, MouseDownEvent, MouseMoveEvent, MouseUpEvent, ParentElement, Pixels, Point,
+    Refineable, RenderOnce, SharedString, StrikethroughStyle, StyleRefinement, Styled, Task, Timer,
+    UTF16Selection, UnderlineStyle, Window, actions, canvas, div, prelude::FluentBuilder, px,
 };
 use gpui_component::{ActiveTheme, menu::ContextMenuExt};
 use serde::{Deserialize, Serialize};
+use serde_json::from_value;
+
+use crate::{app::components::link_editor::LinkEditor, collab::document::TextDelta};
 
 // Actions for keyboard handling
 actions!(
@@ -19,6 +22,7 @@ actions!(
         Delete,
         Enter,
         Tab,
+        ShiftTab,
         Space,
         Slash,
         MoveLeft,
@@ -44,6 +48,7 @@ actions!(
         ToggleUnderline,
         ToggleStrikethrough,
         ToggleCode,
+        EditLink,
         ShowCharacterPalette,
     ]
 );
@@ -56,6 +61,7 @@ pub fn init(cx: &mut App) {
         KeyBinding::new("delete", Delete, Some(CONTEXT)),
         KeyBinding::new("enter", Enter, Some(CONTEXT)),
         KeyBinding::new("tab", Tab, Some(CONTEXT)),
+        KeyBinding::new("shift-tab", ShiftTab, Some(CONTEXT)),
         KeyBinding::new("space", Space, Some(CONTEXT)),
         KeyBinding::new("/", Slash, Some(CONTEXT)),
         KeyBinding::new("left", MoveLeft, Some(CONTEXT)),
@@ -116,6 +122,11 @@ pub fn init(cx: &mut App) {
         KeyBinding::new("cmd-u", ToggleUnderline, Some(CONTEXT)),
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("ctrl-u", ToggleUnderline, Some(CONTEXT)),
+        // Takes over the search palette shortcut while editing, cmd-p still opening it
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k", EditLink, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k", EditLink, Some(CONTEXT)),
         #[cfg(target_os = "macos")]
         KeyBinding::new("cmd-up", MoveToStart, Some(CONTEXT)),
         #[cfg(target_os = "macos")]
@@ -133,10 +144,46 @@ pub enum RichTextStyle {
     Underline,
     Strikethrough,
     Code,
+    /// Link to another document of the workspace, shown as a chip
+    DocumentLink {
+        id: i32,
+    },
+    /// Hyperlink opened in the browser
+    Link {
+        href: String,
+    },
+    /// Text color, the name of a `PALETTE` entry
+    Color(String),
+    /// Background color, the name of a `PALETTE` entry
+    Highlight(String),
+}
+
+/// Colors offered for the text and its highlight. Spans keep the name and the color is
+/// taken from the theme when painting, so it reads well in light and dark themes.
+pub const PALETTE: [&str; 7] = ["gray", "red", "yellow", "green", "cyan", "blue", "magenta"];
+
+/// Color of the palette entry `name` in the current theme
+pub fn palette_color(name: &str, cx: &App) -> Option<Hsla> {
+    let theme = cx.theme();
+    match name {
+        "gray" => Some(theme.muted_foreground),
+        "red" => Some(theme.red),
+        "yellow" => Some(theme.yellow),
+        "green" => Some(theme.green),
+        "cyan" => Some(theme.cyan),
+        "blue" => Some(theme.blue),
+        "magenta" => Some(theme.magenta),
+        _ => None,
+    }
+}
+
+/// Background of the palette entry `name`, light enough to keep the text readable
+pub fn palette_highlight(name: &str, cx: &App) -> Option<Hsla> {
+    palette_color(name, cx).map(|color| color.opacity(0.2))
 }
 
 /// A span of styled text
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TextSpan {
     pub start: usize,
     pub end: usize,
@@ -157,6 +204,134 @@ impl TextSpan {
     }
 }
 
+/// Realign spans after `offset` bytes were removed from the start of their text,
+/// the remaining text being `len` bytes long.
+pub fn shift_spans_left(spans: &[TextSpan], offset: usize, len: usize) -> Vec<TextSpan> {
+    spans
+        .iter()
+        .filter_map(|span| {
+            let start = span.start.saturating_sub(offset).min(len);
+            let end = span.end.saturating_sub(offset).min(len);
+            (start < end).then(|| TextSpan::new(start, end, span.style.clone()))
+        })
+        .collect()
+}
+
+/// Edit of the text of a block, recorded in the history of its document
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    /// Byte offset where `removed` was replaced by `inserted`
+    pub start: usize,
+    pub removed: String,
+    pub inserted: String,
+    pub spans_before: Vec<TextSpan>,
+    pub spans_after: Vec<TextSpan>,
+    pub selection_before: Selection,
+    pub selection_after: Selection,
+}
+
+impl TextEdit {
+    /// Move the edit past a change a collaborator made to the text, `undone` telling whether
+    /// the text is as before the edit. `false` when the change touched the edited part.
+    pub fn rebase(&mut self, change: TextChange, undone: bool) -> bool {
+        let (current_len, other_len) = if undone {
+            (self.removed.len(), self.inserted.len())
+        } else {
+            (self.inserted.len(), self.removed.len())
+        };
+
+        let after_edit = change.start >= self.start + current_len;
+        if change.start + change.removed <= self.start {
+            self.start = self.start + change.inserted - change.removed;
+        } else if !after_edit {
+            return false;
+        }
+
+        // Past the edit, the text it was not made to is offset by the length it changed
+        let other_change = if after_edit {
+            TextChange {
+                start: change.start + other_len - current_len,
+                ..change
+            }
+        } else {
+            change
+        };
+        let (before, after) = if undone {
+            (change, other_change)
+        } else {
+            (other_change, change)
+        };
+
+        for (spans, selection, change) in [
+            (&mut self.spans_before, &mut self.selection_before, before),
+            (&mut self.spans_after, &mut self.selection_after, after),
+        ] {
+            spans.retain_mut(|span| {
+                span.start = change.map(span.start);
+                span.end = change.map(span.end);
+                span.start < span.end
+            });
+            *selection = Selection::new(change.map(selection.start), change.map(selection.end));
+        }
+
+        true
+    }
+}
+
+/// Replacement made by a collaborator in a text, of `removed` bytes at `start` by
+/// `inserted` bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextChange {
+    pub start: usize,
+    pub removed: usize,
+    pub inserted: usize,
+}
+
+impl TextChange {
+    /// Where `offset` is once the change is made, the replaced text moving to the end of
+    /// the inserted one
+    pub fn map(&self, offset: usize) -> usize {
+        if offset <= self.start {
+            offset
+        } else if offset >= self.start + self.removed {
+            offset + self.inserted - self.removed
+        } else {
+            self.start + self.inserted
+        }
+    }
+}
+
+/// Lengths of the start and of the end `old` and `new` have in common, in bytes
+fn common_affixes(old: &str, new: &str) -> (usize, usize) {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .take_while(|((_, old), new)| old == new)
+        .last()
+        .map(|((index, old), _)| index + old.len_utf8())
+        .unwrap_or(0);
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(old, new)| old == new)
+        .map(|(old, _)| old.len_utf8())
+        .sum::<usize>();
+
+    (prefix, suffix)
+}
+
+/// The pasted text as a link target, when it is a single URL
+fn parse_url(text: &str) -> Option<&str> {
+    let text = text.trim();
+    let is_url = ["http://", "https://", "mailto:"]
+        .iter()
+        .any(|scheme| text.len() > scheme.len() && text.starts_with(scheme))
+        && !text.contains(char::is_whitespace);
+
+    is_url.then_some(text)
+}
+
 /// Events emitted by RichText
 #[derive(Clone)]
 pub enum RichTextEvent {
@@ -165,14 +340,26 @@ pub enum RichTextEvent {
     Blur,
     Enter,
     Tab,
+    ShiftTab,
     Backspace,
     Delete,
     Space,
     Slash,
+    /// `[[` was typed, asking for a document to link
+    LinkTrigger,
+    /// A document link was clicked
+    OpenDocument(i32),
+    /// An image was pasted, the owner stores it as a block of its own
+    PasteImage(Image),
+    /// The text was edited, the owner records the edit in its history
+    HistoryPush(TextEdit),
+    /// Undo or redo requested, the owner decides which history it applies to
+    Undo,
+    Redo,
 }
 
 /// Selection in the text
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Selection {
     pub start: usize,
     pub end: usize,
@@ -299,6 +486,14 @@ impl BlinkCursor {
     }
 }
 
+/// Selection of a collaborator editing the same text
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerCursor {
+    pub name: SharedString,
+    pub color: Hsla,
+    pub selection: Selection,
+}
+
 /// State for the RichText component
 pub struct RichTextState {
     pub focus_handle: FocusHandle,
@@ -308,10 +503,12 @@ pub struct RichTextState {
     blink_cursor: BlinkCursor,
     is_selecting: bool,
     last_bounds: Option<Bounds<Pixels>>,
-    history: Vec<(String, Vec<TextSpan>, Selection)>,
-    history_index: usize,
+    /// Text as of the last recorded edit
+    committed: (String, Vec<TextSpan>, Selection),
     marked_range: Option<Range<usize>>,
     wrapped_line_count: usize,
+    peer_cursors: Vec<PeerCursor>,
+    hovered_link: Option<Range<usize>>,
 }
 
 impl EventEmitter<RichTextEvent> for RichTextState {}
@@ -331,10 +528,11 @@ impl RichTextState {
             blink_cursor: BlinkCursor::new(),
             is_selecting: false,
             last_bounds: None,
-            history: vec![(String::new(), Vec::new(), Selection::default())],
+            committed: (String::new(), Vec::new(), Selection::default()),
             wrapped_line_count: 1,
-            history_index: 0,
             marked_range: None,
+            peer_cursors: Vec::new(),
+            hovered_link: None,
         }
     }
 
@@ -584,12 +782,163 @@ impl RichTextState {
             _ => {
                 // Single click: position cursor
                 self.selection = Selection::cursor(cursor_pos);
+
+                if let Some(id) = self.document_link_at(cursor_pos) {
+                    cx.emit(RichTextEvent::OpenDocument(id));
+                } else if let Some((_, href)) = self.link_at(cursor_pos) {
+                    cx.open_url(&href);
+                }
+            }
+        }
+
+        cx.notify();
+    }
+
+    /// Document linked by the text at `index`
+    pub fn document_link_at(&self, index: usize) -> Option<i32> {
+        self.spans.iter().find_map(|span| match span.style {
+            RichTextStyle::DocumentLink { id } if span.start <= index && index < span.end => {
+                Some(id)
+            }
+            _ => None,
+        })
+    }
+
+    /// Insert a link to the document `id` at the cursor, replacing the `[[` typed before it
+    pub fn insert_document_link(&mut self, id: i32, title: &str, cx: &mut Context<Self>) {
+        self.blink_cursor.pause(cx);
+
+        let (mut start, end) = self.selection.normalized();
+        if start == end && self.content[..start].ends_with("[[") {
+            start -= 2;
+        }
+
+        if start != end {
+            self.delete_range(start, end);
+        }
+        self.content.insert_str(start, title);
+        self.shift_spans(start, title.len());
+
+        // Typing right after the link does not extend it, see `shift_spans`
+        self.spans.push(TextSpan::new(
+            start,
+            start + title.len(),
+            RichTextStyle::DocumentLink { id },
+        ));
+        self.merge_spans();
+
+        self.selection = Selection::cursor(start + title.len());
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+    }
+
+    /// Hyperlink covering the text at `index`, with its range
+    pub fn link_at(&self, index: usize) -> Option<(Range<usize>, String)> {
+        self.spans.iter().find_map(|span| match &span.style {
+            RichTextStyle::Link { href } if span.start <= index && index < span.end => {
+                Some((span.start..span.end, href.clone()))
             }
+            _ => None,
+        })
+    }
+
+    /// Link `range` to `href`, replacing the links it had, or unlink it when `href` is `None`
+    pub fn set_link(&mut self, range: Range<usize>, href: Option<String>, cx: &mut Context<Self>) {
+        let end = range.end.min(self.content.len());
+        let start = range.start.min(end);
+        if start == end
+            || !self.content.is_char_boundary(start)
+            || !self.content.is_char_boundary(end)
+        {
+            return;
         }
 
+        self.replace_style(
+            start,
+            end,
+            |style| matches!(style, RichTextStyle::Link { .. }),
+            href.map(|href| RichTextStyle::Link { href }),
+        );
+
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+    }
+
+    /// Insert `href` at the cursor as a link to itself
+    pub fn insert_link(&mut self, href: &str, cx: &mut Context<Self>) {
+        self.blink_cursor.pause(cx);
+
+        let (start, end) = self.selection.normalized();
+        if start != end {
+            self.delete_range(start, end);
+        }
+        self.content.insert_str(start, href);
+        self.shift_spans(start, href.len());
+
+        // Typing right after the link does not extend it, see `shift_spans`
+        self.spans.push(TextSpan::new(
+            start,
+            start + href.len(),
+            RichTextStyle::Link {
+                href: href.to_string(),
+            },
+        ));
+        self.merge_spans();
+
+        self.selection = Selection::cursor(start + href.len());
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
         cx.notify();
     }
 
+    /// Link under the mouse, shown with a pointer cursor
+    pub fn hovered_link(&self) -> Option<Range<usize>> {
+        self.hovered_link.clone()
+    }
+
+    pub fn handle_hover(
+        &mut self,
+        position: Option<Point<Pixels>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Resolved like a click, so the pointer shows exactly where a click opens the link
+        let hovered_link = position.and_then(|position| {
+            let bounds = self
+                .last_bounds
+                .filter(|bounds| bounds.contains(&position))?;
+            let relative_point = Point {
+                x: position.x - bounds.origin.x,
+                y: position.y - bounds.origin.y,
+            };
+            let index = self.position_from_point(relative_point, window, cx);
+
+            self.spans.iter().find_map(|span| {
+                let is_link = matches!(
+                    span.style,
+                    RichTextStyle::Link { .. } | RichTextStyle::DocumentLink { .. }
+                );
+                (is_link && span.start <= index && index < span.end).then(|| span.start..span.end)
+            })
+        });
+
+        if hovered_link != self.hovered_link {
+            self.hovered_link = hovered_link;
+            cx.notify();
+        }
+    }
+
+    /// Ranges of the document links, painted as chips
+    pub fn document_link_spans(&self) -> Vec<Range<usize>> {
+        self.spans
+            .iter()
+            .filter(|s| matches!(s.style, RichTextStyle::DocumentLink { .. }))
+            .map(|s| s.start..s.end)
+            .collect()
+    }
+
     pub fn handle_mouse_move(
         &mut self,
         position: Point<Pixels>,
@@ -624,7 +973,24 @@ impl RichTextState {
         self.content = content.into();
         self.selection = Selection::cursor(self.content.len());
         self.spans.clear();
-        self.push_history();
+        self.push_history(cx);
+        cx.notify();
+    }
+
+    /// Replace the content along with its formatting spans.
+    /// Spans falling outside of the content are clamped or dropped.
+    pub fn set_rich_content(
+        &mut self,
+        content: impl Into<String>,
+        spans: Vec<TextSpan>,
+        cx: &mut Context<Self>,
+    ) {
+        self.content = content.into();
+        self.selection = Selection::cursor(self.content.len());
+        self.spans = spans;
+        self.clamp_spans();
+        self.merge_spans();
+        self.push_history(cx);
         cx.notify();
     }
 
@@ -649,48 +1015,231 @@ impl RichTextState {
         cx.notify();
     }
 
-    pub fn focus(&self, window: &mut Window, _cx: &mut Context<Self>) {
-        self.focus_handle.focus(window);
+    pub fn peer_cursors(&self) -> &[PeerCursor] {
+        &self.peer_cursors
     }
 
-    fn push_history(&mut self) {
-        // Remove future history if we're not at the end
-        if self.history_index < self.history.len() - 1 {
-            self.history.truncate(self.history_index + 1);
+    pub fn set_peer_cursors(&mut self, cursors: Vec<PeerCursor>, cx: &mut Context<Self>) {
+        if self.peer_cursors != cursors {
+            self.peer_cursors = cursors;
+            cx.notify();
         }
-        self.history
-            .push((self.content.clone(), self.spans.clone(), self.selection));
-        self.history_index = self.history.len() - 1;
+    }
 
-        // Limit history size
-        if self.history.len() > 100 {
-            self.history.remove(0);
-            self.history_index = self.history_index.saturating_sub(1);
+    /// Take the text edited by a collaborator, the local selection staying on the same
+    /// characters. Their edit is not recorded, so undo does not revert it, and is returned
+    /// for the recorded edits to be moved past it.
+    pub fn apply_remote(
+        &mut self,
+        content: String,
+        spans: Vec<TextSpan>,
+        cx: &mut Context<Self>,
+    ) -> Vec<TextChange> {
+        if content == self.content && spans == self.spans {
+            return Vec::new();
+        }
+
+        let (prefix, suffix) = common_affixes(&self.content, &content);
+        let change = TextChange {
+            start: prefix,
+            removed: self.content.len() - suffix - prefix,
+            inserted: content.len() - suffix - prefix,
+        };
+
+        self.selection = Selection::new(
+            change.map(self.selection.start),
+            change.map(self.selection.end),
+        );
+        self.content = content;
+        self.spans = spans;
+        self.clamp_spans();
+        self.merge_spans();
+        self.marked_range = None;
+        self.committed = (self.content.clone(), self.spans.clone(), self.selection);
+
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+
+        if change.removed == 0 && change.inserted == 0 {
+            Vec::new()
+        } else {
+            vec![change]
         }
     }
 
-    fn undo(&mut self, cx: &mut Context<Self>) {
-        if self.history_index > 0 {
-            self.history_index -= 1;
-            let (content, spans, selection) = self.history[self.history_index].clone();
-            self.content = content;
-            self.spans = spans;
-            self.selection = selection;
-            cx.emit(RichTextEvent::Change(self.value()));
-            cx.notify();
+    /// Apply a change a collaborator made to the shared text this one was last synced
+    /// with, step by step so that the local selection and spans follow it. Returned like
+    /// `apply_remote`, or `None` when the change does not fit the text, which is left as is.
+    pub fn apply_delta(
+        &mut self,
+        delta: &[TextDelta],
+        cx: &mut Context<Self>,
+    ) -> Option<Vec<TextChange>> {
+        let previous = (self.content.clone(), self.spans.clone(), self.selection);
+        let changes = self.apply_delta_steps(delta);
+        if changes.is_none() {
+            (self.content, self.spans, self.selection) = previous;
+            return None;
         }
+
+        self.clamp_spans();
+        self.merge_spans();
+        self.marked_range = None;
+        self.committed = (self.content.clone(), self.spans.clone(), self.selection);
+
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+        changes
     }
 
-    fn redo(&mut self, cx: &mut Context<Self>) {
-        if self.history_index < self.history.len() - 1 {
-            self.history_index += 1;
-            let (content, spans, selection) = self.history[self.history_index].clone();
-            self.content = content;
-            self.spans = spans;
-            self.selection = selection;
-            cx.emit(RichTextEvent::Change(self.value()));
-            cx.notify();
+    fn apply_delta_steps(&mut self, delta: &[TextDelta]) -> Option<Vec<TextChange>> {
+        let parse = |style: &serde_json::Value| from_value::<RichTextStyle>(style.clone()).ok();
+        let mut at = 0;
+        let mut changes = Vec::new();
+
+        for step in delta {
+            match step {
+                TextDelta::Retain { len, styles } => {
+                    let end = at + len;
+                    if !self.content.is_char_boundary(end) {
+                        return None;
+                    }
+
+                    for (style, set) in styles {
+                        let Some(style) = parse(style) else {
+                            continue;
+                        };
+                        if *set {
+                            self.spans.push(TextSpan::new(at, end, style));
+                        } else {
+                            self.remove_style(at, end, &style);
+                        }
+                    }
+                    at = end;
+                }
+                TextDelta::Insert { text, styles } => {
+                    if !self.content.is_char_boundary(at) {
+                        return None;
+                    }
+
+                    let end = at + text.len();
+                    self.content.insert_str(at, text);
+                    self.shift_spans(at, text.len());
+
+                    // The inserted text has the styles it was inserted with, and only those
+                    let styles: Vec<RichTextStyle> = styles.iter().filter_map(parse).collect();
+                    let extended: Vec<RichTextStyle> = self
+                        .spans
+                        .iter()
+                        .filter(|span| span.overlaps(at, end) && !styles.contains(&span.style))
+                        .map(|span| span.style.clone())
+                        .collect();
+                    for style in &extended {
+                        self.remove_style(at, end, style);
+                    }
+                    for style in styles {
+                        self.spans.push(TextSpan::new(at, end, style));
+                    }
+
+                    self.follow_change(
+                        TextChange {
+                            start: at,
+                            removed: 0,
+                            inserted: text.len(),
+                        },
+                        &mut changes,
+                    );
+                    at = end;
+                }
+                TextDelta::Delete { len } => {
+                    let end = at + len;
+                    if !self.content.is_char_boundary(at) || !self.content.is_char_boundary(end) {
+                        return None;
+                    }
+
+                    self.delete_range(at, end);
+                    self.follow_change(
+                        TextChange {
+                            start: at,
+                            removed: *len,
+                            inserted: 0,
+                        },
+                        &mut changes,
+                    );
+                }
+            }
         }
+
+        Some(changes)
+    }
+
+    fn follow_change(&mut self, change: TextChange, changes: &mut Vec<TextChange>) {
+        self.selection = Selection::new(
+            change.map(self.selection.start),
+            change.map(self.selection.end),
+        );
+        changes.push(change);
+    }
+
+    pub fn focus(&self, window: &mut Window, _cx: &mut Context<Self>) {
+        self.focus_handle.focus(window);
+    }
+
+    fn push_history(&mut self, cx: &mut Context<Self>) {
+        let current = (self.content.clone(), self.spans.clone(), self.selection);
+        let (content, spans_before, selection_before) =
+            std::mem::replace(&mut self.committed, current);
+        if content == self.content && spans_before == self.spans {
+            return;
+        }
+
+        let (prefix, suffix) = common_affixes(&content, &self.content);
+        cx.emit(RichTextEvent::HistoryPush(TextEdit {
+            start: prefix,
+            removed: content[prefix..content.len() - suffix].to_string(),
+            inserted: self.content[prefix..self.content.len() - suffix].to_string(),
+            spans_before,
+            spans_after: self.spans.clone(),
+            selection_before,
+            selection_after: self.selection,
+        }));
+    }
+
+    /// Revert an edit, or make it again. The text is left untouched when the part the edit
+    /// changed was edited since.
+    pub fn apply_edit(&mut self, edit: &TextEdit, undo: bool, cx: &mut Context<Self>) {
+        let (removed, inserted, spans, selection) = if undo {
+            (
+                &edit.inserted,
+                &edit.removed,
+                &edit.spans_before,
+                edit.selection_before,
+            )
+        } else {
+            (
+                &edit.removed,
+                &edit.inserted,
+                &edit.spans_after,
+                edit.selection_after,
+            )
+        };
+
+        let end = edit.start + removed.len();
+        if self.content.get(edit.start..end) != Some(removed.as_str()) {
+            return;
+        }
+
+        self.content.replace_range(edit.start..end, inserted);
+        self.spans = spans.clone();
+        self.clamp_spans();
+        self.merge_spans();
+        let len = self.content.len();
+        self.selection = Selection::new(selection.start.min(len), selection.end.min(len));
+        self.marked_range = None;
+        self.committed = (self.content.clone(), self.spans.clone(), self.selection);
+
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
     }
 
     pub fn insert_text(&mut self, text: &str, cx: &mut Context<Self>) {
@@ -706,21 +1255,37 @@ impl RichTextState {
         // Insert new text
         self.content.insert_str(start, text);
 
-        // Update spans
         let insert_len = text.len();
+        self.shift_spans(start, insert_len);
+
+        self.selection = Selection::cursor(start + insert_len);
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+    }
+
+    /// Shift spans to account for `len` bytes inserted at `start`
+    fn shift_spans(&mut self, start: usize, len: usize) {
         for span in &mut self.spans {
             if span.start >= start {
-                span.start += insert_len;
-                span.end += insert_len;
+                span.start += len;
+                span.end += len;
             } else if span.end > start {
-                span.end += insert_len;
+                span.end += len;
             }
         }
+    }
 
-        self.selection = Selection::cursor(start + insert_len);
-        self.push_history();
-        cx.emit(RichTextEvent::Change(self.value()));
-        cx.notify();
+    /// Clamp spans to the content length and drop the ones left empty
+    fn clamp_spans(&mut self) {
+        let len = self.content.len();
+        self.spans.retain_mut(|span| {
+            span.end = span.end.min(len);
+            span.start = span.start.min(span.end);
+            span.start < span.end
+                && self.content.is_char_boundary(span.start)
+                && self.content.is_char_boundary(span.end)
+        });
     }
 
     fn delete_range(&mut self, start: usize, end: usize) {
@@ -776,7 +1341,7 @@ impl RichTextState {
             self.selection = Selection::cursor(prev_pos);
         }
 
-        self.push_history();
+        self.push_history(cx);
         cx.emit(RichTextEvent::Change(self.value()));
         cx.emit(RichTextEvent::Backspace);
         cx.notify();
@@ -800,7 +1365,7 @@ impl RichTextState {
             self.delete_range(start, next_pos);
         }
 
-        self.push_history();
+        self.push_history(cx);
         cx.emit(RichTextEvent::Change(self.value()));
         cx.emit(RichTextEvent::Delete);
         cx.notify();
@@ -938,7 +1503,7 @@ impl RichTextState {
         }
     }
 
-    fn move_to_start(&mut self, cx: &mut Context<Self>) {
+    pub fn move_to_start(&mut self, cx: &mut Context<Self>) {
         self.selection = Selection::cursor(0);
         cx.notify();
     }
@@ -967,7 +1532,7 @@ impl RichTextState {
         if start != end {
             self.delete_range(start, end);
             self.selection = Selection::cursor(start);
-            self.push_history();
+            self.push_history(cx);
             cx.emit(RichTextEvent::Change(self.value()));
             cx.notify();
         }
@@ -976,7 +1541,22 @@ impl RichTextState {
     fn paste(&mut self, cx: &mut Context<Self>) {
         if let Some(clipboard) = cx.read_from_clipboard() {
             if let Some(text) = clipboard.text() {
-                self.insert_text(text.as_ref(), cx);
+                // A URL pasted over a selection links it instead of replacing it
+                let (start, end) = self.selection.normalized();
+                match parse_url(&text) {
+                    Some(href) if start != end => {
+                        self.set_link(start..end, Some(href.to_string()), cx)
+                    }
+                    _ => self.insert_text(text.as_ref(), cx),
+                }
+            } else {
+                let image = clipboard.entries().iter().find_map(|entry| match entry {
+                    ClipboardEntry::Image(image) => Some(image.clone()),
+                    _ => None,
+                });
+                if let Some(image) = image {
+                    cx.emit(RichTextEvent::PasteImage(image));
+                }
             }
         }
     }
@@ -1002,10 +1582,135 @@ impl RichTextState {
             self.merge_spans();
         }
 
-        self.push_history();
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
         cx.notify();
     }
 
+    /// Window bounds of the selected text, where the style toolbar is anchored
+    pub fn selection_bounds(&self, window: &Window) -> Option<Bounds<Pixels>> {
+        let (start, end) = self.selection.normalized();
+        self.range_bounds(start..end, self.last_bounds?, window)
+    }
+
+    /// Window bounds of the text in `range`, `bounds` being those of the whole text. A range
+    /// wrapping over several visual lines gets their full width.
+    fn range_bounds(
+        &self,
+        range: Range<usize>,
+        bounds: Bounds<Pixels>,
+        window: &Window,
+    ) -> Option<Bounds<Pixels>> {
+        let text_style = window.text_style();
+        let font_size = text_style.font_size.to_pixels(window.rem_size());
+        let line_height = font_size * 1.5;
+
+        let display_content = if self.content.is_empty() {
+            " ".to_string()
+        } else {
+            self.content.clone()
+        };
+        let wrapped = window
+            .text_system()
+            .shape_text(
+                SharedString::from(display_content.clone()),
+                font_size,
+                &[text_style.to_run(display_content.len())],
+                Some(bounds.size.width),
+                None,
+            )
+            .ok()?;
+        let position = |index: usize| {
+            wrapped
+                .iter()
+                .find_map(|line| line.position_for_index(index, line_height))
+        };
+
+        let start = position(range.start.min(self.content.len()))?;
+        let end = position(range.end.min(self.content.len())).unwrap_or(start);
+        let (x_start, x_end) = if (end.y - start.y).abs() < px(1.0) {
+            (start.x, end.x.max(start.x))
+        } else {
+            (px(0.0), bounds.size.width)
+        };
+
+        Some(Bounds::new(
+            gpui::point(bounds.left() + x_start, bounds.top() + start.y),
+            gpui::size(x_end - x_start, end.y - start.y + line_height),
+        ))
+    }
+
+    /// Styles covering the whole selection
+    pub fn selection_styles(&self) -> Vec<RichTextStyle> {
+        let (start, end) = self.selection.normalized();
+        self.spans
+            .iter()
+            .filter(|span| span.contains(start, end))
+            .map(|span| span.style.clone())
+            .collect()
+    }
+
+    /// Color the selected text with the palette entry `name`, or reset its color
+    pub fn set_text_color(&mut self, name: Option<&str>, cx: &mut Context<Self>) {
+        self.set_selection_style(
+            |style| matches!(style, RichTextStyle::Color(_)),
+            name.map(|name| RichTextStyle::Color(name.to_string())),
+            cx,
+        );
+    }
+
+    /// Highlight the selected text with the palette entry `name`, or remove its highlight
+    pub fn set_highlight(&mut self, name: Option<&str>, cx: &mut Context<Self>) {
+        self.set_selection_style(
+            |style| matches!(style, RichTextStyle::Highlight(_)),
+            name.map(|name| RichTextStyle::Highlight(name.to_string())),
+            cx,
+        );
+    }
+
+    fn set_selection_style(
+        &mut self,
+        same_kind: fn(&RichTextStyle) -> bool,
+        style: Option<RichTextStyle>,
+        cx: &mut Context<Self>,
+    ) {
+        let (start, end) = self.selection.normalized();
+        if start == end {
+            return;
+        }
+
+        self.replace_style(start, end, same_kind, style);
+
+        self.push_history(cx);
+        cx.emit(RichTextEvent::Change(self.value()));
+        cx.notify();
+    }
+
+    /// Remove the styles of a kind from a range, then apply `style` to it. A range has
+    /// at most one style of the kinds carrying a value, such as a link or a color.
+    fn replace_style(
+        &mut self,
+        start: usize,
+        end: usize,
+        same_kind: impl Fn(&RichTextStyle) -> bool,
+        style: Option<RichTextStyle>,
+    ) {
+        let replaced: Vec<RichTextStyle> = self
+            .spans
+            .iter()
+            .filter(|span| same_kind(&span.style) && span.overlaps(start, end))
+            .map(|span| span.style.clone())
+            .collect();
+        for replaced in &replaced {
+            self.remove_style(start, end, replaced);
+        }
+
+        if let Some(style) = style {
+            self.spans.push(TextSpan::new(start, end, style));
+        }
+        self.merge_spans();
+    }
+
     fn remove_style(&mut self, start: usize, end: usize, style: &RichTextStyle) {
         let mut new_spans: Vec<TextSpan> = Vec::new();
 
@@ -1096,6 +1801,39 @@ impl RichTextState {
                         color: Some(theme.accent_foreground),
                         ..Default::default()
                     },
+                    // The chip itself is painted behind the text, see `RichTextView`
+                    RichTextStyle::DocumentLink { .. } => HighlightStyle {
+                        color: Some(theme.primary),
+                        font_weight: Some(FontWeight::MEDIUM),
+                        ..Default::default()
+                    },
+                    RichTextStyle::Link { .. } => {
+                        let is_hovered = self
+                            .hovered_link
+                            .as_ref()
+                            .is_some_and(|range| *range == (span.start..span.end));
+                        HighlightStyle {
+                            color: Some(if is_hovered {
+                                theme.link_hover
+                            } else {
+                                theme.link
+                            }),
+                            underline: Some(UnderlineStyle {
+                                thickness: px(1.0),
+                                color: None,
+                                wavy: false,
+                            }),
+                            ..Default::default()
+                        }
+                    }
+                    RichTextStyle::Color(name) => HighlightStyle {
+                        color: palette_color(name, cx),
+                        ..Default::default()
+                    },
+                    RichTextStyle::Highlight(name) => HighlightStyle {
+                        background_color: palette_highlight(name, cx),
+                        ..Default::default()
+                    },
                 };
                 (span.start..span.end, highlight)
             })
@@ -1304,12 +2042,19 @@ impl EntityInputHandler for RichTextState {
             .or(self.marked_range.clone())
             .unwrap_or(sel_start..sel_end);
 
-        self.content =
-            self.content[..range.start].to_owned() + new_text + &self.content[range.end..];
+        if range.start != range.end {
+            self.delete_range(range.start, range.end);
+        }
+        self.content.insert_str(range.start, new_text);
+        self.shift_spans(range.start, new_text.len());
+
         let new_cursor = range.start + new_text.len();
         self.selection = Selection::cursor(new_cursor);
         self.marked_range = None;
         cx.emit(RichTextEvent::Change(self.content.clone().into()));
+        if new_text == "[" && self.content[..new_cursor].ends_with("[[") {
+            cx.emit(RichTextEvent::LinkTrigger);
+        }
         cx.notify();
     }
 
@@ -1328,8 +2073,11 @@ impl EntityInputHandler for RichTextState {
             .or(self.marked_range.clone())
             .unwrap_or(sel_start..sel_end);
 
-        self.content =
-            self.content[..range.start].to_owned() + new_text + &self.content[range.end..];
+        if range.start != range.end {
+            self.delete_range(range.start, range.end);
+        }
+        self.content.insert_str(range.start, new_text);
+        self.shift_spans(range.start, new_text.len());
 
         if !new_text.is_empty() {
             self.marked_range = Some(range.start..range.start + new_text.len());
@@ -1353,13 +2101,13 @@ impl EntityInputHandler for RichTextState {
 
     fn bounds_for_range(
         &mut self,
-        _range_utf16: Range<usize>,
+        range_utf16: Range<usize>,
         bounds: Bounds<Pixels>,
-        _window: &mut Window,
+        window: &mut Window,
         _cx: &mut Context<Self>,
     ) -> Option<Bounds<Pixels>> {
-        // Return the bounds of the text area for IME positioning
-        Some(bounds)
+        let range = self.range_from_utf16(&range_utf16);
+        self.range_bounds(range, bounds, window)
     }
 
     fn character_index_for_point(
@@ -1402,6 +2150,7 @@ impl RenderOnce for RichTextView {
         let focus_handle = self.state.read(cx).focus_handle.clone();
         let is_focused = focus_handle.is_focused(window);
         let cursor_visible = self.state.read(cx).cursor_visible();
+        let is_link_hovered = self.state.read(cx).hovered_link().is_some();
 
         let text_style = window.text_style();
         let theme = cx.theme();
@@ -1454,6 +2203,12 @@ impl RenderOnce for RichTextView {
                     state.update(cx, |_s, cx| cx.emit(RichTextEvent::Tab));
                 }
             })
+            .on_action({
+                let state = state.clone();
+                move |_: &ShiftTab, _, cx| {
+                    state.update(cx, |_s, cx| cx.emit(RichTextEvent::ShiftTab));
+                }
+            })
             .on_action({
                 let state = state.clone();
                 move |_: &Space, _, cx| {
@@ -1559,13 +2314,13 @@ impl RenderOnce for RichTextView {
             .on_action({
                 let state = state.clone();
                 move |_: &Undo, _, cx| {
-                    state.update(cx, |s, cx| s.undo(cx));
+                    state.update(cx, |_, cx| cx.emit(RichTextEvent::Undo));
                 }
             })
             .on_action({
                 let state = state.clone();
                 move |_: &Redo, _, cx| {
-                    state.update(cx, |s, cx| s.redo(cx));
+                    state.update(cx, |_, cx| cx.emit(RichTextEvent::Redo));
                 }
             })
             .on_action({
@@ -1598,6 +2353,12 @@ impl RenderOnce for RichTextView {
                     state.update(cx, |s, cx| s.apply_style(RichTextStyle::Code, cx));
                 }
             })
+            .on_action({
+                let state = state.clone();
+                move |_: &EditLink, window, cx| {
+                    LinkEditor::open(state.clone(), window, cx);
+                }
+            })
             .on_action({
                 move |_: &ShowCharacterPalette, window, _cx| {
                     window.show_character_palette();
@@ -1623,10 +2384,20 @@ impl RenderOnce for RichTextView {
             .on_mouse_move({
                 let state = state.clone();
                 move |event: &MouseMoveEvent, window, cx| {
-                    if event.dragging() {
-                        state.update(cx, |s, cx| {
+                    state.update(cx, |s, cx| {
+                        if event.dragging() {
                             s.handle_mouse_move(event.position, window, cx);
-                        });
+                        } else {
+                            s.handle_hover(Some(event.position), window, cx);
+                        }
+                    });
+                }
+            })
+            .on_hover({
+                let state = state.clone();
+                move |hovered: &bool, window, cx| {
+                    if !hovered {
+                        state.update(cx, |s, cx| s.handle_hover(None, window, cx));
                     }
                 }
             })
@@ -1639,7 +2410,13 @@ impl RenderOnce for RichTextView {
                 }
             })
             .w_full()
-            .cursor_text()
+            .map(|this| {
+                if is_link_hovered {
+                    this.cursor_pointer()
+                } else {
+                    this.cursor_text()
+                }
+            })
             .min_h(line_height)
             .relative()
             // Selection and cursor overlay (absolute positioned, painted first as background)
@@ -1652,6 +2429,9 @@ impl RenderOnce for RichTextView {
                 let cursor_visible_for_overlay = cursor_visible;
                 let theme_selection = theme.selection;
                 let theme_foreground = theme.foreground;
+                let peer_cursors = state.read(cx).peer_cursors.clone();
+                let link_spans = state.read(cx).document_link_spans();
+                let theme_link_chip = theme.primary.opacity(0.12);
 
                 canvas(
                     move |bounds, _window, cx| {
@@ -1683,6 +2463,53 @@ impl RenderOnce for RichTextView {
                         );
 
                         if let Ok(wrapped) = wrapped {
+                            let position = |index: usize| {
+                                wrapped
+                                    .iter()
+                                    .find_map(|line| line.position_for_index(index, line_height))
+                            };
+
+                            // Paint the chips of the document links, one per visual line
+                            for link in &link_spans {
+                                let (Some(start_pos), Some(end_pos)) =
+                                    (position(link.start), position(link.end))
+                                else {
+                                    continue;
+                                };
+
+                                let first_line = (start_pos.y / line_height).floor() as i32;
+                                let last_line = (end_pos.y / line_height).floor() as i32;
+                                for line_idx in first_line..=last_line {
+                                    let x_start = if line_idx == first_line {
+                                        start_pos.x
+                                    } else {
+                                        px(0.0)
+                                    };
+                                    let x_end = if line_idx == last_line {
+                                        end_pos.x
+                                    } else {
+                                        wrap_width
+                                    };
+                                    if x_end <= x_start {
+                                        continue;
+                                    }
+
+                                    let rect = gpui::Bounds::new(
+                                        gpui::point(
+                                            bounds.left() + x_start - px(2.0),
+                                            bounds.top() + line_idx as f32 * line_height + px(1.0),
+                                        ),
+                                        gpui::size(
+                                            x_end - x_start + px(4.0),
+                                            line_height - px(2.0),
+                                        ),
+                                    );
+                                    window.paint_quad(
+                                        gpui::fill(rect, theme_link_chip).corner_radii(px(4.0)),
+                                    );
+                                }
+                            }
+
                             // Paint selection
                             if is_focused_for_overlay && !selection_for_overlay.is_empty() {
                                 let (sel_start, sel_end) = selection_for_overlay.normalized();
@@ -1750,6 +2577,49 @@ impl RenderOnce for RichTextView {
                                 }
                             }
 
+                            // Paint the selections and carets of the collaborators
+                            for peer in &peer_cursors {
+                                let (sel_start, sel_end) = peer.selection.normalized();
+
+                                if let (Some(start_pos), Some(end_pos)) =
+                                    (position(sel_start), position(sel_end))
+                                {
+                                    // Only selections within a visual line are highlighted
+                                    if (start_pos.y - end_pos.y).abs() < px(1.0)
+                                        && end_pos.x > start_pos.x
+                                    {
+                                        let rect = gpui::Bounds::new(
+                                            gpui::point(
+                                                bounds.left() + start_pos.x,
+                                                bounds.top() + start_pos.y,
+                                            ),
+                                            gpui::size(end_pos.x - start_pos.x, line_height),
+                                        );
+                                        window
+                                            .paint_quad(gpui::fill(rect, peer.color.opacity(0.2)));
+                                    }
+                                }
+
+                                if let Some(head_pos) = position(peer.selection.head()) {
+                                    let origin = gpui::point(
+                                        bounds.left() + head_pos.x,
+                                        bounds.top() + head_pos.y,
+                                    );
+                                    window.paint_quad(gpui::fill(
+                                        gpui::Bounds::new(origin, gpui::size(px(2.0), line_height)),
+                                        peer.color,
+                                    ));
+                                    // Flag on top of the caret, telling it apart from the local one
+                                    window.paint_quad(gpui::fill(
+                                        gpui::Bounds::new(
+                                            gpui::point(origin.x - px(1.5), origin.y - px(2.0)),
+                                            gpui::size(px(5.0), px(4.0)),
+                                        ),
+                                        peer.color,
+                                    ));
+                                }
+                            }
+
                             // Paint cursor
                             if is_focused_for_overlay
                                 && cursor_visible_for_overlay
@@ -1819,6 +2689,8 @@ impl RenderOnce for RichTextView {
                         .menu("Underline", Box::new(ToggleUnderline))
                         .menu("Strikethrough", Box::new(ToggleStrikethrough))
                         .menu("Code", Box::new(ToggleCode))
+                        .separator()
+                        .menu("Link", Box::new(EditLink))
                 } else {
                     menu
                 }
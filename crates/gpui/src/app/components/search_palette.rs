@@ -0,0 +1,248 @@
+use std::time::Duration;
+
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, Sizable, WindowExt, h_flex,
+    input::{Input, InputEvent, InputState, MoveDown, MoveUp},
+    scroll::ScrollableElement,
+    v_flex,
+};
+
+use crate::{
+    app::{
+        screens::document_screen::DocumentScreen,
+        states::{
+            app_state::AppState, document_state::DocumentState, repository_state::RepositoryState,
+        },
+    },
+    domain::database::search::SearchResultModel,
+};
+
+actions!(search_palette, [OpenSearchPalette]);
+
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-k", OpenSearchPalette, None),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-k", OpenSearchPalette, None),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-p", OpenSearchPalette, None),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-p", OpenSearchPalette, None),
+    ]);
+}
+
+/// Full-text search over every document of the active workspace
+pub struct SearchPalette {
+    app_state: Entity<AppState>,
+    search_input: Entity<InputState>,
+    results: Vec<SearchResultModel>,
+    selected_index: usize,
+    _search_task: Task<()>,
+}
+
+impl SearchPalette {
+    pub fn new(app_state: Entity<AppState>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_input =
+            cx.new(|cx| InputState::new(window, cx).placeholder("Search documents..."));
+
+        cx.subscribe_in(
+            &search_input,
+            window,
+            |this, _, event: &InputEvent, window, cx| match event {
+                InputEvent::Change => this.search(cx),
+                InputEvent::PressEnter { .. } => this.confirm_selection(window, cx),
+                _ => {}
+            },
+        )
+        .detach();
+
+        Self {
+            app_state,
+            search_input,
+            results: Vec::new(),
+            selected_index: 0,
+            _search_task: Task::ready(()),
+        }
+    }
+
+    /// Open the palette in a dialog with the search input focused
+    pub fn open(app_state: Entity<AppState>, window: &mut Window, cx: &mut App) {
+        let palette = cx.new(|cx| SearchPalette::new(app_state, window, cx));
+        let search_input = palette.read(cx).search_input.clone();
+        search_input.update(cx, |input, cx| input.focus(window, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .w(px(560.))
+                .p(px(8.))
+                .close_button(false)
+                .overlay_closable(true)
+                .child(palette.clone())
+        });
+    }
+
+    fn search(&mut self, cx: &mut Context<Self>) {
+        let text = self.search_input.read(cx).value().to_string();
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        // Replacing the task cancels the previous search, which debounces typing
+        self._search_task = cx.spawn(async move |this, cx| {
+            smol::Timer::after(Duration::from_millis(150)).await;
+
+            let results = repository.search_documents(&text).await.unwrap_or_default();
+            let _ = this.update(cx, |this, cx| {
+                this.results = results;
+                this.selected_index = 0;
+                cx.notify();
+            });
+        });
+    }
+
+    fn move_selection(&mut self, down: bool, cx: &mut Context<Self>) {
+        if self.results.is_empty() {
+            return;
+        }
+
+        let last = self.results.len() - 1;
+        self.selected_index = match (down, self.selected_index) {
+            (true, index) if index >= last => 0,
+            (true, index) => index + 1,
+            (false, 0) => last,
+            (false, index) => index - 1,
+        };
+        cx.notify();
+    }
+
+    fn confirm_selection(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(result) = self.results.get(self.selected_index).cloned() {
+            self.open_result(result, window, cx);
+        }
+    }
+
+    fn open_result(
+        &mut self,
+        result: SearchResultModel,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        window.close_dialog(cx);
+
+        cx.update_global::<DocumentState, _>(|state, _| {
+            state.open_document(result.document_id, result.title.clone());
+            if let Some(node_id) = result.node_id {
+                state.scroll_to_node(result.document_id, node_id);
+            }
+        });
+
+        self.app_state.update(cx, |app_state, cx| {
+            let document_screen = DocumentScreen::new(cx.weak_entity());
+            app_state.navigator.push(document_screen, cx);
+        });
+    }
+
+    fn render_result(
+        &self,
+        index: usize,
+        result: &SearchResultModel,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_selected = self.selected_index == index;
+        let show_snippet = result.node_id.is_some() && !result.snippet.is_empty();
+
+        h_flex()
+            .id(("search-result", index))
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .items_start()
+            .rounded_md()
+            .cursor_pointer()
+            .when(is_selected, |this| this.bg(cx.theme().accent.opacity(0.5)))
+            .hover(|this| this.bg(cx.theme().accent.opacity(0.5)))
+            .on_click({
+                let result = result.clone();
+                cx.listener(move |this, _, window, cx| {
+                    this.open_result(result.clone(), window, cx);
+                })
+            })
+            .child(
+                Icon::default()
+                    .path("icons/file-text.svg")
+                    .size_4()
+                    .mt_0p5()
+                    .text_color(cx.theme().muted_foreground),
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .min_w_0()
+                    .child(
+                        div()
+                            .text_sm()
+                            .text_ellipsis()
+                            .overflow_hidden()
+                            .child(result.title.clone()),
+                    )
+                    .when(show_snippet, |this| {
+                        this.child(
+                            div()
+                                .text_xs()
+                                .text_ellipsis()
+                                .overflow_hidden()
+                                .text_color(cx.theme().muted_foreground)
+                                .child(result.snippet.clone()),
+                        )
+                    }),
+            )
+    }
+}
+
+impl Render for SearchPalette {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_query = !self.search_input.read(cx).value().trim().is_empty();
+
+        let results: Vec<AnyElement> = self
+            .results
+            .iter()
+            .enumerate()
+            .map(|(index, result)| self.render_result(index, result, cx).into_any_element())
+            .collect();
+
+        v_flex()
+            .w_full()
+            .gap_2()
+            .on_action(cx.listener(|this, _: &MoveUp, _, cx| {
+                this.move_selection(false, cx);
+            }))
+            .on_action(cx.listener(|this, _: &MoveDown, _, cx| {
+                this.move_selection(true, cx);
+            }))
+            .child(
+                Input::new(&self.search_input)
+                    .prefix(Icon::default().path("icons/search.svg").small())
+                    .cleanable(true),
+            )
+            .when(has_query && results.is_empty(), |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No results"),
+                )
+            })
+            .when(!results.is_empty(), |this| {
+                this.child(
+                    div()
+                        .id("search-results")
+                        .max_h(px(360.))
+                        .overflow_y_scrollbar()
+                        .child(v_flex().children(results)),
+                )
+            })
+    }
+}
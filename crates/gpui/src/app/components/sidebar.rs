@@ -1,6 +1,6 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
-use gpui::*;
+use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
     ActiveTheme, Icon, IconName, Sizable, WindowExt,
     avatar::Avatar,
@@ -14,18 +14,36 @@ use gpui_component::{
 use crate::{
     LoadingState,
     app::{
-        components::confirm_dialog::ConfirmDialog,
+        components::{
+            confirm_dialog::ConfirmDialog, context_dialog::ContextDialog,
+            search_palette::SearchPalette,
+        },
         screens::document_screen::DocumentScreen,
         states::{
             app_state::AppState, document_state::DocumentState, repository_state::RepositoryState,
+            settings_state::SettingsState,
         },
     },
-    domain::database::document::DocumentModel,
+    domain::{
+        database::{change::DocumentChange, document::DocumentModel},
+        entities::settings::DbContext,
+    },
+    infrastructure::{config::Remindr, markdown::MarkdownConverter},
 };
 
+/// Left indentation added for each level of the page tree
+const DOCUMENT_INDENT: f32 = 12.0;
+
 pub struct AppSidebar {
     document_state: LoadingState<Vec<DocumentModel>>,
     app_state: Entity<AppState>,
+    /// Pages whose sub-pages are shown
+    expanded: HashSet<i32>,
+    /// Row hovered while dragging a page, with where the page would land
+    drop_target: Option<(i32, DocumentDropZone)>,
+    show_trash: bool,
+    trash_state: LoadingState<Vec<DocumentModel>>,
+    _refresh_task: Task<()>,
 }
 
 impl AppSidebar {
@@ -39,41 +57,62 @@ impl AppSidebar {
                 async move |this, cx| {
                     let documents = repository.get_documents().await;
                     if let Ok(documents) = documents {
-                        let _ = this.update(cx, |state: &mut Self, _| {
-                            state.document_state = LoadingState::Loaded(documents);
+                        let _ = this.update(cx, |state: &mut Self, cx| {
+                            state.set_documents(documents, cx);
                         });
                     }
                 }
             })
             .detach();
 
-            // Poll every 5 seconds
-            cx.spawn({
-                let repository = document_repository.clone();
-                async move |this, cx| {
-                    loop {
-                        smol::Timer::after(Duration::from_secs(5)).await;
-                        let documents = repository.get_documents().await;
-                        if let Ok(documents) = documents {
-                            let result = this.update(cx, |state: &mut Self, _| {
-                                state.document_state = LoadingState::Loaded(documents);
-                            });
-                            if result.is_err() {
-                                break;
-                            }
-                        }
-                    }
-                }
+            let changes = cx.global::<RepositoryState>().changes.clone();
+            cx.subscribe(&changes, |this, _, _: &DocumentChange, cx| {
+                this.refresh_documents(cx);
             })
             .detach();
 
             Self {
                 document_state: LoadingState::Loading,
                 app_state,
+                expanded: HashSet::new(),
+                drop_target: None,
+                show_trash: false,
+                trash_state: LoadingState::Loading,
+                _refresh_task: Task::ready(()),
             }
         })
     }
 
+    /// Read the documents again, changes arriving together being read once
+    fn refresh_documents(&mut self, cx: &mut Context<Self>) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let show_trash = self.show_trash;
+
+        self._refresh_task = cx.spawn(async move |this, cx| {
+            smol::Timer::after(Duration::from_millis(100)).await;
+
+            let documents = repository.get_documents().await;
+            let trash = if show_trash {
+                Some(repository.get_trashed_documents().await)
+            } else {
+                None
+            };
+
+            let _ = this.update(cx, |this, cx| {
+                if let Ok(documents) = documents {
+                    this.set_documents(documents, cx);
+                }
+                if let Some(trash) = trash {
+                    this.trash_state = match trash {
+                        Ok(documents) => LoadingState::Loaded(documents),
+                        Err(error) => LoadingState::Error(error.to_string()),
+                    };
+                    cx.notify();
+                }
+            });
+        });
+    }
+
     fn get_username() -> String {
         std::env::var("USER")
             .or_else(|_| std::env::var("USERNAME"))
@@ -83,17 +122,37 @@ impl AppSidebar {
     fn render_user_dropdown(&self, cx: &Context<Self>) -> impl IntoElement {
         let username = Self::get_username();
         let sidebar_fg = cx.theme().sidebar_foreground;
+        let this = cx.entity().clone();
+
+        let workspace = cx
+            .try_global::<SettingsState>()
+            .and_then(|settings| settings.active_context())
+            .and_then(|context| context.name())
+            .unwrap_or("Local")
+            .to_string();
 
         SidebarHeader::new()
             .p_1()
             .child(Avatar::new().name(username.clone()).small())
             .child(
-                div()
+                v_flex()
                     .flex_1()
-                    .text_sm()
                     .overflow_hidden()
-                    .text_ellipsis()
-                    .child(username),
+                    .child(
+                        div()
+                            .text_sm()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .child(username),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .text_color(sidebar_fg.opacity(0.6))
+                            .child(workspace),
+                    ),
             )
             .child(
                 h_flex()
@@ -110,8 +169,98 @@ impl AppSidebar {
                             .text_color(sidebar_fg.opacity(0.6)),
                     ),
             )
-            .dropdown_menu(|menu, _, _| {
-                menu.item(
+            .dropdown_menu(move |menu, _, cx| {
+                let (contexts, active) = cx
+                    .try_global::<SettingsState>()
+                    .map(|settings| {
+                        (
+                            settings.contexts.clone(),
+                            settings.active_context().cloned(),
+                        )
+                    })
+                    .unwrap_or_default();
+                let active_name = active
+                    .as_ref()
+                    .and_then(|context| context.name())
+                    .map(str::to_string);
+
+                let mut menu = menu.label("Workspaces");
+
+                if contexts.is_empty() {
+                    menu = menu.item(PopupMenuItem::new("Local").icon(Icon::new(IconName::Check)));
+                }
+
+                for context in contexts {
+                    let Some(name) = context.name().map(str::to_string) else {
+                        continue;
+                    };
+                    let is_active = active_name.as_deref() == Some(name.as_str());
+                    let icon = if is_active {
+                        Icon::new(IconName::Check)
+                    } else {
+                        Icon::default().path("icons/database.svg")
+                    };
+
+                    let this = this.clone();
+                    menu = menu.item(PopupMenuItem::new(name).icon(icon).on_click(
+                        move |_, window, cx| {
+                            if !is_active {
+                                let context = context.clone();
+                                this.update(cx, |sidebar, cx| {
+                                    sidebar.switch_context(Some(context), window, cx);
+                                });
+                            }
+                        },
+                    ));
+                }
+
+                let this_for_add = this.clone();
+                menu = menu.separator().item(
+                    PopupMenuItem::new("Add workspace")
+                        .icon(Icon::new(IconName::Plus))
+                        .on_click(move |_, window, cx| {
+                            let this = this_for_add.clone();
+                            ContextDialog::open(
+                                move |context, window, cx| {
+                                    this.update(cx, |sidebar, cx| {
+                                        sidebar.add_context(context, window, cx);
+                                    });
+                                },
+                                window,
+                                cx,
+                            );
+                        }),
+                );
+
+                if let Some(name) = active_name {
+                    let this = this.clone();
+                    menu = menu.item(
+                        PopupMenuItem::new("Remove workspace")
+                            .icon(Icon::default().path("icons/trash-2.svg"))
+                            .on_click(move |_, window, cx| {
+                                let this = this.clone();
+                                let name = name.clone();
+
+                                ConfirmDialog::new("Remove Workspace")
+                                    .message(format!(
+                                        "Remove \"{}\" from the workspaces? Its database is kept.",
+                                        name
+                                    ))
+                                    .confirm_text("Remove")
+                                    .cancel_text("Cancel")
+                                    .danger()
+                                    .on_confirm(move |window, cx| {
+                                        this.update(cx, |sidebar, cx| {
+                                            sidebar.remove_context(&name, window, cx);
+                                        });
+                                        true
+                                    })
+                                    .open(window, cx);
+                            }),
+                    );
+                }
+
+                menu.separator().item(
                     PopupMenuItem::new("Settings")
                         .icon(Icon::new(IconName::Settings))
                         .on_click(|_, _, _| {
@@ -120,6 +269,964 @@ impl AppSidebar {
                 )
             })
     }
+
+    /// Persist the settings global to the settings file.
+    fn save_settings(cx: &mut App) {
+        if let Some(settings) = cx.try_global::<SettingsState>().cloned() {
+            cx.spawn(async move |_| Remindr::new().save_settings(&settings).await)
+                .detach();
+        }
+    }
+
+    fn add_context(&mut self, context: DbContext, window: &mut Window, cx: &mut Context<Self>) {
+        let name = context.name().unwrap_or_default().to_string();
+
+        if !cx.has_global::<SettingsState>() {
+            cx.set_global(SettingsState::default());
+        }
+
+        let exists = cx
+            .global::<SettingsState>()
+            .contexts
+            .iter()
+            .any(|existing| existing.name() == Some(name.as_str()));
+        if exists {
+            window.push_notification(format!("A workspace named \"{}\" already exists", name), cx);
+            return;
+        }
+
+        cx.update_global::<SettingsState, _>(|settings, _| {
+            settings.contexts.push(context.clone());
+        });
+        Self::save_settings(cx);
+
+        self.switch_context(Some(context), window, cx);
+    }
+
+    fn remove_context(&mut self, name: &str, window: &mut Window, cx: &mut Context<Self>) {
+        if !cx.has_global::<SettingsState>() {
+            return;
+        }
+
+        cx.update_global::<SettingsState, _>(|settings, _| {
+            settings
+                .contexts
+                .retain(|context| context.name() != Some(name));
+            settings.active_context = None;
+        });
+        Self::save_settings(cx);
+
+        let next = cx.global::<SettingsState>().active_context().cloned();
+        self.switch_context(next, window, cx);
+    }
+
+    /// Connect to another database, then swap the repository and close the open tabs.
+    /// `None` switches back to the default local database.
+    fn switch_context(
+        &mut self,
+        context: Option<DbContext>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let name = context
+            .as_ref()
+            .and_then(|context| context.name())
+            .unwrap_or("Local")
+            .to_string();
+        let retention_days = cx
+            .try_global::<SettingsState>()
+            .map(|settings| settings.trash.clone())
+            .unwrap_or_default()
+            .retention_days;
+
+        let flush =
+            cx.update_global::<DocumentState, _>(|state, cx| state.flush_documents(None, cx));
+
+        cx.spawn_in(window, async move |this, cx| {
+            // The edits of the open tabs belong to the database being left
+            if let Err(error) = flush.await {
+                let _ = cx.update(|window, cx| window.push_notification(error.to_string(), cx));
+                return;
+            }
+
+            let result = async {
+                let repository = Remindr::new().connect_database(context.as_ref()).await?;
+                let _ = repository.purge_trash(retention_days).await;
+                let documents = repository.get_documents().await?;
+                Ok::<_, anyhow::Error>((repository, documents))
+            }
+            .await;
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok((repository, documents)) => {
+                    cx.update_global::<RepositoryState, _>(|state, cx| {
+                        state.set_documents(repository, cx);
+                    });
+                    cx.update_global::<DocumentState, _>(|state, _| {
+                        state.close_all_documents();
+                    });
+
+                    if let Some(context) = &context {
+                        if !cx.has_global::<SettingsState>() {
+                            cx.set_global(SettingsState::default());
+                        }
+                        cx.update_global::<SettingsState, _>(|settings, _| {
+                            settings.active_context = context.name().map(str::to_string);
+                        });
+                        Self::save_settings(cx);
+                    }
+
+                    this.expanded.clear();
+                    this.set_documents(documents, cx);
+                    if this.show_trash {
+                        this.load_trash(window, cx);
+                    }
+                    window.push_notification(format!("Switched to \"{}\"", name), cx);
+                }
+                Err(error) => {
+                    window.push_notification(format!("Could not open \"{}\": {}", name, error), cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Asks for a destination file and writes the stored document as Markdown.
+    fn export_markdown(document_id: i32, title: String, window: &mut Window, cx: &mut App) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let directory = dirs::document_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let path = cx.prompt_for_new_path(&directory, Some(&MarkdownConverter::file_name(&title)));
+        // The edits not saved yet belong to the export
+        let flush = cx.update_global::<DocumentState, _>(|state, cx| {
+            state.flush_documents(Some(&[document_id]), cx)
+        });
+
+        window
+            .spawn(cx, async move |cx| {
+                let Ok(Ok(Some(path))) = path.await else {
+                    return;
+                };
+
+                let result = async {
+                    flush.await?;
+                    let document = repository.get_document_by_id(document_id).await?;
+                    smol::fs::write(&path, MarkdownConverter::export(&document)).await?;
+
+                    // Attachments go next to the file, where its links point at
+                    let directory = path.parent().unwrap_or(std::path::Path::new(""));
+                    for (hash, relative_path) in MarkdownConverter::attachments(&document) {
+                        let Some(attachment) = repository.get_attachment(&hash).await? else {
+                            continue;
+                        };
+                        let attachment_path = directory.join(relative_path);
+                        if let Some(parent) = attachment_path.parent() {
+                            smol::fs::create_dir_all(parent).await?;
+                        }
+                        smol::fs::write(&attachment_path, attachment.data).await?;
+                    }
+                    Ok::<_, anyhow::Error>(())
+                }
+                .await;
+
+                let _ = cx.update(|window, cx| match result {
+                    Ok(_) => {
+                        window.push_notification(format!("\"{}\" has been exported", title), cx)
+                    }
+                    Err(error) => window.push_notification(format!("Export failed: {}", error), cx),
+                });
+            })
+            .detach();
+    }
+
+    /// Asks for a Markdown file, stores it as a new document and opens it.
+    fn import_markdown(
+        this: Entity<Self>,
+        app_state: Entity<AppState>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let paths = cx.prompt_for_paths(PathPromptOptions {
+            files: true,
+            directories: false,
+            multiple: false,
+            prompt: Some("Import".into()),
+        });
+
+        window
+            .spawn(cx, async move |cx| {
+                let Ok(Ok(Some(paths))) = paths.await else {
+                    return;
+                };
+                let Some(path) = paths.into_iter().next() else {
+                    return;
+                };
+
+                let result = async {
+                    let markdown = smol::fs::read_to_string(&path).await?;
+                    let fallback_title = path
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_else(|| "Untitled".to_string());
+
+                    let document = MarkdownConverter::import(&markdown, &fallback_title);
+                    let title = document.title.clone();
+                    let new_id = repository.insert_document(document).await?;
+                    let documents = repository.get_documents().await?;
+
+                    Ok::<_, anyhow::Error>((new_id, title, documents))
+                }
+                .await;
+
+                let _ = cx.update(|window, cx| match result {
+                    Ok((new_id, title, documents)) => {
+                        this.update(cx, |state, cx| {
+                            state.set_documents(documents, cx);
+                        });
+
+                        cx.update_global::<DocumentState, _>(|state, _| {
+                            state.open_document(new_id, title.clone());
+                        });
+
+                        app_state.update(cx, |app_state, cx| {
+                            let document_screen = DocumentScreen::new(cx.weak_entity());
+                            app_state.navigator.push(document_screen, cx);
+                        });
+
+                        window.push_notification(format!("\"{}\" has been imported", title), cx);
+                    }
+                    Err(error) => window.push_notification(format!("Import failed: {}", error), cx),
+                });
+            })
+            .detach();
+    }
+
+    /// Store the listed documents and share the page tree with the breadcrumbs
+    fn set_documents(&mut self, documents: Vec<DocumentModel>, cx: &mut Context<Self>) {
+        cx.update_global::<DocumentState, _>(|state, _| {
+            state.set_outline(&documents);
+        });
+        self.document_state = LoadingState::Loaded(documents);
+        cx.notify();
+    }
+
+    /// Documents shown in the tree, depth first, as `(document, depth, has_children)`.
+    /// Pages under a collapsed parent are skipped.
+    fn visible_documents(&self) -> Vec<(DocumentModel, usize, bool)> {
+        let LoadingState::Loaded(documents) = &self.document_state else {
+            return Vec::new();
+        };
+
+        fn collect(
+            documents: &[DocumentModel],
+            expanded: &HashSet<i32>,
+            parent_id: Option<i32>,
+            depth: usize,
+            rows: &mut Vec<(DocumentModel, usize, bool)>,
+        ) {
+            for document in documents.iter().filter(|doc| doc.parent_id == parent_id) {
+                let has_children = documents
+                    .iter()
+                    .any(|doc| doc.parent_id == Some(document.id));
+                rows.push((document.clone(), depth, has_children));
+
+                if has_children && expanded.contains(&document.id) {
+                    collect(documents, expanded, Some(document.id), depth + 1, rows);
+                }
+            }
+        }
+
+        let mut rows = Vec::new();
+        collect(documents, &self.expanded, None, 0, &mut rows);
+
+        // Pages whose parent is gone are shown at the root rather than lost
+        for document in documents {
+            let is_orphan = document
+                .parent_id
+                .is_some_and(|parent_id| !documents.iter().any(|doc| doc.id == parent_id));
+            if is_orphan {
+                rows.push((document.clone(), 0, false));
+            }
+        }
+
+        rows
+    }
+
+    /// Id of a document and of every page nested under it
+    fn subtree_ids(&self, id: i32) -> Vec<i32> {
+        let LoadingState::Loaded(documents) = &self.document_state else {
+            return vec![id];
+        };
+
+        let mut ids = vec![id];
+        let mut index = 0;
+        while index < ids.len() {
+            let parent_id = ids[index];
+            ids.extend(
+                documents
+                    .iter()
+                    .filter(|doc| doc.parent_id == Some(parent_id))
+                    .map(|doc| doc.id),
+            );
+            index += 1;
+        }
+
+        ids
+    }
+
+    fn toggle_expanded(&mut self, id: i32, cx: &mut Context<Self>) {
+        if !self.expanded.remove(&id) {
+            self.expanded.insert(id);
+        }
+        cx.notify();
+    }
+
+    fn on_drag_move(
+        &mut self,
+        document_id: i32,
+        event: &DragMoveEvent<DraggedDocument>,
+        cx: &mut Context<Self>,
+    ) {
+        let bounds = event.bounds;
+        let mouse_y = event.event.position.y;
+
+        if !bounds.contains(&event.event.position) {
+            if matches!(self.drop_target, Some((id, _)) if id == document_id) {
+                self.drop_target = None;
+                cx.notify();
+            }
+            return;
+        }
+
+        // The edges of a row reorder, its middle nests the dragged page
+        let edge = bounds.size.height / 4.0;
+        let zone = if mouse_y < bounds.top() + edge {
+            DocumentDropZone::Before
+        } else if mouse_y > bounds.bottom() - edge {
+            DocumentDropZone::After
+        } else {
+            DocumentDropZone::Inside
+        };
+
+        if self.drop_target != Some((document_id, zone)) {
+            self.drop_target = Some((document_id, zone));
+            cx.notify();
+        }
+    }
+
+    fn on_drop(
+        &mut self,
+        dragged_id: i32,
+        target_id: i32,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let zone = match self.drop_target.take() {
+            Some((id, zone)) if id == target_id => zone,
+            _ => DocumentDropZone::Inside,
+        };
+
+        let LoadingState::Loaded(documents) = &self.document_state else {
+            return;
+        };
+        let Some(target) = documents.iter().find(|doc| doc.id == target_id) else {
+            return;
+        };
+        if dragged_id == target_id {
+            return;
+        }
+
+        let (parent_id, position) = match zone {
+            DocumentDropZone::Inside => {
+                self.expanded.insert(target_id);
+                (Some(target_id), usize::MAX)
+            }
+            DocumentDropZone::Before | DocumentDropZone::After => {
+                let index = documents
+                    .iter()
+                    .filter(|doc| doc.parent_id == target.parent_id && doc.id != dragged_id)
+                    .position(|doc| doc.id == target_id)
+                    .unwrap_or_default();
+                let index = if zone == DocumentDropZone::After {
+                    index + 1
+                } else {
+                    index
+                };
+                (target.parent_id, index)
+            }
+        };
+
+        self.move_document(dragged_id, parent_id, position, window, cx);
+    }
+
+    /// Persist the new place of a document, then reload the tree
+    fn move_document(
+        &mut self,
+        id: i32,
+        parent_id: Option<i32>,
+        position: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = async {
+                repository.move_document(id, parent_id, position).await?;
+                repository.get_documents().await
+            }
+            .await;
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(documents) => this.set_documents(documents, cx),
+                Err(error) => {
+                    window.push_notification(format!("Could not move the page: {}", error), cx)
+                }
+            });
+        })
+        .detach();
+    }
+
+    /// Create an untitled document, nested under `parent_id` when given, and open it.
+    fn create_document(
+        this: Entity<Self>,
+        app_state: Entity<AppState>,
+        parent_id: Option<i32>,
+        cx: &mut App,
+    ) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        cx.spawn(async move |cx| {
+            let new_document = DocumentModel {
+                id: 0,
+                parent_id,
+                position: 0,
+                title: "Untitled".to_string(),
+                content: serde_json::json!([]),
+            };
+
+            let new_id = repository.insert_document(new_document).await?;
+            let documents = repository.get_documents().await?;
+
+            let _ = cx.update(|cx: &mut App| {
+                let _ = this.update(cx, |state, cx| {
+                    if let Some(parent_id) = parent_id {
+                        state.expanded.insert(parent_id);
+                    }
+                    state.set_documents(documents, cx);
+                });
+
+                cx.update_global::<DocumentState, _>(|state, _| {
+                    state.open_document(new_id, "Untitled".to_string());
+                });
+
+                app_state.update(cx, |app_state, cx| {
+                    let document_screen = DocumentScreen::new(cx.weak_entity());
+                    app_state.navigator.push(document_screen, cx);
+                });
+            });
+
+            Ok::<_, anyhow::Error>(())
+        })
+        .detach();
+    }
+
+    fn render_document_item(
+        &self,
+        document: DocumentModel,
+        depth: usize,
+        has_children: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let item_text_color = cx.theme().sidebar_foreground.opacity(0.9);
+        let icon_color = cx.theme().sidebar_foreground.opacity(0.6);
+        let accent_bg = cx.theme().sidebar_accent;
+        let drop_color = cx.theme().accent_foreground.opacity(0.5);
+
+        let this = cx.entity().clone();
+        let app_state = self.app_state.clone();
+        let document_id = document.id;
+        let document_title = document.title.clone();
+        let is_expanded = self.expanded.contains(&document_id);
+        let is_nested = document.parent_id.is_some();
+        let drop_zone = match self.drop_target {
+            Some((id, zone)) if id == document_id && cx.has_active_drag() => Some(zone),
+            _ => None,
+        };
+
+        h_flex()
+            .id(("document-item", document_id as usize))
+            .relative()
+            .w_full()
+            .h_7()
+            .pl(px(8.0 + DOCUMENT_INDENT * depth as f32))
+            .pr_2()
+            .gap_1()
+            .items_center()
+            .rounded_md()
+            .cursor_pointer()
+            .hover(|el| el.bg(accent_bg))
+            .when(drop_zone == Some(DocumentDropZone::Inside), |el| {
+                el.bg(accent_bg)
+            })
+            .on_click({
+                let document_title = document_title.clone();
+                let app_state = app_state.clone();
+                move |_, _, cx| {
+                    cx.update_global::<DocumentState, _>(|state, _| {
+                        state.open_document(document_id, document_title.clone());
+                    });
+
+                    app_state.update(cx, |app_state, cx| {
+                        let document_screen = DocumentScreen::new(cx.weak_entity());
+                        app_state.navigator.push(document_screen, cx);
+                    });
+                }
+            })
+            .on_drag(
+                DraggedDocument {
+                    id: document_id,
+                    title: document_title.clone(),
+                },
+                |dragged, _, _, cx| cx.new(|_| dragged.clone()),
+            )
+            .on_drag_move(cx.listener(
+                move |this, event: &DragMoveEvent<DraggedDocument>, _, cx| {
+                    this.on_drag_move(document_id, event, cx);
+                },
+            ))
+            .on_drop(
+                cx.listener(move |this, dragged: &DraggedDocument, window, cx| {
+                    cx.stop_propagation();
+                    this.on_drop(dragged.id, document_id, window, cx);
+                }),
+            )
+            .when_some(drop_zone, |el, zone| {
+                let bar = div()
+                    .absolute()
+                    .left(px(8.0 + DOCUMENT_INDENT * depth as f32))
+                    .right_0()
+                    .h(px(2.0))
+                    .bg(drop_color);
+
+                match zone {
+                    DocumentDropZone::Before => el.child(bar.top_0()),
+                    DocumentDropZone::After => el.child(bar.bottom_0()),
+                    DocumentDropZone::Inside => el,
+                }
+            })
+            .child(
+                div()
+                    .size_4()
+                    .flex_shrink_0()
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| {
+                        cx.stop_propagation();
+                    })
+                    .when(has_children, |el| {
+                        el.child(
+                            Button::new(("document-toggle", document_id as usize))
+                                .icon(if is_expanded {
+                                    Icon::new(IconName::ChevronDown)
+                                } else {
+                                    Icon::new(IconName::ChevronRight)
+                                })
+                                .ghost()
+                                .xsmall()
+                                .cursor_pointer()
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    this.toggle_expanded(document_id, cx);
+                                })),
+                        )
+                    }),
+            )
+            .child(
+                Icon::default()
+                    .path("icons/file-text.svg")
+                    .size_4()
+                    .flex_shrink_0()
+                    .text_color(icon_color),
+            )
+            .child(
+                div()
+                    .flex_1()
+                    .text_sm()
+                    .text_ellipsis()
+                    .overflow_hidden()
+                    .text_color(item_text_color)
+                    .child(document.title.clone()),
+            )
+            .child(
+                h_flex()
+                    .gap_0p5()
+                    .opacity(0.0)
+                    .hover(|el| el.opacity(1.0))
+                    .on_mouse_down(MouseButton::Left, |_, _, cx| {
+                        cx.stop_propagation();
+                    })
+                    .child(
+                        Button::new(("document-menu", document_id as usize))
+                            .icon(Icon::default().path("icons/ellipsis.svg"))
+                            .ghost()
+                            .xsmall()
+                            .cursor_pointer()
+                            .dropdown_menu({
+                                let this = this.clone();
+                                let app_state = app_state.clone();
+                                let title = document_title.clone();
+                                move |menu, _, _| {
+                                    let this_for_create = this.clone();
+                                    let app_state_for_create = app_state.clone();
+                                    let this_for_root = this.clone();
+                                    let this = this.clone();
+                                    let app_state = app_state.clone();
+                                    let title = title.clone();
+
+                                    let mut menu = menu.item(
+                                        PopupMenuItem::new("Add sub-page")
+                                            .icon(Icon::new(IconName::Plus))
+                                            .on_click(move |_, _, cx| {
+                                                Self::create_document(
+                                                    this_for_create.clone(),
+                                                    app_state_for_create.clone(),
+                                                    Some(document_id),
+                                                    cx,
+                                                );
+                                            }),
+                                    );
+
+                                    if is_nested {
+                                        menu = menu.item(
+                                            PopupMenuItem::new("Move to top level")
+                                                .icon(
+                                                    Icon::default().path("icons/chevron-left.svg"),
+                                                )
+                                                .on_click(move |_, window, cx| {
+                                                    this_for_root.update(cx, |this, cx| {
+                                                        this.move_document(
+                                                            document_id,
+                                                            None,
+                                                            usize::MAX,
+                                                            window,
+                                                            cx,
+                                                        );
+                                                    });
+                                                }),
+                                        );
+                                    }
+
+                                    menu.separator()
+                                        .item(
+                                            PopupMenuItem::new("Export as Markdown")
+                                                .icon(Icon::default().path("icons/download.svg"))
+                                                .on_click(move |_, window, cx| {
+                                                    Self::export_markdown(
+                                                        document_id,
+                                                        title.clone(),
+                                                        window,
+                                                        cx,
+                                                    );
+                                                }),
+                                        )
+                                        .item(
+                                            PopupMenuItem::new("Import Markdown file")
+                                                .icon(Icon::default().path("icons/upload.svg"))
+                                                .on_click(move |_, window, cx| {
+                                                    Self::import_markdown(
+                                                        this.clone(),
+                                                        app_state.clone(),
+                                                        window,
+                                                        cx,
+                                                    );
+                                                }),
+                                        )
+                                }
+                            }),
+                    )
+                    .child(
+                        Button::new(("delete-doc", document_id as usize))
+                            .icon(Icon::default().path("icons/trash-2.svg"))
+                            .danger()
+                            .xsmall()
+                            .cursor_pointer()
+                            .tooltip("Move to trash")
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.trash_document(
+                                    document_id,
+                                    document_title.clone(),
+                                    window,
+                                    cx,
+                                );
+                            })),
+                    ),
+            )
+    }
+
+    /// Move a document and its sub-pages to the trash, closing their tabs
+    fn trash_document(
+        &mut self,
+        id: i32,
+        title: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let trashed_ids = self.subtree_ids(id);
+
+        cx.update_global::<DocumentState, _>(|state, _| {
+            state.trash_documents(&trashed_ids);
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = async {
+                repository.delete_document(id).await?;
+                repository.get_documents().await
+            }
+            .await;
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok(documents) => {
+                    this.set_documents(documents, cx);
+                    window.push_notification(format!("\"{}\" moved to the trash", title), cx);
+                }
+                Err(error) => {
+                    cx.update_global::<DocumentState, _>(|state, _| {
+                        state.restore_documents(&trashed_ids);
+                    });
+                    window.push_notification(format!("Could not delete the page: {}", error), cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn set_show_trash(&mut self, show_trash: bool, window: &mut Window, cx: &mut Context<Self>) {
+        self.show_trash = show_trash;
+        if show_trash {
+            self.load_trash(window, cx);
+        }
+        cx.notify();
+    }
+
+    fn load_trash(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = repository.get_trashed_documents().await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.trash_state = match result {
+                    Ok(documents) => LoadingState::Loaded(documents),
+                    Err(error) => LoadingState::Error(error.to_string()),
+                };
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Bring a document back from the trash and reopen the tabs it had
+    fn restore_document(
+        &mut self,
+        id: i32,
+        title: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = async {
+                let restored_ids = repository.restore_document(id).await?;
+                let documents = repository.get_documents().await?;
+                Ok::<_, anyhow::Error>((restored_ids, documents))
+            }
+            .await;
+
+            let _ = this.update_in(cx, |this, window, cx| match result {
+                Ok((restored_ids, documents)) => {
+                    cx.update_global::<DocumentState, _>(|state, _| {
+                        state.restore_documents(&restored_ids);
+                    });
+                    this.set_documents(documents, cx);
+                    this.load_trash(window, cx);
+                    window.push_notification(format!("\"{}\" has been restored", title), cx);
+                }
+                Err(error) => {
+                    window.push_notification(format!("Could not restore the page: {}", error), cx);
+                }
+            });
+        })
+        .detach();
+    }
+
+    fn delete_document_forever(
+        &mut self,
+        id: i32,
+        title: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let this = cx.entity().clone();
+
+        ConfirmDialog::new("Delete Page")
+            .message(format!(
+                "Are you sure you want to permanently delete \"{}\" and all of its sub-pages? This action cannot be undone.",
+                title
+            ))
+            .confirm_text("Delete forever")
+            .cancel_text("Cancel")
+            .danger()
+            .on_confirm(move |window, cx| {
+                let repository = cx.global::<RepositoryState>().documents.clone();
+                let title = title.clone();
+
+                cx.update_global::<DocumentState, _>(|state, _| {
+                    state.forget_trashed_documents(&[id]);
+                });
+
+                this.update(cx, |_, cx| {
+                    cx.spawn_in(window, async move |this, cx| {
+                        let result = repository.delete_document_forever(id).await;
+
+                        let _ = this.update_in(cx, |this, window, cx| {
+                            match result {
+                                Ok(_) => window.push_notification(
+                                    format!("\"{}\" has been deleted forever", title),
+                                    cx,
+                                ),
+                                Err(error) => window.push_notification(
+                                    format!("Could not delete the page: {}", error),
+                                    cx,
+                                ),
+                            }
+                            this.load_trash(window, cx);
+                        });
+                    })
+                    .detach();
+                });
+
+                true
+            })
+            .open(window, cx);
+    }
+
+    fn render_trash(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let item_text_color = cx.theme().sidebar_foreground.opacity(0.9);
+        let muted_color = cx.theme().sidebar_foreground.opacity(0.5);
+        let icon_color = cx.theme().sidebar_foreground.opacity(0.6);
+        let accent_bg = cx.theme().sidebar_accent;
+
+        let retention_days = cx
+            .try_global::<SettingsState>()
+            .map(|settings| settings.trash.clone())
+            .unwrap_or_default()
+            .retention_days;
+
+        let documents = match &self.trash_state {
+            LoadingState::Loaded(documents) => documents.clone(),
+            _ => Vec::new(),
+        };
+        let is_empty = matches!(self.trash_state, LoadingState::Loaded(_)) && documents.is_empty();
+
+        let items: Vec<AnyElement> = documents
+            .into_iter()
+            .map(|document| {
+                let document_id = document.id;
+                let title = document.title.clone();
+
+                h_flex()
+                    .id(("trashed-document", document_id as usize))
+                    .w_full()
+                    .h_7()
+                    .px_2()
+                    .gap_2()
+                    .items_center()
+                    .rounded_md()
+                    .hover(|el| el.bg(accent_bg))
+                    .child(
+                        Icon::default()
+                            .path("icons/file-text.svg")
+                            .size_4()
+                            .flex_shrink_0()
+                            .text_color(icon_color),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .text_ellipsis()
+                            .overflow_hidden()
+                            .text_color(item_text_color)
+                            .child(document.title),
+                    )
+                    .child(
+                        Button::new(("restore-doc", document_id as usize))
+                            .icon(Icon::default().path("icons/undo-2.svg"))
+                            .ghost()
+                            .xsmall()
+                            .cursor_pointer()
+                            .tooltip("Restore")
+                            .on_click(cx.listener({
+                                let title = title.clone();
+                                move |this, _, window, cx| {
+                                    this.restore_document(document_id, title.clone(), window, cx);
+                                }
+                            })),
+                    )
+                    .child(
+                        Button::new(("delete-doc-forever", document_id as usize))
+                            .icon(Icon::default().path("icons/trash-2.svg"))
+                            .danger()
+                            .xsmall()
+                            .cursor_pointer()
+                            .tooltip("Delete forever")
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.delete_document_forever(
+                                    document_id,
+                                    title.clone(),
+                                    window,
+                                    cx,
+                                );
+                            })),
+                    )
+                    .into_any_element()
+            })
+            .collect();
+
+        v_flex()
+            .id("trash")
+            .flex_1()
+            .min_h_0()
+            .w_full()
+            .px_1()
+            .overflow_y_scroll()
+            .children(items)
+            .when(is_empty, |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_1()
+                        .text_sm()
+                        .text_color(muted_color)
+                        .child("The trash is empty"),
+                )
+            })
+            .when(retention_days > 0, |this| {
+                this.child(
+                    div()
+                        .px_2()
+                        .py_2()
+                        .text_xs()
+                        .text_color(muted_color)
+                        .child(format!(
+                            "Pages are deleted forever after {} days in the trash",
+                            retention_days
+                        )),
+                )
+            })
+    }
 }
 
 impl Render for AppSidebar {
@@ -135,11 +1242,6 @@ impl Render for AppSidebar {
         let this = cx.entity().clone();
         let app_state = self.app_state.clone();
 
-        let documents = match &self.document_state {
-            LoadingState::Loaded(docs) => docs.clone(),
-            _ => vec![],
-        };
-
         // Header
         let header = h_flex()
             .flex_shrink_0()
@@ -154,6 +1256,20 @@ impl Render for AppSidebar {
             .child(
                 h_flex()
                     .gap_1()
+                    .child(
+                        Button::new("search-documents")
+                            .icon(Icon::default().path("icons/search.svg"))
+                            .ghost()
+                            .xsmall()
+                            .cursor_pointer()
+                            .tooltip("Search documents")
+                            .on_click({
+                                let app_state = app_state.clone();
+                                move |_, window, cx| {
+                                    SearchPalette::open(app_state.clone(), window, cx);
+                                }
+                            }),
+                    )
                     .child(
                         Button::new("refresh-documents")
                             .icon(Icon::default().path("icons/refresh-cw.svg"))
@@ -171,8 +1287,8 @@ impl Render for AppSidebar {
                                     cx.spawn(async move |cx| {
                                         let documents = repository.get_documents().await?;
 
-                                        let _ = this_clone.update(cx, |state, _| {
-                                            state.document_state = LoadingState::Loaded(documents);
+                                        let _ = this_clone.update(cx, |state, cx| {
+                                            state.set_documents(documents, cx);
                                         });
 
                                         Ok::<_, anyhow::Error>(())
@@ -192,164 +1308,89 @@ impl Render for AppSidebar {
                                 let this = this.clone();
                                 let app_state = app_state.clone();
                                 move |_, _, cx| {
-                                    let repository =
-                                        cx.global::<RepositoryState>().documents.clone();
-                                    let this_clone = this.clone();
-                                    let app_state = app_state.clone();
-
-                                    cx.spawn(async move |cx| {
-                                        let new_document = DocumentModel {
-                                            id: 0,
-                                            title: "Untitled".to_string(),
-                                            content: serde_json::json!([]),
-                                        };
-
-                                        let new_id =
-                                            repository.insert_document(new_document).await?;
-                                        let documents = repository.get_documents().await?;
-
-                                        let _ = cx.update(|cx: &mut App| {
-                                            let _ = this_clone.update(cx, |state, _| {
-                                                state.document_state =
-                                                    LoadingState::Loaded(documents);
-                                            });
-
-                                            cx.update_global::<DocumentState, _>(|state, _| {
-                                                state.open_document(new_id, "Untitled".to_string());
-                                            });
-
-                                            app_state.update(cx, |app_state, cx| {
-                                                let document_screen =
-                                                    DocumentScreen::new(cx.weak_entity());
-                                                app_state.navigator.push(document_screen, cx);
-                                            });
-                                        });
-
-                                        Ok::<_, anyhow::Error>(())
-                                    })
-                                    .detach();
+                                    Self::create_document(
+                                        this.clone(),
+                                        app_state.clone(),
+                                        None,
+                                        cx,
+                                    );
                                 }
                             }),
                     ),
             );
 
-        // Document items
-        let items = documents.into_iter().map({
-            let this = this.clone();
-            let app_state = app_state.clone();
-            move |document| {
-                let document_id = document.id;
-                let document_title = document.title.clone();
-                let delete_title = document.title.clone();
-                let this_clone = this.clone();
-                let app_state_clone = app_state.clone();
-
-                h_flex()
-                    .id(("document-item", document_id as usize))
-                    .w_full()
-                    .h_7()
-                    .px_2()
-                    .gap_2()
-                    .items_center()
-                    .rounded_md()
+        let trash_header = h_flex()
+            .flex_shrink_0()
+            .px_2()
+            .gap_1()
+            .text_xs()
+            .text_color(header_text_color)
+            .h_8()
+            .items_center()
+            .child(
+                Button::new("close-trash")
+                    .icon(Icon::default().path("icons/chevron-left.svg"))
+                    .ghost()
+                    .xsmall()
                     .cursor_pointer()
-                    .hover(|el| el.bg(accent_bg))
-                    .on_click({
-                        let document_title = document_title.clone();
-                        let app_state = app_state_clone.clone();
-                        move |_, _, cx| {
-                            cx.update_global::<DocumentState, _>(|state, _| {
-                                state.open_document(document_id, document_title.clone());
-                            });
-
-                            app_state.update(cx, |app_state, cx| {
-                                let document_screen = DocumentScreen::new(cx.weak_entity());
-                                app_state.navigator.push(document_screen, cx);
-                            });
-                        }
-                    })
-                    .child(
-                        Icon::default()
-                            .path("icons/file-text.svg")
-                            .size_4()
-                            .text_color(icon_color),
-                    )
-                    .child(
-                        div()
-                            .flex_1()
-                            .text_sm()
-                            .text_ellipsis()
-                            .overflow_hidden()
-                            .text_color(item_text_color)
-                            .child(document.title.clone()),
-                    )
-                    .child(
-                        div()
-                            .opacity(0.0)
-                            .hover(|el| el.opacity(1.0))
-                            .on_mouse_down(MouseButton::Left, |_, _, cx| {
-                                cx.stop_propagation();
-                            })
-                            .child(
-                                Button::new(("delete-doc", document_id as usize))
-                                    .icon(Icon::default().path("icons/trash-2.svg"))
-                                    .danger()
-                                    .xsmall()
-                                    .cursor_pointer()
-                                    .on_click({
-                                        let this_clone = this_clone.clone();
-                                        move |_, window, cx| {
-                                            let this_clone = this_clone.clone();
-                                            let delete_title = delete_title.clone();
-
-                                            ConfirmDialog::new("Delete Page")
-                                                .message(format!(
-                                                    "Are you sure you want to delete \"{}\"? This action cannot be undone.",
-                                                    delete_title
-                                                ))
-                                                .confirm_text("Delete")
-                                                .cancel_text("Cancel")
-                                                .danger()
-                                                .on_confirm(move |window, cx| {
-                                                    let repository = cx.global::<RepositoryState>().documents.clone();
-                                                    let this_for_spawn = this_clone.clone();
-                                                    let deleted_title = delete_title.clone();
-
-                                                    cx.update_global::<DocumentState, _>(|state, _| {
-                                                        state.remove_document(document_id);
-                                                        if state.current_opened_document == Some(document_id) {
-                                                            state.current_opened_document = None;
-                                                        }
-                                                    });
+                    .tooltip("Back to documents")
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.set_show_trash(false, window, cx);
+                    })),
+            )
+            .child("Trash");
 
-                                                    window.push_notification(
-                                                        format!("\"{}\" has been deleted", deleted_title),
-                                                        cx,
-                                                    );
+        // Document tree
+        let items: Vec<AnyElement> = self
+            .visible_documents()
+            .into_iter()
+            .map(|(document, depth, has_children)| {
+                self.render_document_item(document, depth, has_children, cx)
+                    .into_any_element()
+            })
+            .collect();
 
-                                                    cx.spawn(async move |cx| {
-                                                        let _ = repository.delete_document(document_id).await;
-
-                                                        let documents = repository.get_documents().await;
-                                                        if let Ok(documents) = documents {
-                                                            let _ = this_for_spawn.update(cx, |state, _| {
-                                                                state.document_state = LoadingState::Loaded(documents);
-                                                            });
-                                                        }
-
-                                                        Ok::<_, anyhow::Error>(())
-                                                    })
-                                                    .detach();
-
-                                                    true
-                                                })
-                                                .open(window, cx);
-                                        }
-                                    }),
-                            ),
-                    )
-            }
-        });
+        let tree = div()
+            .id("document-tree")
+            .flex()
+            .flex_col()
+            .flex_1()
+            .min_h_0()
+            .w_full()
+            .px_1()
+            .overflow_y_scroll()
+            .children(items)
+            // Dropping below the pages moves the dragged one to the top level
+            .on_drop(cx.listener(|this, dragged: &DraggedDocument, window, cx| {
+                this.drop_target = None;
+                this.move_document(dragged.id, None, usize::MAX, window, cx);
+            }));
+
+        let trash_button = h_flex()
+            .id("open-trash")
+            .flex_shrink_0()
+            .mx_1()
+            .mb_2()
+            .h_7()
+            .px_2()
+            .gap_2()
+            .items_center()
+            .rounded_md()
+            .cursor_pointer()
+            .text_sm()
+            .text_color(item_text_color)
+            .hover(|el| el.bg(accent_bg))
+            .when(self.show_trash, |el| el.bg(accent_bg))
+            .child(
+                Icon::default()
+                    .path("icons/trash-2.svg")
+                    .size_4()
+                    .text_color(icon_color),
+            )
+            .child("Trash")
+            .on_click(cx.listener(|this, _, window, cx| {
+                this.set_show_trash(!this.show_trash, window, cx);
+            }));
 
         v_flex()
             .h_full()
@@ -358,7 +1399,44 @@ impl Render for AppSidebar {
             .border_r_1()
             .border_color(border_color)
             .child(div().px_2().py_2().child(self.render_user_dropdown(cx)))
-            .child(header)
-            .child(div().flex().flex_col().w_full().px_1().children(items))
+            .map(|this| {
+                if self.show_trash {
+                    this.child(trash_header).child(self.render_trash(cx))
+                } else {
+                    this.child(header).child(tree)
+                }
+            })
+            .child(trash_button)
+    }
+}
+
+/// Where a dragged page lands relative to the row it is dropped on
+#[derive(Clone, Copy, PartialEq)]
+enum DocumentDropZone {
+    Before,
+    Inside,
+    After,
+}
+
+/// Page being dragged in the sidebar, rendered as the drag preview
+#[derive(Clone)]
+struct DraggedDocument {
+    id: i32,
+    title: String,
+}
+
+impl Render for DraggedDocument {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .h_7()
+            .px_2()
+            .gap_2()
+            .items_center()
+            .rounded_md()
+            .bg(cx.theme().sidebar_accent)
+            .text_sm()
+            .text_color(cx.theme().sidebar_foreground)
+            .child(Icon::default().path("icons/file-text.svg").size_4())
+            .child(self.title.clone())
     }
 }
@@ -8,10 +8,15 @@ use gpui_component::{
 use uuid::Uuid;
 
 use crate::app::{
-    components::nodes::{
-        element::{NodePayload, RemindrElement},
-        heading::data::HeadingMetadata,
-        text::data::TextMetadata,
+    components::{
+        attachments,
+        nodes::{
+            code::data::CodeMetadata,
+            element::{NodePayload, RemindrElement},
+            heading::data::HeadingMetadata,
+            list::data::{ListKind, ListMetadata},
+            text::data::TextMetadata,
+        },
     },
     states::node_state::NodeState,
 };
@@ -34,6 +39,10 @@ enum MenuAction {
     InsertHeading2,
     InsertHeading3,
     InsertDivider,
+    InsertList(ListKind),
+    InsertCode,
+    /// Pick files to attach, images being shown inline
+    InsertAttachment,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -81,12 +90,48 @@ impl SlashMenu {
                 shortcut: Some("###"),
                 action: MenuAction::InsertHeading3,
             },
+            MenuItem {
+                label: "Bulleted list",
+                icon_path: "icons/list.svg",
+                shortcut: Some("-"),
+                action: MenuAction::InsertList(ListKind::Bulleted),
+            },
+            MenuItem {
+                label: "Numbered list",
+                icon_path: "icons/list-ordered.svg",
+                shortcut: Some("1."),
+                action: MenuAction::InsertList(ListKind::Numbered),
+            },
+            MenuItem {
+                label: "To-do list",
+                icon_path: "icons/list-todo.svg",
+                shortcut: Some("[]"),
+                action: MenuAction::InsertList(ListKind::Todo),
+            },
+            MenuItem {
+                label: "Code",
+                icon_path: "icons/code.svg",
+                shortcut: Some("```"),
+                action: MenuAction::InsertCode,
+            },
             MenuItem {
                 label: "Divider",
                 icon_path: "icons/separator-horizontal.svg",
                 shortcut: Some("---"),
                 action: MenuAction::InsertDivider,
             },
+            MenuItem {
+                label: "Image",
+                icon_path: "icons/image.svg",
+                shortcut: None,
+                action: MenuAction::InsertAttachment,
+            },
+            MenuItem {
+                label: "File",
+                icon_path: "icons/paperclip.svg",
+                shortcut: None,
+                action: MenuAction::InsertAttachment,
+            },
         ];
 
         let search_input = cx.new(|cx| InputState::new(window, cx).placeholder("Search blocks..."));
@@ -197,6 +242,9 @@ impl SlashMenu {
                 MenuAction::InsertHeading2 => self.insert_heading(2, window, cx),
                 MenuAction::InsertHeading3 => self.insert_heading(3, window, cx),
                 MenuAction::InsertDivider => self.insert_divider(window, cx),
+                MenuAction::InsertList(kind) => self.insert_list(kind, window, cx),
+                MenuAction::InsertCode => self.insert_code(window, cx),
+                MenuAction::InsertAttachment => self.insert_attachment(window, cx),
             }
         }
         self.selected_index = 0;
@@ -262,6 +310,9 @@ impl SlashMenu {
                 MenuAction::InsertHeading2 => this.insert_heading(2, window, cx),
                 MenuAction::InsertHeading3 => this.insert_heading(3, window, cx),
                 MenuAction::InsertDivider => this.insert_divider(window, cx),
+                MenuAction::InsertList(kind) => this.insert_list(kind, window, cx),
+                MenuAction::InsertCode => this.insert_code(window, cx),
+                MenuAction::InsertAttachment => this.insert_attachment(window, cx),
             }))
             .child(
                 div()
@@ -385,7 +436,8 @@ impl SlashMenu {
                 RemindrElement::Text(element) => element.update(cx, |element, cx| {
                     element.rich_text_state.update(cx, |state, cx| {
                         let value = self.remove_slash_command(state.value());
-                        state.set_content(value.to_string(), cx);
+                        let spans = state.spans().to_vec();
+                        state.set_rich_content(value.to_string(), spans, cx);
                     })
                 }),
                 RemindrElement::Heading(element) => element.update(cx, |element, cx| {
@@ -394,6 +446,15 @@ impl SlashMenu {
                         element.set_value(value, window, cx);
                     })
                 }),
+                RemindrElement::BulletedList(element)
+                | RemindrElement::NumberedList(element)
+                | RemindrElement::Todo(element) => element.update(cx, |element, cx| {
+                    element.rich_text_state.update(cx, |state, cx| {
+                        let value = self.remove_slash_command(state.value());
+                        let spans = state.spans().to_vec();
+                        state.set_rich_content(value.to_string(), spans, cx);
+                    })
+                }),
                 _ => {}
             }
         }
@@ -489,12 +550,116 @@ impl SlashMenu {
         cx.notify();
     }
 
+    fn insert_list(&mut self, kind: ListKind, window: &mut Window, cx: &mut Context<Self>) {
+        let current_id = self.related_id;
+
+        if self.mode == SlashMenuMode::InsertAfter {
+            self.state.update(cx, |state, cx| {
+                let node = RemindrElement::create_node(
+                    NodePayload::list(kind, ListMetadata::default(), true),
+                    &self.state,
+                    window,
+                    cx,
+                );
+                state.insert_node_after(current_id, &node);
+            });
+        } else {
+            // Replace mode: the current block becomes a list item keeping its content
+            let content = self.remove_slash_command(self.get_current_content(cx));
+
+            self.state.update(cx, |state, cx| {
+                let node = RemindrElement::create_node_with_id(
+                    current_id,
+                    NodePayload::list(
+                        kind,
+                        ListMetadata {
+                            content,
+                            ..Default::default()
+                        },
+                        true,
+                    ),
+                    &self.state,
+                    window,
+                    cx,
+                );
+                state.replace_node(current_id, &node);
+            });
+        }
+
+        self.open = false;
+        cx.emit(SlashMenuDismissEvent {
+            restore_focus: false,
+        });
+        cx.notify();
+    }
+
+    fn insert_attachment(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.mode == SlashMenuMode::Replace {
+            self.remove_slash(window, cx);
+        }
+
+        attachments::pick_files(Some(self.related_id), &self.state, window, cx);
+
+        self.open = false;
+        cx.emit(SlashMenuDismissEvent {
+            restore_focus: true,
+        });
+        cx.notify();
+    }
+
+    fn insert_code(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let current_id = self.related_id;
+
+        if self.mode == SlashMenuMode::InsertAfter {
+            self.state.update(cx, |state, cx| {
+                let node = RemindrElement::create_node(
+                    NodePayload::Code((CodeMetadata::default(), true)),
+                    &self.state,
+                    window,
+                    cx,
+                );
+                state.insert_node_after(current_id, &node);
+            });
+        } else {
+            // Replace mode: the current block content becomes the snippet
+            let content = self.remove_slash_command(self.get_current_content(cx));
+
+            self.state.update(cx, |state, cx| {
+                let node = RemindrElement::create_node_with_id(
+                    current_id,
+                    NodePayload::Code((
+                        CodeMetadata {
+                            content,
+                            ..Default::default()
+                        },
+                        true,
+                    )),
+                    &self.state,
+                    window,
+                    cx,
+                );
+                state.replace_node(current_id, &node);
+            });
+        }
+
+        self.open = false;
+        cx.emit(SlashMenuDismissEvent {
+            restore_focus: false,
+        });
+        cx.notify();
+    }
+
     fn get_current_content(&self, cx: &App) -> SharedString {
         let current_node = self.state.read(cx).get_current_nodes(self.related_id);
         if let Some(node) = current_node {
             match &node.element {
                 RemindrElement::Text(element) => element.read(cx).rich_text_state.read(cx).value(),
                 RemindrElement::Heading(element) => element.read(cx).input_state.read(cx).value(),
+                RemindrElement::BulletedList(element)
+                | RemindrElement::NumberedList(element)
+                | RemindrElement::Todo(element) => {
+                    element.read(cx).rich_text_state.read(cx).value()
+                }
                 _ => SharedString::default(),
             }
         } else {
@@ -514,6 +679,12 @@ impl SlashMenu {
                 RemindrElement::Heading(element) => {
                     element.read(cx).input_state.read(cx).cursor_position()
                 }
+                RemindrElement::BulletedList(element)
+                | RemindrElement::NumberedList(element)
+                | RemindrElement::Todo(element) => {
+                    let selection = element.read(cx).rich_text_state.read(cx).selection();
+                    Position::new(0, selection.head() as u32)
+                }
                 _ => Position::default(),
             }
         } else {
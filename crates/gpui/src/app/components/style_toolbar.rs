@@ -0,0 +1,289 @@
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Icon, Selectable, Sizable,
+    button::{Button, ButtonVariants},
+    h_flex,
+    menu::{DropdownMenu as _, PopupMenuItem},
+    v_flex,
+};
+use uuid::Uuid;
+
+use crate::app::{
+    components::{
+        link_editor::LinkEditor,
+        rich_text::{PALETTE, RichTextState, RichTextStyle, palette_color, palette_highlight},
+    },
+    states::node_state::NodeState,
+};
+
+/// Formatting toolbar floating over the selected text of a rich text
+pub struct StyleToolbar {
+    rich_text: Entity<RichTextState>,
+    /// Node of the text, turned into another type from the toolbar
+    node_id: Uuid,
+    state: Entity<NodeState>,
+    /// Type of the node, shown on the "turn into" dropdown
+    node_label: SharedString,
+    palette_open: bool,
+    pub focus_handle: FocusHandle,
+}
+
+impl StyleToolbar {
+    pub fn new(
+        rich_text: Entity<RichTextState>,
+        node_id: Uuid,
+        state: &Entity<NodeState>,
+        node_label: impl Into<SharedString>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        cx.observe(&rich_text, |this, rich_text, cx| {
+            if rich_text.read(cx).selection().is_empty() {
+                this.palette_open = false;
+            }
+            cx.notify();
+        })
+        .detach();
+
+        Self {
+            rich_text,
+            node_id,
+            state: state.clone(),
+            node_label: node_label.into(),
+            palette_open: false,
+            focus_handle: cx.focus_handle(),
+        }
+    }
+
+    /// Dropdown listing the types the node can be turned into, see `NodeMenuProvider`
+    fn render_turn_into(&self) -> impl IntoElement {
+        let node_id = self.node_id;
+        let state = self.state.clone();
+
+        Button::new("style-turn-into")
+            .ghost()
+            .xsmall()
+            .label(self.node_label.clone())
+            .dropdown_caret(true)
+            .dropdown_menu(move |menu, _, cx| {
+                let items = state
+                    .read(cx)
+                    .get_current_nodes(node_id)
+                    .map(|node| node.element.menu_items(cx))
+                    .unwrap_or_default();
+
+                let mut menu = menu.label("Turn into");
+                for item in items {
+                    let state = state.clone();
+                    let action = item.action.clone();
+                    menu = menu.item(
+                        PopupMenuItem::new(item.label.clone())
+                            .icon(Icon::default().path(item.icon_path))
+                            .on_click(move |_, window, cx| (action)(&state, window, cx)),
+                    );
+                }
+                menu
+            })
+    }
+
+    fn render_style_button(
+        &self,
+        id: &'static str,
+        icon_path: &'static str,
+        tooltip: &'static str,
+        style: RichTextStyle,
+        styles: &[RichTextStyle],
+    ) -> impl IntoElement {
+        let rich_text = self.rich_text.clone();
+
+        Button::new(id)
+            .ghost()
+            .xsmall()
+            .icon(Icon::default().path(icon_path))
+            .tooltip(tooltip)
+            .selected(styles.contains(&style))
+            .on_click(move |_, _, cx| {
+                rich_text.update(cx, |rich_text, cx| rich_text.apply_style(style.clone(), cx));
+            })
+    }
+
+    fn render_palette(&self, styles: &[RichTextStyle], cx: &mut Context<Self>) -> impl IntoElement {
+        let text_color = styles.iter().find_map(|style| match style {
+            RichTextStyle::Color(name) => Some(name.clone()),
+            _ => None,
+        });
+        let highlight = styles.iter().find_map(|style| match style {
+            RichTextStyle::Highlight(name) => Some(name.clone()),
+            _ => None,
+        });
+
+        let swatch = |id: SharedString, is_selected: bool, cx: &App| {
+            div()
+                .id(id)
+                .size_6()
+                .flex()
+                .items_center()
+                .justify_center()
+                .rounded_sm()
+                .border_1()
+                .border_color(if is_selected {
+                    cx.theme().ring
+                } else {
+                    cx.theme().border
+                })
+                .cursor_pointer()
+                .text_sm()
+                .font_weight(FontWeight::MEDIUM)
+        };
+
+        let rich_text = self.rich_text.clone();
+        let colors = std::iter::once(None)
+            .chain(PALETTE.map(Some))
+            .map(|name| {
+                let rich_text = rich_text.clone();
+                let color = name.and_then(|name| palette_color(name, cx));
+                swatch(
+                    format!("text-color-{}", name.unwrap_or("default")).into(),
+                    text_color.as_deref() == name,
+                    cx,
+                )
+                .text_color(color.unwrap_or(cx.theme().foreground))
+                .child("A")
+                .on_click(move |_, _, cx| {
+                    rich_text.update(cx, |rich_text, cx| rich_text.set_text_color(name, cx));
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let highlights = std::iter::once(None)
+            .chain(PALETTE.map(Some))
+            .map(|name| {
+                let rich_text = rich_text.clone();
+                let background = name.and_then(|name| palette_highlight(name, cx));
+                swatch(
+                    format!("highlight-{}", name.unwrap_or("default")).into(),
+                    highlight.as_deref() == name,
+                    cx,
+                )
+                .when_some(background, |this, background| this.bg(background))
+                .on_click(move |_, _, cx| {
+                    rich_text.update(cx, |rich_text, cx| rich_text.set_highlight(name, cx));
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let label = |text: &'static str, cx: &App| {
+            div()
+                .text_xs()
+                .text_color(cx.theme().muted_foreground)
+                .child(text)
+        };
+
+        v_flex()
+            .gap_1()
+            .pt_1()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(label("Text", cx))
+            .child(h_flex().gap_1().children(colors))
+            .child(label("Highlight", cx))
+            .child(h_flex().gap_1().children(highlights))
+    }
+}
+
+impl Render for StyleToolbar {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let styles = self.rich_text.read(cx).selection_styles();
+        let is_colored = styles
+            .iter()
+            .any(|style| matches!(style, RichTextStyle::Color(_) | RichTextStyle::Highlight(_)));
+        let rich_text = self.rich_text.clone();
+
+        v_flex()
+            .id("style-toolbar")
+            .track_focus(&self.focus_handle)
+            .p_1()
+            .gap_1()
+            .bg(cx.theme().popover)
+            .border_1()
+            .border_color(cx.theme().border)
+            .rounded_md()
+            .shadow_md()
+            // Keep the focus, and the selection, in the text being formatted
+            .on_mouse_down(MouseButton::Left, |_, window, cx| {
+                window.prevent_default();
+                cx.stop_propagation();
+            })
+            .child(
+                h_flex()
+                    .gap_0p5()
+                    .items_center()
+                    .child(self.render_turn_into())
+                    .child(div().w(px(1.0)).h_4().mx_0p5().bg(cx.theme().border))
+                    .child(self.render_style_button(
+                        "style-bold",
+                        "icons/bold.svg",
+                        "Bold",
+                        RichTextStyle::Bold,
+                        &styles,
+                    ))
+                    .child(self.render_style_button(
+                        "style-italic",
+                        "icons/italic.svg",
+                        "Italic",
+                        RichTextStyle::Italic,
+                        &styles,
+                    ))
+                    .child(self.render_style_button(
+                        "style-underline",
+                        "icons/underline.svg",
+                        "Underline",
+                        RichTextStyle::Underline,
+                        &styles,
+                    ))
+                    .child(self.render_style_button(
+                        "style-strikethrough",
+                        "icons/strikethrough.svg",
+                        "Strikethrough",
+                        RichTextStyle::Strikethrough,
+                        &styles,
+                    ))
+                    .child(self.render_style_button(
+                        "style-code",
+                        "icons/code.svg",
+                        "Code",
+                        RichTextStyle::Code,
+                        &styles,
+                    ))
+                    .child(
+                        Button::new("style-link")
+                            .ghost()
+                            .xsmall()
+                            .icon(Icon::default().path("icons/link.svg"))
+                            .tooltip("Link")
+                            .selected(
+                                styles
+                                    .iter()
+                                    .any(|style| matches!(style, RichTextStyle::Link { .. })),
+                            )
+                            .on_click(move |_, window, cx| {
+                                LinkEditor::open(rich_text.clone(), window, cx);
+                            }),
+                    )
+                    .child(
+                        Button::new("style-color")
+                            .ghost()
+                            .xsmall()
+                            .icon(Icon::default().path("icons/baseline.svg"))
+                            .tooltip("Color")
+                            .selected(self.palette_open || is_colored)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.palette_open = !this.palette_open;
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .when(self.palette_open, |this| {
+                this.child(self.render_palette(&styles, cx))
+            })
+    }
+}
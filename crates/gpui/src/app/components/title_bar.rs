@@ -6,7 +6,7 @@ use gpui_component::{
     button::{Button, ButtonVariants},
 };
 
-use crate::app::states::settings_state::{Settings, ThemeMode};
+use crate::{app::states::settings_state::SettingsState, domain::entities::settings::ThemeMode};
 
 pub struct TitleBar;
 
@@ -16,7 +16,7 @@ impl TitleBar {
     }
 
     fn cycle_theme_mode(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        cx.update_global::<Settings, _>(|settings, _cx| {
+        cx.update_global::<SettingsState, _>(|settings, _cx| {
             settings.theme.mode = settings.theme.mode.next();
         });
 
@@ -28,7 +28,7 @@ impl TitleBar {
 impl Render for TitleBar {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let theme_mode = cx
-            .try_global::<Settings>()
+            .try_global::<SettingsState>()
             .map(|s| s.theme.mode)
             .unwrap_or_default();
 
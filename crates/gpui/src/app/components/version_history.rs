@@ -0,0 +1,281 @@
+use chrono::Local;
+use gpui::{prelude::FluentBuilder, *};
+use gpui_component::{
+    ActiveTheme, Disableable, Sizable, WindowExt,
+    button::{Button, ButtonVariants},
+    h_flex,
+    scroll::ScrollableElement,
+    v_flex,
+};
+use serde_json::Value;
+
+use crate::{
+    LoadingState,
+    app::states::{document_state::DocumentState, repository_state::RepositoryState},
+    domain::database::{
+        document::DocumentModel,
+        version::{BlockDiff, DocumentVersionModel},
+    },
+};
+
+/// Versions of a document, compared block by block with the document as currently edited
+pub struct VersionHistory {
+    document: DocumentModel,
+    versions: LoadingState<Vec<DocumentVersionModel>>,
+    selected_index: usize,
+    diff: Vec<BlockDiff>,
+}
+
+impl VersionHistory {
+    pub fn new(document: DocumentModel, cx: &mut Context<Self>) -> Self {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let document_id = document.id;
+
+        cx.spawn(async move |this, cx| {
+            let result = repository.get_versions(document_id).await;
+
+            let _ = this.update(cx, |this, cx| {
+                this.versions = match result {
+                    Ok(versions) => LoadingState::Loaded(versions),
+                    Err(error) => LoadingState::Error(error.to_string()),
+                };
+                this.select(0, cx);
+            });
+        })
+        .detach();
+
+        Self {
+            document,
+            versions: LoadingState::Loading,
+            selected_index: 0,
+            diff: Vec::new(),
+        }
+    }
+
+    /// Open the history of a document in a dialog
+    pub fn open(document: DocumentModel, window: &mut Window, cx: &mut App) {
+        let history = cx.new(|cx| VersionHistory::new(document, cx));
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            dialog
+                .w(px(760.))
+                .title(v_flex().text_sm().font_semibold().child("Version history"))
+                .overlay_closable(true)
+                .child(history.clone())
+        });
+    }
+
+    fn selected_version(&self) -> Option<&DocumentVersionModel> {
+        match &self.versions {
+            LoadingState::Loaded(versions) => versions.get(self.selected_index),
+            _ => None,
+        }
+    }
+
+    fn select(&mut self, index: usize, cx: &mut Context<Self>) {
+        self.selected_index = index;
+        self.diff = self
+            .selected_version()
+            .map(|version| version.diff(&self.document.content))
+            .unwrap_or_default();
+        cx.notify();
+    }
+
+    /// Save the document as edited, then bring back the selected version. Both states are
+    /// kept in the history, so the restore can be reverted from here as well.
+    fn restore(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(version) = self.selected_version().cloned() else {
+            return;
+        };
+
+        let repository = cx.global::<RepositoryState>().documents.clone();
+        let document = self.document.clone();
+        let document_id = document.id;
+
+        window.close_dialog(cx);
+        window
+            .spawn(cx, async move |cx| {
+                let result = async {
+                    repository.update_document(document).await?;
+                    repository.restore_version(document_id, version.id).await
+                }
+                .await;
+
+                let _ = cx.update(|window, cx| match result {
+                    Ok(_) => {
+                        cx.update_global::<DocumentState, _>(|state, _| {
+                            state.reload_document(document_id);
+                        });
+                        window.push_notification(
+                            format!("Restored the version of {}", format_date(&version)),
+                            cx,
+                        );
+                    }
+                    Err(error) => window
+                        .push_notification(format!("Could not restore the version: {}", error), cx),
+                });
+            })
+            .detach();
+    }
+
+    fn render_version(
+        &self,
+        index: usize,
+        version: &DocumentVersionModel,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let is_selected = index == self.selected_index;
+
+        v_flex()
+            .id(("document-version", index))
+            .w_full()
+            .px_2()
+            .py_1()
+            .rounded_md()
+            .cursor_pointer()
+            .when(is_selected, |this| this.bg(cx.theme().accent))
+            .hover(|this| this.bg(cx.theme().accent.opacity(0.5)))
+            .on_click(cx.listener(move |this, _, _, cx| this.select(index, cx)))
+            .child(div().text_sm().child(format_date(version)))
+            .child(
+                div()
+                    .text_xs()
+                    .text_ellipsis()
+                    .overflow_hidden()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(version.title.clone()),
+            )
+    }
+
+    fn render_diff(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        let mut lines: Vec<AnyElement> = Vec::new();
+
+        let line = |sign: &'static str, node: &Value, color: Hsla| {
+            h_flex()
+                .items_start()
+                .gap_2()
+                .font_family("monospace")
+                .text_xs()
+                .text_color(color)
+                .child(div().flex_shrink_0().child(sign))
+                .child(div().flex_1().min_w_0().child(node_json(node)))
+                .into_any_element()
+        };
+
+        for block in &self.diff {
+            match block {
+                BlockDiff::Added(node) => {
+                    added += 1;
+                    lines.push(line("+", node, cx.theme().success));
+                }
+                BlockDiff::Removed(node) => {
+                    removed += 1;
+                    lines.push(line("-", node, cx.theme().danger));
+                }
+                BlockDiff::Changed { before, after } => {
+                    changed += 1;
+                    lines.push(line("-", before, cx.theme().danger));
+                    lines.push(line("+", after, cx.theme().success));
+                }
+                BlockDiff::Unchanged(_) => {}
+            }
+        }
+
+        let summary = if lines.is_empty() {
+            "Identical to the current document".to_string()
+        } else {
+            format!(
+                "Since this version: {} added, {} removed, {} changed",
+                added, removed, changed
+            )
+        };
+
+        v_flex()
+            .flex_1()
+            .min_w_0()
+            .gap_2()
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(summary),
+            )
+            .child(
+                div()
+                    .id("version-diff")
+                    .h(px(360.))
+                    .overflow_y_scrollbar()
+                    .child(v_flex().gap_1().children(lines)),
+            )
+    }
+}
+
+impl Render for VersionHistory {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let versions = match &self.versions {
+            LoadingState::Loaded(versions) => versions.clone(),
+            LoadingState::Loading => {
+                return div().child("Loading...").into_any_element();
+            }
+            LoadingState::Error(error) => {
+                return div().child(error.clone()).into_any_element();
+            }
+        };
+
+        if versions.is_empty() {
+            return div()
+                .text_sm()
+                .text_color(cx.theme().muted_foreground)
+                .child("No version yet, one is taken while the document is edited")
+                .into_any_element();
+        }
+
+        let items: Vec<AnyElement> = versions
+            .iter()
+            .enumerate()
+            .map(|(index, version)| self.render_version(index, version, cx).into_any_element())
+            .collect();
+
+        v_flex()
+            .gap_3()
+            .child(
+                h_flex()
+                    .items_start()
+                    .gap_3()
+                    .child(
+                        div()
+                            .id("version-list")
+                            .w(px(220.))
+                            .h(px(384.))
+                            .flex_shrink_0()
+                            .overflow_y_scrollbar()
+                            .child(v_flex().children(items)),
+                    )
+                    .child(self.render_diff(cx)),
+            )
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("restore-version")
+                        .small()
+                        .primary()
+                        .label("Restore this version")
+                        .disabled(self.selected_version().is_none())
+                        .on_click(cx.listener(|this, _, window, cx| this.restore(window, cx))),
+                ),
+            )
+            .into_any_element()
+    }
+}
+
+fn format_date(version: &DocumentVersionModel) -> String {
+    version
+        .created_at
+        .with_timezone(&Local)
+        .format("%b %-d, %Y %H:%M")
+        .to_string()
+}
+
+fn node_json(node: &Value) -> String {
+    serde_json::to_string(node).unwrap_or_default()
+}
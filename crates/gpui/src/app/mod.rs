@@ -1,24 +1,21 @@
 pub mod components;
-pub mod remindr;
 pub mod screens;
 pub mod states;
 
 use gpui::{App, SharedString, Window, WindowAppearance};
 use gpui_component::theme::{Theme, ThemeRegistry};
 
-use self::states::settings_state::{Settings, ThemeMode};
+use crate::{
+    app::states::settings_state::SettingsState,
+    domain::entities::settings::{ThemeMode, ThemeSettings},
+};
 
 /// Get theme settings and determine if dark mode should be used
-fn get_theme_info(cx: &App) -> (states::settings_state::ThemeSettings, bool) {
+fn get_theme_info(cx: &App) -> (ThemeSettings, bool) {
     let (theme_settings, mode) = cx
-        .try_global::<Settings>()
+        .try_global::<SettingsState>()
         .map(|s| (s.theme.clone(), s.theme.mode))
-        .unwrap_or_else(|| {
-            (
-                states::settings_state::ThemeSettings::default(),
-                ThemeMode::System,
-            )
-        });
+        .unwrap_or_else(|| (ThemeSettings::default(), ThemeMode::System));
 
     let is_system_dark = matches!(
         cx.window_appearance(),
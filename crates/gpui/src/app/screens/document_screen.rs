@@ -2,29 +2,41 @@ use std::time::Duration;
 
 use gpui::{prelude::FluentBuilder, *};
 use gpui_component::{
-    ActiveTheme, Colorize, Disableable, Icon, Sizable,
+    ActiveTheme, Colorize, Disableable, Icon, Sizable, WindowExt,
     button::{Button, ButtonVariants},
+    h_flex,
     input::Input,
     scroll::ScrollableElement,
     tab::{Tab, TabBar},
+    v_flex,
 };
 use gpui_nav::{Screen, ScreenContext};
+use serde_json::Value;
 
 use crate::{
     LoadingState,
     app::{
-        components::code_window::CodeWindow,
+        components::{
+            code_window::CodeWindow, collab_dialog::CollabDialog, version_history::VersionHistory,
+        },
         states::{
             app_state::AppState,
+            collab_state::CollabState,
             document_state::{DocumentContent, DocumentState, OpenedDocument, PersistenceState},
             repository_state::RepositoryState,
         },
     },
+    domain::database::{change::DocumentChange, document::DocumentModel},
+    infrastructure::sync::SyncStatus,
 };
 
 pub struct DocumentScreen {
     _ctx: ScreenContext<AppState>,
     initialized: bool,
+    /// Documents linking to the current one, with the id of the document they were read for,
+    /// or why they could not be read
+    backlinks: Option<(i32, Result<Vec<(i32, String)>, String>)>,
+    _backlinks_task: Task<()>,
 }
 
 impl Screen for DocumentScreen {
@@ -38,6 +50,8 @@ impl DocumentScreen {
         Self {
             _ctx: ScreenContext::new(app_state),
             initialized: false,
+            backlinks: None,
+            _backlinks_task: Task::ready(()),
         }
     }
 
@@ -49,9 +63,58 @@ impl DocumentScreen {
                 cx.notify();
             })
             .detach();
+
+            let sync = cx.global::<RepositoryState>().sync.clone();
+            cx.observe(&sync, |_, _, cx| cx.notify()).detach();
+
+            // Any save may add or remove a link to the current document
+            let changes = cx.global::<RepositoryState>().changes.clone();
+            cx.subscribe(&changes, |this, _, _: &DocumentChange, cx| {
+                if let Some((uid, _)) = this.backlinks {
+                    this.load_backlinks(uid, cx);
+                }
+            })
+            .detach();
+        }
+    }
+
+    fn load_backlinks_if_needed(&mut self, cx: &mut Context<Self>) {
+        let uid = cx.global::<DocumentState>().current_opened_document;
+        match uid {
+            Some(uid) if self.backlinks.as_ref().map(|(id, _)| *id) != Some(uid) => {
+                self.backlinks = Some((uid, Ok(Vec::new())));
+                self.load_backlinks(uid, cx);
+            }
+            Some(_) => {}
+            None => self.backlinks = None,
         }
     }
 
+    fn load_backlinks(&mut self, uid: i32, cx: &mut Context<Self>) {
+        let repository = cx.global::<RepositoryState>().documents.clone();
+
+        // Replacing the task drops the backlinks of the previous document
+        self._backlinks_task = cx.spawn(async move |this, cx| {
+            let backlinks = repository
+                .get_backlinks(uid)
+                .await
+                .map(|documents| {
+                    documents
+                        .into_iter()
+                        .map(|document| (document.id, document.title))
+                        .collect()
+                })
+                .map_err(|error| format!("Could not load the linking documents: {}", error));
+
+            let _ = this.update(cx, |this, cx| {
+                if this.backlinks.as_ref().map(|(id, _)| *id) == Some(uid) {
+                    this.backlinks = Some((uid, backlinks));
+                    cx.notify();
+                }
+            });
+        });
+    }
+
     fn load_document_if_needed(&self, window: &mut Window, cx: &mut Context<Self>) {
         let (needs_loading, document_id, is_stuck) =
             cx.read_global::<DocumentState, _>(|state, _| {
@@ -133,10 +196,73 @@ impl DocumentScreen {
     }
 }
 
+impl DocumentScreen {
+    /// Save the document if it was edited, then close its tab. The tab stays open when the
+    /// save fails so the edits are not lost.
+    fn close_tab(&mut self, uid: i32, window: &mut Window, cx: &mut Context<Self>) {
+        let flush = cx
+            .update_global::<DocumentState, _>(|state, cx| state.flush_documents(Some(&[uid]), cx));
+
+        cx.spawn_in(window, async move |_, cx| {
+            let result = flush.await;
+
+            let _ = cx.update(|window, cx| match result {
+                Ok(()) => {
+                    cx.update_global::<DocumentState, _>(|state, _| {
+                        let previous_document = state.get_previous_document(uid);
+                        state.current_opened_document = previous_document.map(|doc| doc.uid);
+                        state.remove_document(uid);
+                    });
+                    CollabState::stop(uid, cx);
+                }
+                Err(error) => window.push_notification(error.to_string(), cx),
+            });
+        })
+        .detach();
+    }
+
+    /// Forward a pending scroll request (e.g. from the search palette) to the loaded renderer
+    fn apply_scroll_target(&self, cx: &mut Context<Self>) {
+        let current_document = cx.read_global::<DocumentState, _>(|state, _| {
+            state
+                .get_current_document()
+                .and_then(|doc| match &doc.state {
+                    LoadingState::Loaded(content) if doc.scroll_target.is_some() => {
+                        Some((doc.uid, content.renderer.clone()))
+                    }
+                    _ => None,
+                })
+        });
+
+        if let Some((uid, renderer)) = current_document {
+            let target =
+                cx.update_global::<DocumentState, _>(|state, _| state.take_scroll_target(uid));
+
+            if let Some(node_id) = target {
+                renderer.update(cx, |renderer, cx| renderer.scroll_to_node(node_id, cx));
+            }
+        }
+    }
+}
+
 impl Render for DocumentScreen {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         self.ensure_initialized(cx);
         self.load_document_if_needed(window, cx);
+        self.load_backlinks_if_needed(cx);
+        self.apply_scroll_target(cx);
+
+        let breadcrumbs = cx.read_global::<DocumentState, _>(|state, _| {
+            state
+                .current_opened_document
+                .map(|uid| state.breadcrumbs(uid))
+                .unwrap_or_default()
+        });
+        let (backlinks, backlinks_error) = match &self.backlinks {
+            Some((_, Ok(backlinks))) => (backlinks.clone(), None),
+            Some((_, Err(error))) => (Vec::new(), Some(error.clone())),
+            None => (Vec::new(), None),
+        };
 
         let (documents, current_document, current_index, is_saving, can_go_previous, can_go_next) =
             cx.read_global::<DocumentState, _>(|state, _| {
@@ -163,22 +289,29 @@ impl Render for DocumentScreen {
             .w_full()
             .h_full()
             .relative()
-            .when(is_saving, |this| {
-                this.child(
-                    div().absolute().bottom_4().right_4().child(
-                        Icon::default()
-                            .path("icons/loader-circle.svg")
-                            .size_4()
-                            .with_animation(
-                                "rotate-loader",
-                                Animation::new(Duration::from_secs(1)).repeat(),
-                                |icon, delta| {
-                                    icon.transform(Transformation::rotate(percentage(delta)))
-                                },
-                            ),
-                    ),
-                )
-            })
+            .child(
+                h_flex()
+                    .absolute()
+                    .bottom_4()
+                    .right_4()
+                    .gap_2()
+                    .items_center()
+                    .children(self.render_sync_status(cx))
+                    .when(is_saving, |this| {
+                        this.child(
+                            Icon::default()
+                                .path("icons/loader-circle.svg")
+                                .size_4()
+                                .with_animation(
+                                    "rotate-loader",
+                                    Animation::new(Duration::from_secs(1)).repeat(),
+                                    |icon, delta| {
+                                        icon.transform(Transformation::rotate(percentage(delta)))
+                                    },
+                                ),
+                        )
+                    }),
+            )
             .when(!documents.is_empty(), |this| {
                 this.child(
                     TabBar::new("tabs")
@@ -240,36 +373,100 @@ impl Render for DocumentScreen {
                                 ),
                         )
                         .suffix(
-                            div().px_4().flex().items_center().child(
-                                Button::new("toggle-code-btn")
-                                    .xsmall()
-                                    .ghost()
-                                    .cursor_pointer()
-                                    .icon(Icon::default().path("icons/braces.svg"))
-                                    .tooltip("Open code view")
-                                    .on_click({
-                                        let current_doc = current_document.clone();
-                                        cx.listener(move |_, _, _, cx| {
-                                            if let Some(doc) = &current_doc {
-                                                if let LoadingState::Loaded(content) = &doc.state {
-                                                    let nodes = content
-                                                        .renderer
-                                                        .read(cx)
-                                                        .state
-                                                        .read(cx)
-                                                        .get_nodes()
-                                                        .clone();
-                                                    CodeWindow::open(
-                                                        doc.title.clone(),
-                                                        doc.uid,
-                                                        nodes,
-                                                        cx,
-                                                    );
+                            div()
+                                .px_4()
+                                .flex()
+                                .items_center()
+                                .gap_1()
+                                .child(
+                                    Button::new("collab-btn")
+                                        .xsmall()
+                                        .ghost()
+                                        .cursor_pointer()
+                                        .icon(Icon::default().path("icons/users.svg"))
+                                        .tooltip("Collaborate")
+                                        .on_click({
+                                            let current_doc = current_document.clone();
+                                            cx.listener(move |_, _, window, cx| {
+                                                if let Some(doc) = &current_doc {
+                                                    if let LoadingState::Loaded(content) =
+                                                        &doc.state
+                                                    {
+                                                        let node_state =
+                                                            content.renderer.read(cx).state.clone();
+                                                        CollabDialog::open(
+                                                            doc.uid, node_state, window, cx,
+                                                        );
+                                                    }
                                                 }
-                                            }
-                                        })
-                                    }),
-                            ),
+                                            })
+                                        }),
+                                )
+                                .child(
+                                    Button::new("history-btn")
+                                        .xsmall()
+                                        .ghost()
+                                        .cursor_pointer()
+                                        .icon(Icon::default().path("icons/history.svg"))
+                                        .tooltip("Version history")
+                                        .on_click({
+                                            let current_doc = current_document.clone();
+                                            cx.listener(move |_, _, window, cx| {
+                                                if let Some(doc) = &current_doc {
+                                                    if let LoadingState::Loaded(content) =
+                                                        &doc.state
+                                                    {
+                                                        let nodes = content
+                                                            .renderer
+                                                            .read(cx)
+                                                            .state
+                                                            .read(cx)
+                                                            .serialize_nodes(cx);
+                                                        let document = DocumentModel {
+                                                            id: doc.uid,
+                                                            parent_id: None,
+                                                            position: 0,
+                                                            title: doc.title.clone(),
+                                                            content: Value::from_iter(nodes),
+                                                        };
+                                                        VersionHistory::open(document, window, cx);
+                                                    }
+                                                }
+                                            })
+                                        }),
+                                )
+                                .child(
+                                    Button::new("toggle-code-btn")
+                                        .xsmall()
+                                        .ghost()
+                                        .cursor_pointer()
+                                        .icon(Icon::default().path("icons/braces.svg"))
+                                        .tooltip("Open code view")
+                                        .on_click({
+                                            let current_doc = current_document.clone();
+                                            cx.listener(move |_, _, window, cx| {
+                                                if let Some(doc) = &current_doc {
+                                                    if let LoadingState::Loaded(content) =
+                                                        &doc.state
+                                                    {
+                                                        let nodes = content
+                                                            .renderer
+                                                            .read(cx)
+                                                            .state
+                                                            .read(cx)
+                                                            .serialize_nodes(cx);
+                                                        CodeWindow::open(
+                                                            doc.title.clone(),
+                                                            doc.uid,
+                                                            nodes,
+                                                            window.window_handle(),
+                                                            cx,
+                                                        );
+                                                    }
+                                                }
+                                            })
+                                        }),
+                                ),
                         )
                         .selected_index(current_index.unwrap_or(0))
                         .on_click(cx.listener(|_, index: &usize, _, cx| {
@@ -294,37 +491,88 @@ impl Render for DocumentScreen {
                                         .tooltip("Close tab")
                                         .on_click({
                                             let element_id = element.uid;
-                                            cx.listener(move |_, _, _, cx| {
-                                                cx.update_global::<DocumentState, _>(|state, _| {
-                                                    let previous_document =
-                                                        state.get_previous_document(element_id);
-
-                                                    state.current_opened_document =
-                                                        previous_document.map(|doc| doc.uid);
-
-                                                    state.remove_document(element_id);
-                                                })
+                                            cx.listener(move |this, _, window, cx| {
+                                                this.close_tab(element_id, window, cx);
                                             })
                                         }),
                                 )
                         })),
                 )
-                .child(self.render_document_content(current_document))
+                .child(self.render_document_content(
+                    current_document,
+                    breadcrumbs,
+                    backlinks,
+                    backlinks_error,
+                ))
             })
             .when(documents.is_empty(), |this| this.child(DocumentStateEmpty))
     }
 }
 
 impl DocumentScreen {
+    /// Where the offline copy of a remote workspace stands, clicking it syncing right away
+    fn render_sync_status(&self, cx: &Context<Self>) -> Option<impl IntoElement> {
+        let status = cx
+            .global::<RepositoryState>()
+            .sync
+            .read(cx)
+            .status
+            .clone()?;
+        let muted = cx.theme().muted_foreground;
+
+        let (icon, label, color) = match status {
+            SyncStatus::Syncing => ("icons/refresh-cw.svg", "Syncing".to_string(), muted),
+            SyncStatus::Synced => ("icons/cloud-check.svg", "Synced".to_string(), muted),
+            SyncStatus::Offline { pending: 0 } => {
+                ("icons/cloud-off.svg", "Offline".to_string(), muted)
+            }
+            SyncStatus::Offline { pending } => (
+                "icons/cloud-off.svg",
+                format!("Offline · {} pending", pending),
+                muted,
+            ),
+            SyncStatus::Failed(error) => (
+                "icons/triangle-alert.svg",
+                format!("Sync failed: {}", error),
+                cx.theme().danger,
+            ),
+        };
+
+        Some(
+            h_flex()
+                .id("sync-status")
+                .gap_1()
+                .items_center()
+                .max_w(px(320.))
+                .text_xs()
+                .text_color(color)
+                .cursor_pointer()
+                .child(Icon::default().path(icon).size_3())
+                .child(div().truncate().child(label))
+                .on_click(|_, _, cx| {
+                    if let Some(sync) = cx.global::<RepositoryState>().documents.sync() {
+                        sync.sync_now();
+                    }
+                }),
+        )
+    }
+
     fn render_document_content(
         &self,
         current_document: Option<OpenedDocument>,
+        breadcrumbs: Vec<(i32, String)>,
+        backlinks: Vec<(i32, String)>,
+        backlinks_error: Option<String>,
     ) -> impl IntoElement {
         match current_document {
             Some(doc) => match &doc.state {
                 LoadingState::Loading => DocumentLoading.into_any_element(),
                 LoadingState::Loaded(content) => DocumentStateLoaded {
                     content: content.clone(),
+                    title: doc.title.clone(),
+                    breadcrumbs,
+                    backlinks,
+                    backlinks_error,
                 }
                 .into_any_element(),
                 LoadingState::Error(error) => DocumentLoadingError {
@@ -373,10 +621,117 @@ impl RenderOnce for DocumentLoadingError {
 #[derive(IntoElement)]
 struct DocumentStateLoaded {
     content: DocumentContent,
+    title: String,
+    /// Pages containing the document, from the top level down
+    breadcrumbs: Vec<(i32, String)>,
+    /// Documents linking to this one
+    backlinks: Vec<(i32, String)>,
+    /// Why the documents linking to this one could not be read
+    backlinks_error: Option<String>,
+}
+
+impl DocumentStateLoaded {
+    fn render_breadcrumbs(&self, cx: &App) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let hover_bg = cx.theme().accent;
+        let separator = || {
+            Icon::default()
+                .path("icons/chevron-right.svg")
+                .size_3()
+                .text_color(muted)
+        };
+
+        h_flex()
+            .ml_10()
+            .mb_2()
+            .gap_1()
+            .text_xs()
+            .text_color(muted)
+            .children(self.breadcrumbs.iter().map(|(id, title)| {
+                let id = *id;
+                let title = title.clone();
+
+                h_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .id(("breadcrumb", id as usize))
+                            .px_1()
+                            .rounded_sm()
+                            .cursor_pointer()
+                            .hover(|this| this.bg(hover_bg))
+                            .child(title.clone())
+                            .on_click(move |_, _, cx| {
+                                cx.update_global::<DocumentState, _>(|state, _| {
+                                    state.open_document(id, title.clone());
+                                });
+                            }),
+                    )
+                    .child(separator())
+            }))
+            .child(div().px_1().child(self.title.clone()))
+    }
+}
+
+impl DocumentStateLoaded {
+    fn render_backlinks(&self, cx: &App) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let hover_bg = cx.theme().accent;
+
+        v_flex()
+            .ml_10()
+            .mt_10()
+            .pt_3()
+            .gap_1()
+            .border_t_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .gap_1()
+                    .items_center()
+                    .text_xs()
+                    .text_color(muted)
+                    .child(Icon::default().path("icons/link.svg").size_3())
+                    .child(match (&self.backlinks_error, self.backlinks.len()) {
+                        (Some(error), _) => error.clone(),
+                        (None, 1) => "Linked from 1 document".to_string(),
+                        (None, count) => format!("Linked from {} documents", count),
+                    }),
+            )
+            .children(self.backlinks.iter().map(|(id, title)| {
+                let id = *id;
+                let title = title.clone();
+
+                h_flex()
+                    .id(("backlink", id as usize))
+                    .gap_2()
+                    .px_1()
+                    .py_0p5()
+                    .items_center()
+                    .rounded_sm()
+                    .text_sm()
+                    .cursor_pointer()
+                    .hover(|this| this.bg(hover_bg))
+                    .child(
+                        Icon::default()
+                            .path("icons/file-text.svg")
+                            .size_4()
+                            .text_color(muted),
+                    )
+                    .child(title.clone())
+                    .on_click(move |_, _, cx| {
+                        cx.update_global::<DocumentState, _>(|state, _| {
+                            state.open_document(id, title.clone());
+                        });
+                    })
+            }))
+    }
 }
 
 impl RenderOnce for DocumentStateLoaded {
     fn render(self, _window: &mut Window, cx: &mut App) -> impl IntoElement {
+        let scroll_handle = self.content.renderer.read(cx).scroll_handle.clone();
+
         div()
             .bg(cx.theme().background.lighten(0.2))
             .flex()
@@ -386,17 +741,23 @@ impl RenderOnce for DocumentStateLoaded {
             .overflow_hidden()
             .child(
                 div()
+                    .id("document-content")
                     .flex()
                     .gap_10()
                     .flex_1()
                     .min_h_0()
-                    .overflow_y_scrollbar()
+                    .track_scroll(&scroll_handle)
+                    .overflow_y_scroll()
+                    .vertical_scrollbar(&scroll_handle)
                     .child(
                         div()
                             .max_w(px(820.0))
                             .w_full()
                             .mx_auto()
                             .py_5()
+                            .when(!self.breadcrumbs.is_empty(), |this| {
+                                this.child(self.render_breadcrumbs(cx))
+                            })
                             .child(
                                 Input::new(&self.content.title_input)
                                     .appearance(false)
@@ -404,7 +765,11 @@ impl RenderOnce for DocumentStateLoaded {
                                     .ml_10()
                                     .large(),
                             )
-                            .child(self.content.renderer.clone()),
+                            .child(self.content.renderer.clone())
+                            .when(
+                                !self.backlinks.is_empty() || self.backlinks_error.is_some(),
+                                |this| this.child(self.render_backlinks(cx)),
+                            ),
                     ),
             )
     }
@@ -2,7 +2,11 @@ use gpui::*;
 use gpui_component::{ActiveTheme, Root};
 
 use crate::app::{
-    components::{sidebar::AppSidebar, title_bar::TitleBar},
+    components::{
+        search_palette::{OpenSearchPalette, SearchPalette},
+        sidebar::AppSidebar,
+        title_bar::TitleBar,
+    },
     screens::home_screen::HomeScreen,
     states::app_state::AppState,
 };
@@ -15,6 +19,7 @@ pub struct AppRouter {
     app_state: Entity<AppState>,
     sidebar: Entity<AppSidebar>,
     title_bar: Entity<TitleBar>,
+    focus_handle: FocusHandle,
 }
 
 impl AppRouter {
@@ -30,6 +35,7 @@ impl AppRouter {
             app_state: app_state.clone(),
             sidebar: AppSidebar::new(app_state, cx),
             title_bar: cx.new(TitleBar::new),
+            focus_handle: cx.focus_handle(),
         }
     }
 }
@@ -39,7 +45,16 @@ impl Render for AppRouter {
         let notification_layer = Root::render_notification_layer(window, cx);
         let dialog_layer = Root::render_dialog_layer(window, cx);
 
+        // Keep application shortcuts reachable when no input is focused
+        if window.focused(cx).is_none() {
+            self.focus_handle.focus(window);
+        }
+
         div()
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(|this, _: &OpenSearchPalette, window, cx| {
+                SearchPalette::open(this.app_state.clone(), window, cx);
+            }))
             .w_full()
             .h_full()
             .flex()
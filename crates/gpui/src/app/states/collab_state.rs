@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::Error;
+use gpui::{
+    AnyWindowHandle, App, AppContext, BorrowAppContext, Context, Entity, EntityId, Global,
+    Subscription, Task, Window, rgb,
+};
+use gpui_component::WindowExt;
+use serde_json::Value;
+use tokio::sync::broadcast::{Receiver, error::RecvError};
+
+use crate::{
+    Utils,
+    app::{
+        components::rich_text::{PeerCursor, Selection},
+        states::{document_state::DocumentState, node_state::NodeState},
+    },
+    collab::{
+        document::CollabBlock,
+        message::RemoteCursor,
+        peer::{CollabEvent, CollabPeer},
+    },
+};
+
+/// Port a document is shared on when none is given
+pub const DEFAULT_COLLAB_PORT: u16 = 7410;
+
+/// Colors the local cursor is picked from
+const CURSOR_COLORS: [u32; 6] = [0xe11d48, 0x2563eb, 0x16a34a, 0xd97706, 0x9333ea, 0x0891b2];
+
+/// How the local copy of a shared document reaches the others
+#[derive(Clone, Debug)]
+pub enum CollabRole {
+    /// Accepting the collaborators sending `token` on `address`
+    Host { address: SocketAddr, token: String },
+    /// Connected to the host at the address
+    Guest(String),
+}
+
+/// Collaboration sessions of the opened documents, by document id
+#[derive(Default)]
+pub struct CollabState {
+    sessions: HashMap<i32, Entity<CollabSession>>,
+}
+
+impl Global for CollabState {}
+
+impl CollabState {
+    pub fn session(&self, uid: i32) -> Option<Entity<CollabSession>> {
+        self.sessions.get(&uid).cloned()
+    }
+
+    /// Share the document `uid` with the collaborators connecting on `port`, from this
+    /// computer only unless `expose` lets the other computers of the network in
+    pub fn host(
+        uid: i32,
+        node_state: Entity<NodeState>,
+        port: u16,
+        expose: bool,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<SocketAddr, Error>> {
+        let (peer, events) = match Self::new_peer() {
+            Ok(peer) => peer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let window_handle = window.window_handle();
+        cx.spawn(async move |cx| {
+            let ip = if expose {
+                Ipv4Addr::UNSPECIFIED
+            } else {
+                Ipv4Addr::LOCALHOST
+            };
+            let token = CollabPeer::pairing_token();
+            let address = peer.host((ip, port), token.clone()).await?;
+
+            cx.update_window(window_handle, |_, window, cx| {
+                let role = CollabRole::Host { address, token };
+                let session = cx
+                    .new(|cx| CollabSession::new(uid, peer, role, node_state, events, window, cx));
+                Self::insert(uid, session, cx);
+            })?;
+
+            Ok(address)
+        })
+    }
+
+    /// Join the document shared on `address` with its pairing `token`, its content replacing
+    /// the one of `uid`
+    pub fn join(
+        uid: i32,
+        node_state: Entity<NodeState>,
+        address: String,
+        token: String,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<(), Error>> {
+        let (peer, events) = match Self::new_peer() {
+            Ok(peer) => peer,
+            Err(error) => return Task::ready(Err(error)),
+        };
+
+        let window_handle = window.window_handle();
+        cx.spawn(async move |cx| {
+            peer.join(address.as_str(), &token).await?;
+
+            cx.update_window(window_handle, |_, window, cx| {
+                let role = CollabRole::Guest(address);
+                let session = cx
+                    .new(|cx| CollabSession::new(uid, peer, role, node_state, events, window, cx));
+                Self::insert(uid, session, cx);
+            })?;
+
+            Ok(())
+        })
+    }
+
+    /// Stop sharing the document `uid`, disconnecting its collaborators
+    pub fn stop(uid: i32, cx: &mut App) {
+        let session = cx.update_global::<CollabState, _>(|state, _| state.sessions.remove(&uid));
+        if let Some(session) = session {
+            session.update(cx, |session, cx| session.clear_cursors(cx));
+        }
+    }
+
+    /// Send the local edits of the document `uid` to its collaborators, if it is shared
+    pub fn push_local(uid: i32, window: &mut Window, cx: &mut App) {
+        let session = cx.global::<CollabState>().session(uid);
+        if let Some(session) = session {
+            session.update(cx, |session, cx| session.push_local(window, cx));
+        }
+    }
+
+    fn new_peer() -> Result<(Arc<CollabPeer>, Receiver<CollabEvent>), Error> {
+        let name = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_else(|_| "Anonymous".to_string());
+        let color = CURSOR_COLORS[Utils::generate_uuid().as_u128() as usize % CURSOR_COLORS.len()];
+
+        let peer = CollabPeer::new(name, color)?;
+        // Remote changes are applied between two local edits, see `CollabSession::apply_remote`
+        peer.defer_updates();
+
+        // Subscribed before connecting, so that the first sync is not missed
+        let events = peer.subscribe();
+        Ok((Arc::new(peer), events))
+    }
+
+    fn insert(uid: i32, session: Entity<CollabSession>, cx: &mut App) {
+        let previous =
+            cx.update_global::<CollabState, _>(|state, _| state.sessions.insert(uid, session));
+        if let Some(previous) = previous {
+            previous.update(cx, |session, cx| session.clear_cursors(cx));
+        }
+    }
+}
+
+/// A shared document, kept in sync with the nodes displaying it
+pub struct CollabSession {
+    uid: i32,
+    pub role: CollabRole,
+    peer: Arc<CollabPeer>,
+    node_state: Entity<NodeState>,
+    /// Window showing the document, the one whose focus tells where the local cursor is
+    window_handle: AnyWindowHandle,
+    /// Whether the content of the host was received, local edits being kept until then
+    synced: bool,
+    /// Cursor updates of the rich texts, by rich text entity
+    cursor_observers: HashMap<EntityId, Subscription>,
+    _events_task: Task<()>,
+}
+
+impl CollabSession {
+    fn new(
+        uid: i32,
+        peer: Arc<CollabPeer>,
+        role: CollabRole,
+        node_state: Entity<NodeState>,
+        mut events: Receiver<CollabEvent>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let events_task = cx.spawn_in(window, async move |this, cx| {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(_)) => CollabEvent::RemoteChange,
+                    Err(RecvError::Closed) => break,
+                };
+
+                let handled =
+                    this.update_in(cx, |this, window, cx| this.handle_event(event, window, cx));
+                if handled.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let synced = matches!(role, CollabRole::Host { .. });
+        let mut session = Self {
+            uid,
+            role,
+            peer,
+            node_state,
+            window_handle: window.window_handle(),
+            synced,
+            cursor_observers: HashMap::new(),
+            _events_task: events_task,
+        };
+
+        // Collaborators start from the content of the host
+        session.push_local(window, cx);
+        session
+    }
+
+    /// Other peers of the document, with their cursor
+    pub fn collaborators(&self) -> Vec<RemoteCursor> {
+        self.peer.cursors()
+    }
+
+    fn handle_event(&mut self, event: CollabEvent, window: &mut Window, cx: &mut Context<Self>) {
+        match event {
+            // The local content is dropped for the one of the host
+            CollabEvent::Synced if !self.synced => {
+                self.synced = true;
+                self.show_remote(window, cx);
+            }
+            CollabEvent::Synced => self.apply_remote(window, cx),
+            CollabEvent::RemoteChange => self.apply_remote(window, cx),
+            CollabEvent::CursorsChanged => self.show_cursors(cx),
+            CollabEvent::Disconnected { error } => match &self.role {
+                CollabRole::Guest(address) => {
+                    let reason = error
+                        .map(|error| format!(": {}", error))
+                        .unwrap_or_default();
+                    window
+                        .push_notification(format!("Disconnected from {}{}", address, reason), cx);
+                    let uid = self.uid;
+                    cx.defer(move |cx| CollabState::stop(uid, cx));
+                }
+                CollabRole::Host { .. } => {
+                    if let Some(error) = error {
+                        window.push_notification(
+                            format!("A collaborator was disconnected: {}", error),
+                            cx,
+                        );
+                    }
+                }
+            },
+        }
+        cx.notify();
+    }
+
+    /// Mirror the nodes in the shared document
+    fn push_local(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.synced {
+            return;
+        }
+
+        let blocks: Vec<CollabBlock> = self
+            .node_state
+            .read(cx)
+            .get_nodes()
+            .iter()
+            .map(|node| CollabBlock {
+                id: node.id.to_string(),
+                depth: node.depth,
+                node: node.element.get_data(cx),
+            })
+            .collect();
+        self.peer.document().sync_blocks(&blocks);
+
+        self.watch_cursors(window, cx);
+    }
+
+    /// Show the changes received from the collaborators. The shared document only changes
+    /// here, so the local edits not sent yet are pushed first and merged with theirs.
+    fn apply_remote(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if !self.synced {
+            return;
+        }
+
+        self.push_local(window, cx);
+        self.show_remote(window, cx);
+    }
+
+    fn show_remote(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let text_updates = match self.peer.apply_pending() {
+            Ok(text_updates) => text_updates,
+            Err(error) => {
+                window.push_notification(
+                    format!("Could not apply the changes of a collaborator: {}", error),
+                    cx,
+                );
+                return;
+            }
+        };
+
+        let blocks: Vec<(usize, Value)> = self
+            .peer
+            .document()
+            .blocks()
+            .into_iter()
+            .map(|block| (block.depth, block.node))
+            .collect();
+
+        let node_state = self.node_state.clone();
+        node_state.update(cx, |state, cx| {
+            state.apply_remote_nodes(&blocks, &text_updates, &node_state, window, cx);
+            cx.notify();
+        });
+
+        // Saved like a local edit, the collaborators saving their own copy
+        let uid = self.uid;
+        cx.update_global::<DocumentState, _>(|state, cx| {
+            state.mark_document_changed(uid, window, cx);
+        });
+
+        self.watch_cursors(window, cx);
+        self.show_cursors(cx);
+    }
+
+    /// Share the selection of the focused rich text, observing the ones added since last time
+    fn watch_cursors(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        // Edits made from another window, e.g. the code view
+        if window.window_handle() != self.window_handle {
+            return;
+        }
+
+        let rich_texts: Vec<_> = self
+            .node_state
+            .read(cx)
+            .get_nodes()
+            .iter()
+            .filter_map(|node| Some((node.id, node.element.rich_text(cx)?)))
+            .collect();
+
+        self.cursor_observers.retain(|entity_id, _| {
+            rich_texts
+                .iter()
+                .any(|(_, rich_text)| rich_text.entity_id() == *entity_id)
+        });
+
+        for (id, rich_text) in rich_texts {
+            if self.cursor_observers.contains_key(&rich_text.entity_id()) {
+                continue;
+            }
+
+            let subscription =
+                cx.observe_in(&rich_text, window, move |this, rich_text, window, cx| {
+                    let rich_text = rich_text.read(cx);
+                    if rich_text.focus_handle.is_focused(window) {
+                        let selection = rich_text.selection();
+                        this.peer.set_cursor(
+                            Some(id.to_string()),
+                            selection.anchor(),
+                            selection.head(),
+                        );
+                    }
+                });
+            self.cursor_observers
+                .insert(rich_text.entity_id(), subscription);
+        }
+    }
+
+    /// Give each rich text the cursors of the collaborators inside it
+    fn show_cursors(&self, cx: &mut Context<Self>) {
+        let cursors = self.peer.cursors();
+        let rich_texts: Vec<_> = self
+            .node_state
+            .read(cx)
+            .get_nodes()
+            .iter()
+            .filter_map(|node| Some((node.id.to_string(), node.element.rich_text(cx)?)))
+            .collect();
+
+        for (id, rich_text) in rich_texts {
+            let peer_cursors = cursors
+                .iter()
+                .filter(|cursor| cursor.block_id.as_deref() == Some(id.as_str()))
+                .map(|cursor| PeerCursor {
+                    name: cursor.name.clone().into(),
+                    color: rgb(cursor.color).into(),
+                    selection: Selection::new(cursor.anchor, cursor.head),
+                })
+                .collect();
+            rich_text.update(cx, |rich_text, cx| {
+                rich_text.set_peer_cursors(peer_cursors, cx)
+            });
+        }
+    }
+
+    fn clear_cursors(&mut self, cx: &mut Context<Self>) {
+        self.cursor_observers.clear();
+
+        let rich_texts: Vec<_> = self
+            .node_state
+            .read(cx)
+            .get_nodes()
+            .iter()
+            .filter_map(|node| node.element.rich_text(cx))
+            .collect();
+        for rich_text in rich_texts {
+            rich_text.update(cx, |rich_text, cx| {
+                rich_text.set_peer_cursors(Vec::new(), cx)
+            });
+        }
+    }
+}
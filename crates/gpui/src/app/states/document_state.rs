@@ -1,8 +1,16 @@
-use gpui::{App, AppContext, BorrowAppContext, Context, Entity, Global, Window};
-use gpui_component::input::{InputEvent, InputState};
+use anyhow::Error;
+use gpui::{App, AppContext, BorrowAppContext, Context, Entity, Global, Task, Window};
+use gpui_component::{
+    WindowExt,
+    input::{InputEvent, InputState},
+};
 use serde_json::Value;
-use std::time::{Duration, Instant};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
 use tokio::time::sleep;
+use uuid::Uuid;
 
 use crate::{
     LoadingState,
@@ -14,11 +22,19 @@ use crate::{
                 text::data::TextMetadata,
             },
         },
-        states::repository_state::RepositoryState,
+        states::{collab_state::CollabState, repository_state::RepositoryState},
     },
-    domain::database::document::DocumentModel,
+    domain::database::{change::DocumentChange, document::DocumentModel},
 };
 
+/// A version is taken before the first autosave of a session, then every
+/// `VERSION_EVERY_SAVES` autosaves or `VERSION_INTERVAL`, whichever comes first
+const VERSION_EVERY_SAVES: u32 = 25;
+const VERSION_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Time without edits after which a document is saved
+const SAVE_DEBOUNCE: Duration = Duration::from_secs(1);
+
 /// Helper entity to handle title input events with proper subscription context
 pub struct TitleInputHandler {
     pub input_state: Entity<InputState>,
@@ -63,7 +79,7 @@ impl TitleInputHandler {
                         {
                             doc.title = new_title;
                         }
-                        doc_state.mark_changed(window, cx);
+                        doc_state.mark_document_changed(document_id, window, cx);
                     });
                 }
                 _ => {}
@@ -86,6 +102,13 @@ pub struct OpenedDocument {
     pub state: LoadingState<DocumentContent>,
     /// Indicates if a loading task is currently in progress
     pub loading_in_progress: bool,
+    /// Block to bring into view once the document is displayed
+    pub scroll_target: Option<Uuid>,
+    /// Edited since the document was last saved
+    pub dirty: bool,
+    /// Autosaves written since a version of the document was last taken
+    pub saves_since_version: u32,
+    pub last_version: Option<Instant>,
 }
 
 #[derive(Clone)]
@@ -96,6 +119,21 @@ pub struct DocumentContent {
     _title_handler: Entity<TitleInputHandler>,
 }
 
+/// Tab closed because its document went to the trash, reopened when it is restored
+#[derive(Clone)]
+pub struct TrashedTab {
+    pub index: usize,
+    pub document: OpenedDocument,
+    pub was_current: bool,
+}
+
+/// Place of a document in the page tree, as last listed by the sidebar
+#[derive(Clone)]
+pub struct DocumentOutline {
+    pub parent_id: Option<i32>,
+    pub title: String,
+}
+
 #[derive(Clone, PartialEq)]
 pub enum PersistenceState {
     Pending,
@@ -107,7 +145,15 @@ pub struct DocumentState {
     pub current_opened_document: Option<i32>,
 
     pub persistence: PersistenceState,
-    pub last_change: Option<Instant>,
+    /// Time of the last edit of each document waiting to be saved
+    pub save_queue: HashMap<i32, Instant>,
+    saves_in_progress: usize,
+    /// Last save of each document, the next one being written only once it is done
+    saves: HashMap<i32, Task<()>>,
+
+    /// Page tree of the workspace, used to build the breadcrumbs
+    pub outline: HashMap<i32, DocumentOutline>,
+    pub trashed_tabs: Vec<TrashedTab>,
 }
 
 impl DocumentState {
@@ -143,11 +189,72 @@ impl DocumentState {
                 title,
                 state: LoadingState::Loading,
                 loading_in_progress: false,
+                scroll_target: None,
+                dirty: false,
+                saves_since_version: 0,
+                last_version: None,
             });
         }
         self.current_opened_document = Some(id);
     }
 
+    /// Open the document a link points to, `false` when it is no longer in the workspace
+    pub fn open_link(&mut self, id: i32) -> bool {
+        let Some(title) = self.outline.get(&id).map(|document| document.title.clone()) else {
+            return false;
+        };
+
+        self.open_document(id, title);
+        true
+    }
+
+    /// Count a save of a document, telling whether a version should be taken first
+    fn record_save(&mut self, uid: i32) -> bool {
+        let Some(doc) = self.documents.iter_mut().find(|d| d.uid == uid) else {
+            return false;
+        };
+
+        let is_due = match doc.last_version {
+            None => true,
+            Some(last) => {
+                doc.saves_since_version >= VERSION_EVERY_SAVES || last.elapsed() >= VERSION_INTERVAL
+            }
+        };
+
+        if is_due {
+            doc.saves_since_version = 0;
+            doc.last_version = Some(Instant::now());
+        }
+        doc.saves_since_version += 1;
+
+        is_due
+    }
+
+    /// Drop the loaded content of a document so it is read again from the database
+    pub fn reload_document(&mut self, uid: i32) {
+        // A pending autosave would write the previous content back
+        self.save_queue.remove(&uid);
+        if let Some(doc) = self.documents.iter_mut().find(|d| d.uid == uid) {
+            doc.state = LoadingState::Loading;
+            doc.loading_in_progress = false;
+            doc.dirty = false;
+        }
+    }
+
+    /// Scroll to a block of a document the next time it is displayed
+    pub fn scroll_to_node(&mut self, uid: i32, node_id: Uuid) {
+        if let Some(doc) = self.documents.iter_mut().find(|d| d.uid == uid) {
+            doc.scroll_target = Some(node_id);
+        }
+    }
+
+    pub fn take_scroll_target(&mut self, uid: i32) -> Option<Uuid> {
+        self.documents
+            .iter_mut()
+            .find(|d| d.uid == uid)
+            .and_then(|doc| doc.scroll_target.take())
+    }
+
     /// Create document content (entities) - call this outside of update_global
     pub fn create_document_content(
         uid: i32,
@@ -241,74 +348,284 @@ impl DocumentState {
         self.documents.retain(|element| element.uid != uid);
     }
 
-    pub fn mark_changed(&mut self, _: &mut Window, cx: &mut App) {
+    /// Close every tab, used when the documents come from another database
+    pub fn close_all_documents(&mut self) {
+        self.documents.clear();
+        self.current_opened_document = None;
+        self.outline.clear();
+        self.trashed_tabs.clear();
+    }
+
+    /// Close the tabs of trashed documents, keeping them to reopen on restore
+    pub fn trash_documents(&mut self, ids: &[i32]) {
+        for id in ids {
+            if let Some(index) = self.documents.iter().position(|doc| doc.uid == *id) {
+                let document = self.documents.remove(index);
+                self.trashed_tabs.push(TrashedTab {
+                    index,
+                    document,
+                    was_current: self.current_opened_document == Some(*id),
+                });
+            }
+        }
+
+        if self
+            .current_opened_document
+            .is_some_and(|id| ids.contains(&id))
+        {
+            self.current_opened_document = None;
+        }
+    }
+
+    /// Reopen the tabs closed when the documents went to the trash
+    pub fn restore_documents(&mut self, ids: &[i32]) {
+        let (mut restored, trashed): (Vec<TrashedTab>, Vec<TrashedTab>) = self
+            .trashed_tabs
+            .drain(..)
+            .partition(|tab| ids.contains(&tab.document.uid));
+        self.trashed_tabs = trashed;
+
+        restored.sort_by_key(|tab| tab.index);
+        for tab in restored {
+            if self.documents.iter().any(|doc| doc.uid == tab.document.uid) {
+                continue;
+            }
+
+            if tab.was_current {
+                self.current_opened_document = Some(tab.document.uid);
+            }
+            let index = tab.index.min(self.documents.len());
+            self.documents.insert(index, tab.document);
+        }
+    }
+
+    /// Drop the tabs kept for documents deleted forever
+    pub fn forget_trashed_documents(&mut self, ids: &[i32]) {
+        self.trashed_tabs
+            .retain(|tab| !ids.contains(&tab.document.uid));
+    }
+
+    /// Follow a change of the stored documents, made here or by another client
+    pub fn apply_change(&mut self, change: &DocumentChange) {
+        match change {
+            DocumentChange::Renamed { id, title } => {
+                if let Some(outline) = self.outline.get_mut(id) {
+                    outline.title = title.clone();
+                }
+
+                // The title input of a loaded document is what gets saved, it keeps the lead
+                if let Some(doc) = self
+                    .documents
+                    .iter_mut()
+                    .find(|doc| doc.uid == *id && !matches!(doc.state, LoadingState::Loaded(_)))
+                {
+                    doc.title = title.clone();
+                }
+            }
+            DocumentChange::Deleted { id } => self.trash_documents(&[*id]),
+            DocumentChange::Inserted { id } => self.restore_documents(&[*id]),
+            DocumentChange::Updated { .. } => {}
+        }
+    }
+
+    pub fn set_outline(&mut self, documents: &[DocumentModel]) {
+        self.outline = documents
+            .iter()
+            .map(|document| {
+                let outline = DocumentOutline {
+                    parent_id: document.parent_id,
+                    title: document.title.clone(),
+                };
+                (document.id, outline)
+            })
+            .collect();
+    }
+
+    /// Pages containing a document, from the top level down to its direct parent
+    pub fn breadcrumbs(&self, uid: i32) -> Vec<(i32, String)> {
+        let mut breadcrumbs: Vec<(i32, String)> = Vec::new();
+        let mut parent_id = self.outline.get(&uid).and_then(|doc| doc.parent_id);
+
+        while let Some(id) = parent_id {
+            // Stop on a cycle rather than looping forever
+            if id == uid || breadcrumbs.iter().any(|(crumb, _)| *crumb == id) {
+                break;
+            }
+            let Some(parent) = self.outline.get(&id) else {
+                break;
+            };
+
+            breadcrumbs.push((id, parent.title.clone()));
+            parent_id = parent.parent_id;
+        }
+
+        breadcrumbs.reverse();
+        breadcrumbs
+    }
+
+    /// Flag the document being displayed as edited
+    pub fn mark_changed(&mut self, window: &mut Window, cx: &mut App) {
+        if let Some(uid) = self.current_opened_document {
+            self.mark_document_changed(uid, window, cx);
+        }
+    }
+
+    /// Flag a document as edited and queue its save, done once it is left untouched for
+    /// `SAVE_DEBOUNCE`. Save errors are reported in `window`.
+    pub fn mark_document_changed(&mut self, uid: i32, window: &mut Window, cx: &mut App) {
+        let Some(doc) = self.documents.iter_mut().find(|d| d.uid == uid) else {
+            return;
+        };
+        doc.dirty = true;
+
         let trigger_time = Instant::now();
+        self.save_queue.insert(uid, trigger_time);
 
-        self.last_change = Some(trigger_time);
+        // Shared once the nodes that changed are no longer being updated
+        window.defer(cx, move |window, cx| {
+            CollabState::push_local(uid, window, cx)
+        });
+
+        let window_handle = window.window_handle();
+        cx.spawn(async move |cx| {
+            sleep(SAVE_DEBOUNCE).await;
+
+            let save = cx.update_global::<DocumentState, _>(|state, cx| {
+                // Pushed back by a later edit, or already flushed
+                (state.save_queue.get(&uid) == Some(&trigger_time))
+                    .then(|| state.flush_documents(Some(&[uid]), cx))
+            });
+
+            if let Ok(Some(save)) = save {
+                if let Err(error) = save.await {
+                    let _ = cx.update_window(window_handle, |_, window, cx| {
+                        window.push_notification(error.to_string(), cx);
+                    });
+                }
+            }
+        })
+        .detach();
+    }
 
-        let documents = cx.global::<RepositoryState>().documents.clone();
+    /// Save right away the edited documents among `ids`, or all of them when `None`.
+    /// Title and content are read now, so the tabs can be closed as soon as this returns.
+    /// Documents that could not be saved stay edited and are listed in the error.
+    pub fn flush_documents(
+        &mut self,
+        ids: Option<&[i32]>,
+        cx: &mut App,
+    ) -> Task<Result<(), Error>> {
+        let repository = cx.global::<RepositoryState>().documents.clone();
 
-        let document = self
+        let dirty_ids: Vec<i32> = self
             .documents
             .iter()
-            .find(|doc| Some(doc.uid) == self.current_opened_document)
-            .cloned();
-
-        if let Some(document) = document {
-            if let LoadingState::Loaded(content) = &document.state {
-                let renderer = content.renderer.clone();
-                let doc_uid = document.uid;
-                let doc_title = document.title.clone();
-
-                cx.spawn(async move |cx| {
-                    sleep(Duration::from_secs(1)).await;
-
-                    let _ = cx.update_global::<DocumentState, _>(move |state, cx| {
-                        if let Some(last) = state.last_change {
-                            if last <= trigger_time {
-                                // Debounce expired, start saving
-                                state.persistence = PersistenceState::Pending;
-                                cx.refresh_windows();
-
-                                let nodes = {
-                                    let nodes = renderer.read(cx).state.clone();
-                                    let nodes = nodes.read(cx).get_nodes().clone();
-                                    nodes
-                                        .iter()
-                                        .map(|node| node.element.get_data(cx))
-                                        .collect::<Vec<_>>()
-                                };
-
-                                let document_model = DocumentModel {
-                                    id: doc_uid,
-                                    title: doc_title,
-                                    content: Value::from_iter(nodes),
-                                };
-
-                                cx.spawn(async move |cx| {
-                                    let result = documents.update_document(document_model).await;
-
-                                    // Minimum display time for the loader
-                                    sleep(Duration::from_secs(1)).await;
-
-                                    // Mark as idle when save completes
-                                    let _ = cx.update_global::<DocumentState, _>(|state, cx| {
-                                        state.persistence = PersistenceState::Idle;
-                                        cx.refresh_windows();
-                                    });
-
-                                    result
-                                })
-                                .detach();
-                            }
-                        }
-                    });
+            .filter(|doc| doc.dirty && ids.is_none_or(|ids| ids.contains(&doc.uid)))
+            .map(|doc| doc.uid)
+            .collect();
+
+        let mut saves = Vec::new();
+        for uid in dirty_ids {
+            self.save_queue.remove(&uid);
+            let take_version = self.record_save(uid);
+
+            let Some(doc) = self.documents.iter_mut().find(|d| d.uid == uid) else {
+                continue;
+            };
+            let LoadingState::Loaded(content) = &doc.state else {
+                continue;
+            };
+
+            // Saving only writes the title and content, the place in the tree is left untouched
+            let document = DocumentModel {
+                id: uid,
+                parent_id: None,
+                position: 0,
+                title: doc.title.clone(),
+                content: Value::from_iter(
+                    content.renderer.read(cx).state.read(cx).serialize_nodes(cx),
+                ),
+            };
+            doc.dirty = false;
+
+            // Chained to the previous save of the document, so they are written in order
+            let previous = self.saves.remove(&uid);
+            let repository = repository.clone();
+            let title = document.title.clone();
+            let (sender, receiver) = smol::channel::bounded(1);
+            let save = cx.spawn(async move |_| {
+                if let Some(previous) = previous {
+                    previous.await;
+                }
 
-                    Ok::<_, anyhow::Error>(())
-                })
-                .detach();
-            }
+                // The version keeps the stored state this save replaces
+                if take_version {
+                    let _ = repository.create_version(uid).await;
+                }
+                let _ = sender
+                    .send(repository.update_document(document).await)
+                    .await;
+            });
+            self.saves.insert(uid, save);
+            saves.push((uid, title, receiver));
         }
+
+        if saves.is_empty() {
+            return Task::ready(Ok(()));
+        }
+
+        self.saves_in_progress += 1;
+        self.persistence = PersistenceState::Pending;
+        cx.refresh_windows();
+
+        cx.spawn(async move |cx| {
+            let mut failures = Vec::new();
+            for (id, title, receiver) in saves {
+                let result = receiver
+                    .recv()
+                    .await
+                    .unwrap_or_else(|_| Err(anyhow::anyhow!("the save was cancelled")));
+                if let Err(error) = result {
+                    failures.push((id, title, error));
+                }
+            }
+
+            let _ = cx.update_global::<DocumentState, _>(|state, _| {
+                for (id, _, _) in &failures {
+                    if let Some(doc) = state.documents.iter_mut().find(|d| d.uid == *id) {
+                        doc.dirty = true;
+                    }
+                }
+            });
+
+            // Minimum display time for the loader
+            cx.spawn(async move |cx| {
+                sleep(Duration::from_secs(1)).await;
+                let _ = cx.update_global::<DocumentState, _>(|state, cx| {
+                    state.saves_in_progress -= 1;
+                    if state.saves_in_progress == 0 {
+                        state.persistence = PersistenceState::Idle;
+                        cx.refresh_windows();
+                    }
+                });
+            })
+            .detach();
+
+            match failures.first() {
+                None => Ok(()),
+                Some((_, _, error)) => {
+                    let titles: Vec<String> = failures
+                        .iter()
+                        .map(|(_, title, _)| format!("\"{}\"", title))
+                        .collect();
+                    Err(anyhow::anyhow!(
+                        "Could not save {}: {}",
+                        titles.join(", "),
+                        error
+                    ))
+                }
+            }
+        })
     }
 }
 
@@ -318,7 +635,11 @@ impl Default for DocumentState {
             documents: Vec::new(),
             current_opened_document: None,
             persistence: PersistenceState::Idle,
-            last_change: None,
+            save_queue: HashMap::new(),
+            saves_in_progress: 0,
+            saves: HashMap::new(),
+            outline: HashMap::new(),
+            trashed_tabs: Vec::new(),
         }
     }
 }
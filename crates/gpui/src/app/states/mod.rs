@@ -1,5 +1,7 @@
 pub mod app_state;
+pub mod collab_state;
 pub mod document_state;
+pub mod node_history;
 pub mod node_state;
 pub mod repository_state;
 pub mod settings_state;
@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use gpui::{Entity, EntityId};
+use uuid::Uuid;
+
+use crate::app::components::{
+    nodes::node::RemindrNode,
+    rich_text::{RichTextState, TextChange, TextEdit},
+};
+
+/// Maximum number of operations kept per document, text edits included
+const MAX_OPERATIONS: usize = 200;
+
+/// Operation recorded on a document, undone and redone in order across blocks
+#[derive(Clone)]
+pub enum NodeOperation {
+    /// Blocks inserted, removed, replaced or moved, with the layout before and after
+    Structure {
+        before: Vec<RemindrNode>,
+        after: Vec<RemindrNode>,
+    },
+    /// Text edited inside a block
+    Text {
+        rich_text: Entity<RichTextState>,
+        edit: TextEdit,
+    },
+    HeadingLevel {
+        id: Uuid,
+        before: u32,
+        after: u32,
+    },
+}
+
+impl NodeOperation {
+    /// Follow the changes of a collaborator, `undone` telling whether the operation is one
+    /// to redo. `false` when the operation touched what they removed or parsed again.
+    fn rebase(&mut self, changes: &RemoteChanges, undone: bool) -> bool {
+        match self {
+            NodeOperation::Structure { before, after } => {
+                if before
+                    .iter()
+                    .chain(after.iter())
+                    .any(|node| changes.removed.contains(&node.id))
+                {
+                    return false;
+                }
+
+                changes.rebase_layout(before);
+                changes.rebase_layout(after);
+                true
+            }
+            NodeOperation::Text { rich_text, edit } => {
+                let entity_id = rich_text.entity_id();
+                if changes.dropped_texts.contains(&entity_id) {
+                    return false;
+                }
+
+                changes
+                    .text_changes
+                    .get(&entity_id)
+                    .is_none_or(|text_changes| {
+                        text_changes
+                            .iter()
+                            .all(|change| edit.rebase(*change, undone))
+                    })
+            }
+            NodeOperation::HeadingLevel { id, .. } => !changes.removed.contains(id),
+        }
+    }
+}
+
+/// What a collaborator changed in the blocks of a document
+#[derive(Default)]
+pub struct RemoteChanges {
+    /// Blocks in their new layout
+    pub current: Vec<RemindrNode>,
+    /// Ids of the blocks they added
+    pub added: HashSet<Uuid>,
+    /// Ids of the blocks they removed
+    pub removed: HashSet<Uuid>,
+    /// Blocks parsed again, by the entity id of the element they replace
+    pub replaced: HashMap<EntityId, RemindrNode>,
+    /// Rich texts of the blocks removed or parsed again
+    pub dropped_texts: HashSet<EntityId>,
+    /// Changes made in place to the rich texts kept, in order
+    pub text_changes: HashMap<EntityId, Vec<TextChange>>,
+}
+
+impl RemoteChanges {
+    /// Bring a recorded layout up to date: the elements parsed again take the place of the
+    /// previous ones, and the added blocks follow the block they come after
+    fn rebase_layout(&self, layout: &mut Vec<RemindrNode>) {
+        for node in layout.iter_mut() {
+            if let Some(replacement) = self.replaced.get(&node.element.entity_id()) {
+                node.element = replacement.element.clone();
+            }
+        }
+
+        for (index, node) in self.current.iter().enumerate() {
+            if !self.added.contains(&node.id) || layout.iter().any(|kept| kept.id == node.id) {
+                continue;
+            }
+
+            let at = self.current[..index]
+                .iter()
+                .rev()
+                .find_map(|previous| layout.iter().position(|kept| kept.id == previous.id))
+                .map_or(0, |position| position + 1);
+            layout.insert(at, node.clone());
+        }
+    }
+}
+
+/// Transaction log of a document
+#[derive(Clone, Default)]
+pub struct NodeHistory {
+    undo_stack: Vec<NodeOperation>,
+    redo_stack: Vec<NodeOperation>,
+}
+
+impl NodeHistory {
+    /// Record a new operation, dropping everything that could have been redone
+    pub fn push(&mut self, operation: NodeOperation) {
+        self.redo_stack.clear();
+        self.undo_stack.push(operation);
+
+        if self.undo_stack.len() > MAX_OPERATIONS {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    /// Keep the operations recorded before a collaborator changed the document, dropping
+    /// the ones whose blocks they removed or changed beyond what can be followed
+    pub fn rebase(&mut self, changes: &RemoteChanges) {
+        self.undo_stack
+            .retain_mut(|operation| operation.rebase(changes, false));
+        self.redo_stack
+            .retain_mut(|operation| operation.rebase(changes, true));
+    }
+
+    /// Take the last operation to undo, it becomes the next one to redo
+    pub fn undo(&mut self) -> Option<NodeOperation> {
+        let operation = self.undo_stack.pop()?;
+        self.redo_stack.push(operation.clone());
+        Some(operation)
+    }
+
+    /// Take the last undone operation, it becomes the next one to undo
+    pub fn redo(&mut self) -> Option<NodeOperation> {
+        let operation = self.redo_stack.pop()?;
+        self.undo_stack.push(operation.clone());
+        Some(operation)
+    }
+}
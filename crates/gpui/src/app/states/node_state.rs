@@ -1,19 +1,45 @@
-use gpui::{App, AppContext, DragMoveEvent, Entity, Window};
+use std::{collections::HashMap, ops::Range};
+
+use anyhow::{Context as _, Error, bail};
+use gpui::{App, AppContext, BorrowAppContext, Context, DragMoveEvent, Entity, Window};
 use serde_json::{Value, from_value};
 use uuid::Uuid;
 
-use crate::app::components::nodes::{
-    divider::divider_node::DividerNode,
-    element::RemindrElement,
-    heading::heading_node::HeadingNode,
-    node::{PartialRemindrNode, RemindrNode, RemindrNodeType},
-    text::text_node::TextNode,
+use crate::{
+    Utils,
+    app::{
+        components::{
+            nodes::{
+                code::{code_node::CodeNode, data::CodeNodeData},
+                divider::{data::DividerNodeData, divider_node::DividerNode},
+                element::RemindrElement,
+                file::{data::FileNodeData, file_node::FileNode},
+                heading::{data::HeadingNodeData, heading_node::HeadingNode},
+                image::{data::ImageNodeData, image_node::ImageNode},
+                list::{
+                    data::{ListKind, ListNodeData},
+                    list_node::ListNode,
+                },
+                node::{PartialRemindrNode, RemindrNode, RemindrNodeType},
+                text::{data::TextNodeData, text_node::TextNode},
+                unknown::unknown_node::UnknownNode,
+            },
+            rich_text::{RichTextState, TextChange, TextEdit, TextSpan},
+        },
+        states::{
+            document_state::DocumentState,
+            node_history::{NodeHistory, NodeOperation, RemoteChanges},
+        },
+    },
+    collab::document::{TextDelta, TextUpdate},
 };
 
 #[derive(Clone, PartialEq)]
 pub enum MovingElement {
     Before,
     After,
+    /// Dropped as the first child of the target
+    Child,
 }
 
 #[derive(Clone)]
@@ -22,6 +48,7 @@ pub struct NodeState {
     pub hovered_drop_zone: Option<(Uuid, MovingElement)>,
     pub dragging_id: Option<Uuid>,
     pub is_dragging: bool,
+    history: NodeHistory,
 }
 
 impl NodeState {
@@ -73,35 +100,107 @@ impl NodeState {
         false
     }
 
-    pub fn drop_element_by_index(
-        &mut self,
-        from_index: usize,
-        target_index: usize,
-        position: MovingElement,
-    ) {
-        let element = self.elements.remove(from_index);
+    /// Range of indexes covered by the node at `index` and its descendants
+    fn subtree_range(&self, index: usize) -> Range<usize> {
+        let depth = self.elements[index].depth;
+        let end = self.elements[index + 1..]
+            .iter()
+            .position(|node| node.depth <= depth)
+            .map(|offset| index + 1 + offset)
+            .unwrap_or(self.elements.len());
 
-        let mut to_index = target_index;
+        index..end
+    }
 
-        match position {
-            MovingElement::After => {
-                if from_index < target_index {
-                    to_index = target_index.saturating_sub(1);
-                }
-            }
-            MovingElement::Before => {
-                if from_index >= target_index {
-                    to_index = target_index + 1;
+    /// Ensure every node is at most one level deeper than the node above it
+    fn normalize_depths(&mut self) {
+        let mut max_depth = 0;
+        for node in self.elements.iter_mut() {
+            node.depth = node.depth.min(max_depth);
+            max_depth = node.depth + 1;
+        }
+    }
+
+    /// Move a node, along with its children, next to or under the target node
+    pub fn move_node(&mut self, id: Uuid, target_id: Uuid, position: MovingElement) {
+        let from_index = self.elements.iter().position(|node| node.id == id);
+        let target_index = self.elements.iter().position(|node| node.id == target_id);
+
+        if let (Some(from_index), Some(target_index)) = (from_index, target_index) {
+            let range = self.subtree_range(from_index);
+
+            // A node cannot be dropped inside its own subtree
+            if !range.contains(&target_index) {
+                let before = self.elements.clone();
+                let mut moved: Vec<RemindrNode> = self.elements.drain(range).collect();
+
+                let target_index = self
+                    .elements
+                    .iter()
+                    .position(|node| node.id == target_id)
+                    .unwrap_or(0);
+                let target_depth = self.elements[target_index].depth;
+
+                let (insert_index, depth) = match position {
+                    MovingElement::After => (target_index, target_depth),
+                    MovingElement::Before => (self.subtree_range(target_index).end, target_depth),
+                    MovingElement::Child => (target_index + 1, target_depth + 1),
+                };
+
+                let base_depth = moved[0].depth;
+                for node in moved.iter_mut() {
+                    node.depth = node.depth - base_depth + depth;
                 }
+
+                self.elements.splice(insert_index..insert_index, moved);
+                self.normalize_depths();
+                self.record_structure(before);
             }
         }
 
-        let final_index = to_index.clamp(0, self.elements.len());
-        self.elements.insert(final_index, element);
-
         self.stop_drag();
     }
 
+    /// Nest the node (and its children) under the previous node
+    pub fn indent_node(&mut self, id: Uuid) -> bool {
+        let Some(index) = self.elements.iter().position(|node| node.id == id) else {
+            return false;
+        };
+
+        if index == 0 || self.elements[index].depth > self.elements[index - 1].depth {
+            return false;
+        }
+
+        let before = self.elements.clone();
+        let range = self.subtree_range(index);
+        for node in self.elements[range].iter_mut() {
+            node.depth += 1;
+        }
+        self.record_structure(before);
+
+        true
+    }
+
+    /// Move the node (and its children) one level up
+    pub fn outdent_node(&mut self, id: Uuid) -> bool {
+        let Some(index) = self.elements.iter().position(|node| node.id == id) else {
+            return false;
+        };
+
+        if self.elements[index].depth == 0 {
+            return false;
+        }
+
+        let before = self.elements.clone();
+        let range = self.subtree_range(index);
+        for node in self.elements[range].iter_mut() {
+            node.depth -= 1;
+        }
+        self.record_structure(before);
+
+        true
+    }
+
     pub fn on_outside<T>(&mut self, event: &DragMoveEvent<T>) -> bool {
         let mouse_position = event.event.position;
         let bounds = event.bounds;
@@ -118,6 +217,8 @@ impl NodeState {
         is_outside
     }
 
+    /// Parse a serialized node. A node that cannot be parsed is kept as an unknown node
+    /// showing the error, so that its data survives the next save.
     pub fn parse_node(
         &self,
         value: &Value,
@@ -125,51 +226,542 @@ impl NodeState {
         window: &mut Window,
         app: &mut App,
     ) -> RemindrNode {
-        let partial_node = from_value::<PartialRemindrNode>(value.clone()).unwrap();
-        let element = match partial_node.node_type {
+        let parsed = Self::validate_data(value).and_then(|_| {
+            let partial_node = from_value::<PartialRemindrNode>(value.clone())?;
+            let element = Self::parse_element(partial_node.node_type, value, state, window, app)?;
+            Ok(RemindrNode::new(partial_node.id, element))
+        });
+
+        parsed.unwrap_or_else(|error| {
+            let id = value
+                .get("id")
+                .and_then(Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .unwrap_or_else(Utils::generate_uuid);
+            let element = app.new(|_| UnknownNode::new(id, value, format!("{:#}", error), state));
+            RemindrNode::new(id, RemindrElement::Unknown(element))
+        })
+    }
+
+    /// Read the data of a node of this type, then create its entity, which cannot fail
+    fn parse_element(
+        node_type: RemindrNodeType,
+        value: &Value,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        app: &mut App,
+    ) -> Result<RemindrElement, Error> {
+        let element = match node_type {
+            RemindrNodeType::Text => {
+                let data = from_value::<TextNodeData>(value.clone())?;
+                RemindrElement::Text(app.new(|cx| TextNode::new(data, state, window, cx)))
+            }
+            RemindrNodeType::Heading => {
+                let data = from_value::<HeadingNodeData>(value.clone())?;
+                RemindrElement::Heading(app.new(|cx| HeadingNode::new(data, state, window, cx)))
+            }
+            RemindrNodeType::Divider => {
+                let data = from_value::<DividerNodeData>(value.clone())?;
+                RemindrElement::Divider(app.new(|cx| DividerNode::new(data, window, cx)))
+            }
+            RemindrNodeType::BulletedList => {
+                Self::parse_list(ListKind::Bulleted, value, state, window, app)?
+            }
+            RemindrNodeType::NumberedList => {
+                Self::parse_list(ListKind::Numbered, value, state, window, app)?
+            }
+            RemindrNodeType::Todo => Self::parse_list(ListKind::Todo, value, state, window, app)?,
+            RemindrNodeType::Code => {
+                let data = from_value::<CodeNodeData>(value.clone())?;
+                RemindrElement::Code(app.new(|cx| CodeNode::new(data, state, window, cx)))
+            }
+            RemindrNodeType::Image => {
+                let data = from_value::<ImageNodeData>(value.clone())?;
+                RemindrElement::Image(app.new(|cx| ImageNode::new(data, state, window, cx)))
+            }
+            RemindrNodeType::File => {
+                let data = from_value::<FileNodeData>(value.clone())?;
+                RemindrElement::File(app.new(|cx| FileNode::new(data, state, window, cx)))
+            }
+        };
+
+        Ok(element)
+    }
+
+    /// Check a serialized node and its children against the schema of their type, so that
+    /// `parse_node` can be called on it
+    pub fn validate_node(value: &Value) -> Result<(), Error> {
+        Self::validate_data(value)?;
+
+        if let Some(children) = value.get("children") {
+            let children = children
+                .as_array()
+                .context("`children` must be an array of nodes")?;
+            for child in children {
+                Self::validate_node(child)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the data of a single node, without its children
+    fn validate_data(value: &Value) -> Result<(), Error> {
+        let partial_node = from_value::<PartialRemindrNode>(value.clone())?;
+        match partial_node.node_type {
             RemindrNodeType::Text => {
-                let element = app.new(|cx| TextNode::parse(value, state, window, cx).unwrap());
-                RemindrElement::Text(element)
+                from_value::<TextNodeData>(value.clone())?;
             }
             RemindrNodeType::Heading => {
-                let element = app.new(|cx| HeadingNode::parse(value, state, window, cx).unwrap());
-                RemindrElement::Heading(element)
+                let data = from_value::<HeadingNodeData>(value.clone())?;
+                if !(1..=6).contains(&data.metadata.level) {
+                    bail!("heading level must be between 1 and 6");
+                }
             }
             RemindrNodeType::Divider => {
-                let element = app.new(|cx| DividerNode::parse(value, window, cx).unwrap());
-                RemindrElement::Divider(element)
+                from_value::<DividerNodeData>(value.clone())?;
+            }
+            RemindrNodeType::BulletedList
+            | RemindrNodeType::NumberedList
+            | RemindrNodeType::Todo => {
+                from_value::<ListNodeData>(value.clone())?;
+            }
+            RemindrNodeType::Code => {
+                from_value::<CodeNodeData>(value.clone())?;
+            }
+            RemindrNodeType::Image => {
+                from_value::<ImageNodeData>(value.clone())?;
+            }
+            RemindrNodeType::File => {
+                from_value::<FileNodeData>(value.clone())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn parse_list(
+        kind: ListKind,
+        value: &Value,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        app: &mut App,
+    ) -> Result<RemindrElement, Error> {
+        let data = from_value::<ListNodeData>(value.clone())?;
+        let element = app.new(|cx| ListNode::new(data, kind, state, window, cx));
+        Ok(RemindrElement::list(kind, element))
+    }
+
+    /// Position of a numbered list item among the consecutive numbered items of its level
+    pub fn list_number(&self, id: Uuid) -> usize {
+        let Some(index) = self.elements.iter().position(|node| node.id == id) else {
+            return 1;
+        };
+        let depth = self.elements[index].depth;
+
+        let mut number = 1;
+        for node in self.elements[..index].iter().rev() {
+            if node.depth > depth {
+                continue;
+            }
+            if node.depth < depth || !matches!(node.element, RemindrElement::NumberedList(_)) {
+                break;
+            }
+            number += 1;
+        }
+
+        number
+    }
+
+    /// Parse a list of serialized nodes, flattening their `children` into the state
+    pub fn load_nodes(
+        &mut self,
+        values: &[Value],
+        depth: usize,
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        app: &mut App,
+    ) {
+        for value in values {
+            let mut node = self.parse_node(value, state, window, app);
+            node.depth = depth;
+            self.elements.push(node);
+
+            if let Some(children) = value.get("children").and_then(Value::as_array) {
+                self.load_nodes(children, depth + 1, state, window, app);
+            }
+        }
+    }
+
+    /// Replace the nodes with `values`, as an operation that can be undone. Nodes whose
+    /// data did not change keep their element, along with its focus and text history.
+    pub fn replace_nodes(
+        &mut self,
+        values: &[Value],
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        app: &mut App,
+    ) {
+        let before = self.elements.clone();
+        let mut previous: HashMap<Uuid, (RemindrNode, Value)> = before
+            .iter()
+            .map(|node| (node.id, (node.clone(), node.element.get_data(app))))
+            .collect();
+
+        let mut elements = Vec::new();
+        for (depth, value) in flatten_values(values, 0) {
+            let id = value.get("id").and_then(Value::as_str);
+            let kept = id
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .and_then(|id| previous.remove(&id))
+                .filter(|(_, data)| *data == value);
+
+            let mut node = match kept {
+                Some((node, _)) => node,
+                None => self.parse_node(&value, state, window, app),
+            };
+            node.depth = depth;
+            elements.push(node);
+        }
+        self.elements = elements;
+
+        self.normalize_depths();
+        self.record_structure(before);
+    }
+
+    /// Take the nodes edited by a collaborator, flattened with their depth. Text blocks
+    /// whose other data is unchanged apply the deltas of `text_updates` in place, keeping
+    /// the local focus and selection. The history is rebased on their changes, the
+    /// operations touching the blocks they removed or parsed again being dropped.
+    pub fn apply_remote_nodes(
+        &mut self,
+        blocks: &[(usize, Value)],
+        text_updates: &[TextUpdate],
+        state: &Entity<NodeState>,
+        window: &mut Window,
+        app: &mut App,
+    ) {
+        let mut deltas: HashMap<&str, Vec<&[TextDelta]>> = HashMap::new();
+        for update in text_updates {
+            deltas
+                .entry(update.block_id.as_str())
+                .or_default()
+                .push(&update.delta);
+        }
+
+        let mut previous: HashMap<Uuid, RemindrNode> = self
+            .elements
+            .iter()
+            .map(|node| (node.id, node.clone()))
+            .collect();
+        let mut changed = blocks.len() != self.elements.len();
+        let mut changes = RemoteChanges::default();
+
+        let mut elements = Vec::new();
+        for (index, (depth, value)) in blocks.iter().enumerate() {
+            let existing = value
+                .get("id")
+                .and_then(Value::as_str)
+                .and_then(|id| Uuid::parse_str(id).ok())
+                .and_then(|id| previous.remove(&id));
+
+            let mut node = match existing {
+                Some(node) => match Self::take_remote_text(
+                    &node,
+                    value,
+                    deltas
+                        .get(node.id.to_string().as_str())
+                        .map(Vec::as_slice)
+                        .unwrap_or_default(),
+                    app,
+                ) {
+                    Some(text_changes) => {
+                        if let Some(rich_text) = node.element.rich_text(app) {
+                            changes
+                                .text_changes
+                                .insert(rich_text.entity_id(), text_changes);
+                        }
+                        node
+                    }
+                    None => {
+                        changed = true;
+                        let replacement = self.parse_node(value, state, window, app);
+                        changes
+                            .dropped_texts
+                            .extend(node.element.rich_text(app).map(|text| text.entity_id()));
+                        changes
+                            .replaced
+                            .insert(node.element.entity_id(), replacement.clone());
+                        replacement
+                    }
+                },
+                None => {
+                    changed = true;
+                    let node = self.parse_node(value, state, window, app);
+                    changes.added.insert(node.id);
+                    node
+                }
+            };
+            changed |= self
+                .elements
+                .get(index)
+                .is_none_or(|current| current.id != node.id || current.depth != *depth);
+            node.depth = *depth;
+            elements.push(node);
+        }
+
+        for (id, node) in previous {
+            changes.removed.insert(id);
+            changes
+                .dropped_texts
+                .extend(node.element.rich_text(app).map(|text| text.entity_id()));
+        }
+
+        if changed {
+            self.elements = elements;
+            self.normalize_depths();
+        }
+
+        changes.current = self.elements.clone();
+        self.history.rebase(&changes);
+    }
+
+    /// Update `node` to the remote `value` with the `deltas` of its text, returning the
+    /// changes made to the text, or `None` when it has to be parsed again
+    fn take_remote_text(
+        node: &RemindrNode,
+        value: &Value,
+        deltas: &[&[TextDelta]],
+        app: &mut App,
+    ) -> Option<Vec<TextChange>> {
+        let data = node.element.get_data(app);
+        if data == *value {
+            return Some(Vec::new());
+        }
+
+        let rich_text = node.element.rich_text(app)?;
+
+        let without_text = |value: &Value| {
+            let mut value = value.clone();
+            if let Some(metadata) = value.get_mut("metadata").and_then(Value::as_object_mut) {
+                metadata.remove("content");
+                metadata.remove("spans");
             }
+            value
         };
+        if without_text(&data) != without_text(value) {
+            return None;
+        }
+
+        let metadata = &value["metadata"];
+        let content = metadata["content"].as_str().unwrap_or_default().to_string();
+        let spans = from_value::<Vec<TextSpan>>(metadata["spans"].clone()).unwrap_or_default();
+
+        Some(rich_text.update(app, |rich_text, cx| {
+            let mut changes = Vec::new();
+            for delta in deltas {
+                match rich_text.apply_delta(delta, cx) {
+                    Some(applied) => changes.extend(applied),
+                    None => break,
+                }
+            }
+
+            // The deltas follow the shared text, which the local one was pushed to before. On
+            // the first sync, or if they miss a change, the text is taken as a whole instead.
+            if rich_text.content() != content || !same_spans(rich_text.spans(), &spans) {
+                changes.extend(rich_text.apply_remote(content, spans, cx));
+            }
+            changes
+        }))
+    }
+
+    /// Serialize the nodes, children being nested under their parent's `children` key
+    pub fn serialize_nodes(&self, cx: &App) -> Vec<Value> {
+        let mut roots = Vec::new();
+        let mut stack: Vec<(usize, Value)> = Vec::new();
+
+        fn attach(roots: &mut Vec<Value>, stack: &mut Vec<(usize, Value)>, value: Value) {
+            match stack.last_mut() {
+                Some((_, parent)) => {
+                    if let Some(parent) = parent.as_object_mut() {
+                        if let Some(children) = parent
+                            .entry("children")
+                            .or_insert_with(|| Value::Array(Vec::new()))
+                            .as_array_mut()
+                        {
+                            children.push(value);
+                        }
+                    }
+                }
+                None => roots.push(value),
+            }
+        }
+
+        for node in &self.elements {
+            while stack.last().is_some_and(|(depth, _)| *depth >= node.depth) {
+                let (_, value) = stack.pop().unwrap();
+                attach(&mut roots, &mut stack, value);
+            }
+            stack.push((node.depth, node.element.get_data(cx)));
+        }
 
-        RemindrNode {
-            id: partial_node.id,
-            element,
+        while let Some((_, value)) = stack.pop() {
+            attach(&mut roots, &mut stack, value);
         }
+
+        roots
     }
 
     pub fn push_node(&mut self, node: &RemindrNode) {
+        let before = self.elements.clone();
         self.elements.push(node.clone());
+        self.record_structure(before);
     }
 
+    /// Remove a node, its children are moved one level up
     pub fn remove_node(&mut self, id: Uuid) {
-        self.elements.retain(|node| node.id != id);
+        if let Some(index) = self.elements.iter().position(|node| node.id == id) {
+            let before = self.elements.clone();
+            let range = self.subtree_range(index);
+            for node in self.elements[range.start + 1..range.end].iter_mut() {
+                node.depth -= 1;
+            }
+
+            self.elements.remove(index);
+            self.normalize_depths();
+            self.record_structure(before);
+        }
     }
 
+    /// Insert a node after the given one, as a sibling or as its first child when it has children
     pub fn insert_node_after(&mut self, id: Uuid, node: &RemindrNode) {
         let index = self.elements.iter().position(|node| node.id == id).unwrap();
-        self.elements.insert(index + 1, node.clone());
+        let depth = self.elements[index].depth;
+        let has_children = self
+            .elements
+            .get(index + 1)
+            .is_some_and(|next| next.depth > depth);
+
+        let before = self.elements.clone();
+        let mut node = node.clone();
+        node.depth = if has_children { depth + 1 } else { depth };
+        self.elements.insert(index + 1, node);
+        self.record_structure(before);
     }
 
     pub fn insert_node_at(&mut self, index: usize, node: &RemindrNode) {
+        let before = self.elements.clone();
         self.elements.insert(index, node.clone());
+        self.normalize_depths();
+        self.record_structure(before);
     }
 
     pub fn replace_node(&mut self, id: Uuid, node: &RemindrNode) {
         if let Some(index) = self.elements.iter().position(|n| n.id == id) {
+            let before = self.elements.clone();
+            let depth = self.elements[index].depth;
             self.elements[index] = node.clone();
+            self.elements[index].depth = depth;
+            self.record_structure(before);
+        }
+    }
+
+    fn record_structure(&mut self, before: Vec<RemindrNode>) {
+        self.history.push(NodeOperation::Structure {
+            before,
+            after: self.elements.clone(),
+        });
+    }
+
+    /// Record an edit made to the text of a block
+    pub fn record_text(&mut self, rich_text: Entity<RichTextState>, edit: TextEdit) {
+        self.history.push(NodeOperation::Text { rich_text, edit });
+    }
+
+    pub fn record_heading_level(&mut self, id: Uuid, before: u32, after: u32) {
+        if before != after {
+            self.history
+                .push(NodeOperation::HeadingLevel { id, before, after });
+        }
+    }
+
+    /// Undo the last operation of the document, whichever block it touched
+    pub fn undo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(operation) = self.history.undo() {
+            self.apply_operation(operation, true, window, cx);
+        }
+    }
+
+    pub fn redo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(operation) = self.history.redo() {
+            self.apply_operation(operation, false, window, cx);
         }
     }
 
+    fn apply_operation(
+        &mut self,
+        operation: NodeOperation,
+        undo: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match operation {
+            NodeOperation::Structure { before, after } => {
+                let (current, restored) = if undo {
+                    (after, before)
+                } else {
+                    (before, after)
+                };
+
+                // Focus the block where the layouts start to differ
+                let changed_index = restored
+                    .iter()
+                    .zip(current.iter())
+                    .position(|(restored, current)| {
+                        restored.id != current.id
+                            || restored.depth != current.depth
+                            || restored.element.entity_id() != current.element.entity_id()
+                    })
+                    .unwrap_or(restored.len().min(current.len()));
+                let focused = restored
+                    .get(changed_index)
+                    .or_else(|| restored.get(changed_index.saturating_sub(1)))
+                    .map(|node| node.element.clone());
+
+                self.elements = restored;
+
+                // The block emitting the shortcut may still be updating, focus it afterwards
+                if let Some(element) = focused {
+                    window.defer(cx, move |window, cx| element.focus_end(window, cx));
+                }
+            }
+            NodeOperation::Text { rich_text, edit } => {
+                rich_text.update(cx, |state, cx| {
+                    state.apply_edit(&edit, undo, cx);
+                    state.focus(window, cx);
+                });
+            }
+            NodeOperation::HeadingLevel { id, before, after } => {
+                let heading = self
+                    .get_current_nodes(id)
+                    .and_then(|node| match &node.element {
+                        RemindrElement::Heading(heading) => Some(heading.clone()),
+                        _ => None,
+                    });
+
+                if let Some(heading) = heading {
+                    let level = if undo { before } else { after };
+                    heading.update(cx, |heading, cx| heading.apply_level(level, cx));
+                }
+            }
+        }
+
+        cx.update_global::<DocumentState, _>(|state, app_cx| {
+            state.mark_changed(window, app_cx);
+        });
+        cx.notify();
+    }
+
     pub fn get_previous_node(&self, id: Uuid) -> Option<RemindrNode> {
         let index = self.elements.iter().position(|node| node.id == id)?;
         if index == 0 {
@@ -186,6 +778,36 @@ impl Default for NodeState {
             dragging_id: None,
             hovered_drop_zone: None,
             is_dragging: false,
+            history: NodeHistory::default(),
         }
     }
 }
+
+/// Whether two lists hold the same spans, whatever their order
+fn same_spans(a: &[TextSpan], b: &[TextSpan]) -> bool {
+    let sorted = |spans: &[TextSpan]| {
+        let mut spans = spans.to_vec();
+        spans.sort_by_cached_key(|span| (span.start, span.end, format!("{:?}", span.style)));
+        spans
+    };
+
+    a.len() == b.len() && sorted(a) == sorted(b)
+}
+
+/// Serialized nodes in document order with their depth, without their `children` key
+fn flatten_values(values: &[Value], depth: usize) -> Vec<(usize, Value)> {
+    let mut flattened = Vec::new();
+    for value in values {
+        let mut node = value.clone();
+        let children = node
+            .as_object_mut()
+            .and_then(|object| object.remove("children"));
+        flattened.push((depth, node));
+
+        if let Some(Value::Array(children)) = children {
+            flattened.extend(flatten_values(&children, depth + 1));
+        }
+    }
+
+    flattened
+}
@@ -1,8 +1,113 @@
-use crate::infrastructure::repositories::document_repository::DocumentRepository;
-use gpui::Global;
+use crate::{
+    domain::database::change::DocumentChange,
+    infrastructure::{
+        repositories::any_document_repository::AnyDocumentRepository, sync::SyncStatus,
+    },
+};
+use gpui::{App, AppContext, Context, Entity, EventEmitter, Global, Task};
+use tokio::sync::broadcast::error::RecvError;
 
 pub struct RepositoryState {
-    pub documents: DocumentRepository,
+    pub documents: AnyDocumentRepository,
+    /// Changes of `documents`, still delivered after switching to another database
+    pub changes: Entity<DocumentChanges>,
+    /// Sync of `documents` when it is an offline copy
+    pub sync: Entity<SyncProgress>,
+}
+
+impl RepositoryState {
+    pub fn new(documents: AnyDocumentRepository, cx: &mut App) -> Self {
+        let changes = cx.new(|cx| {
+            let mut changes = DocumentChanges {
+                _watch_task: Task::ready(()),
+            };
+            changes.watch(&documents, cx);
+            changes
+        });
+        let sync = cx.new(|cx| {
+            let mut sync = SyncProgress {
+                status: None,
+                _watch_task: Task::ready(()),
+            };
+            sync.watch(&documents, cx);
+            sync
+        });
+
+        Self {
+            documents,
+            changes,
+            sync,
+        }
+    }
+
+    /// Use another repository, the subscribers of `changes` following it
+    pub fn set_documents(&mut self, documents: AnyDocumentRepository, cx: &mut App) {
+        self.changes
+            .update(cx, |changes, cx| changes.watch(&documents, cx));
+        self.sync.update(cx, |sync, cx| sync.watch(&documents, cx));
+        self.documents = documents;
+    }
 }
 
 impl Global for RepositoryState {}
+
+/// Emits the changes of the active repository to the views subscribed to it
+pub struct DocumentChanges {
+    _watch_task: Task<()>,
+}
+
+impl EventEmitter<DocumentChange> for DocumentChanges {}
+
+impl DocumentChanges {
+    fn watch(&mut self, documents: &AnyDocumentRepository, cx: &mut Context<Self>) {
+        let mut receiver = documents.subscribe();
+
+        // Replacing the task stops forwarding the changes of the previous repository
+        self._watch_task = cx.spawn(async move |this, cx| {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) => {
+                        if this.update(cx, |_, cx| cx.emit(change)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+/// Status of the sync of the active repository, `None` when it is not an offline copy
+pub struct SyncProgress {
+    pub status: Option<SyncStatus>,
+    _watch_task: Task<()>,
+}
+
+impl SyncProgress {
+    fn watch(&mut self, documents: &AnyDocumentRepository, cx: &mut Context<Self>) {
+        let Some(mut receiver) = documents.sync().map(|sync| sync.status()) else {
+            self.status = None;
+            self._watch_task = Task::ready(());
+            cx.notify();
+            return;
+        };
+
+        self.status = Some(receiver.borrow_and_update().clone());
+        cx.notify();
+
+        self._watch_task = cx.spawn(async move |this, cx| {
+            while receiver.changed().await.is_ok() {
+                let status = receiver.borrow_and_update().clone();
+                let updated = this.update(cx, |this, cx| {
+                    this.status = Some(status);
+                    cx.notify();
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
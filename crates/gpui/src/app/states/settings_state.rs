@@ -1,77 +1,25 @@
-use gpui::Global;
-use serde::{Deserialize, Serialize};
-
-use crate::domain::entities::settings::DbContext;
-
-#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
-pub enum ThemeMode {
-    Light,
-    Dark,
-    #[default]
-    System,
-}
-
-impl ThemeMode {
-    pub fn next(&self) -> Self {
-        match self {
-            ThemeMode::Light => ThemeMode::Dark,
-            ThemeMode::Dark => ThemeMode::System,
-            ThemeMode::System => ThemeMode::Light,
-        }
-    }
-
-    pub fn icon_name(&self) -> &'static str {
-        match self {
-            ThemeMode::Light => "sun",
-            ThemeMode::Dark => "moon",
-            ThemeMode::System => "monitor",
-        }
-    }
-}
+use std::ops::{Deref, DerefMut};
 
-#[derive(Serialize, Deserialize)]
-pub struct Settings {
-    contexts: Vec<DbContext>,
-    #[serde(default)]
-    pub theme: ThemeSettings,
-}
+use gpui::Global;
 
-#[derive(Serialize, Deserialize, Clone)]
-pub struct ThemeSettings {
-    #[serde(default = "default_light_theme")]
-    pub light: String,
-    #[serde(default = "default_dark_theme")]
-    pub dark: String,
-    #[serde(default)]
-    pub mode: ThemeMode,
-}
+use crate::domain::entities::settings::Settings;
 
-fn default_light_theme() -> String {
-    "Default Light".to_string()
-}
+/// Settings of the app, shared as a global
+#[derive(Clone, Default)]
+pub struct SettingsState(pub Settings);
 
-fn default_dark_theme() -> String {
-    "Default Dark".to_string()
-}
+impl Deref for SettingsState {
+    type Target = Settings;
 
-impl Default for ThemeSettings {
-    fn default() -> Self {
-        Self {
-            light: default_light_theme(),
-            dark: default_dark_theme(),
-            mode: ThemeMode::default(),
-        }
+    fn deref(&self) -> &Self::Target {
+        &self.0
     }
 }
 
-impl Default for Settings {
-    fn default() -> Self {
-        Self {
-            contexts: Vec::new(),
-            theme: ThemeSettings::default(),
-        }
+impl DerefMut for SettingsState {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
     }
 }
 
-impl Global for Settings {}
+impl Global for SettingsState {}
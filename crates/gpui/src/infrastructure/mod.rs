@@ -1,2 +0,0 @@
-pub mod entities;
-pub mod repositories;
@@ -1,21 +1,6 @@
-use chrono::Utc;
-use uuid::{NoContext, Timestamp, Uuid};
+pub use remindr_core::{Utils, collab, domain, infrastructure};
 
 pub mod app;
-pub mod domain;
-pub mod infrastructure;
-
-pub struct Utils;
-
-impl Utils {
-    pub fn generate_uuid() -> Uuid {
-        let now = Utc::now();
-        let seconds: u64 = now.timestamp().try_into().unwrap_or(0);
-        let timestamp = Timestamp::from_unix(NoContext, seconds, 0);
-
-        Uuid::new_v7(timestamp)
-    }
-}
 
 #[derive(Clone)]
 pub enum LoadingState<T> {
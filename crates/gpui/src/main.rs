@@ -5,21 +5,24 @@ use std::borrow::Cow;
 use anyhow::Error;
 use gpui::*;
 use gpui_component::{
-    Root,
+    Root, WindowExt,
     theme::{self, ThemeRegistry},
 };
 use remindr_gpui::{
+    LoadingState,
     app::{
         apply_theme, apply_theme_global,
-        components::rich_text,
-        remindr::Remindr,
+        components::{confirm_dialog::ConfirmDialog, node_renderer, rich_text, search_palette},
         screens::AppRouter,
-        states::{document_state::DocumentState, repository_state::RepositoryState},
+        states::{
+            collab_state::CollabState, document_state::DocumentState,
+            repository_state::RepositoryState, settings_state::SettingsState,
+        },
     },
-    infrastructure::repositories::document_repository::DocumentRepository,
+    domain::database::change::DocumentChange,
+    infrastructure::config::Remindr,
 };
 use rust_embed::RustEmbed;
-use sqlx::{SqlitePool, migrate};
 
 #[derive(RustEmbed)]
 #[folder = "./assets"]
@@ -87,19 +90,19 @@ async fn main() -> Result<(), Error> {
     let settings = remindr.load_settings().await;
 
     let _ = remindr.init().await;
-    let database_path = remindr.init_default_database().await;
 
-    let pool = if let Ok(database_path) = database_path {
-        let database_url = format!("sqlite://{}", database_path.to_str().unwrap());
-        SqlitePool::connect(&database_url).await?
-    } else {
-        panic!("Failed to initialize database");
-    };
+    let context = settings
+        .as_ref()
+        .ok()
+        .and_then(|settings| settings.active_context());
+    let documents = remindr.connect_database(context).await?;
 
-    migrate!("./migrations")
-        .run(&pool)
-        .await
-        .map_err(|err| Error::msg(err.to_string()))?;
+    let retention_days = settings
+        .as_ref()
+        .map(|settings| settings.trash.clone())
+        .unwrap_or_default()
+        .retention_days;
+    let _ = documents.purge_trash(retention_days).await;
 
     app.on_reopen(|cx| {
         if let Some(window) = cx.active_window() {
@@ -118,17 +121,26 @@ async fn main() -> Result<(), Error> {
         gpui_router::init(cx);
         theme::init(cx);
         rich_text::init(cx);
+        node_renderer::init(cx);
+        search_palette::init(cx);
 
         // Set settings as global (must be done before apply_theme)
         if let Ok(settings) = settings {
-            cx.set_global(settings);
+            cx.set_global(SettingsState(settings));
         }
 
-        cx.set_global(RepositoryState {
-            documents: DocumentRepository::new(pool.clone()),
-        });
+        let repository_state = RepositoryState::new(documents.clone(), cx);
+        let changes = repository_state.changes.clone();
+        cx.set_global(repository_state);
 
         cx.set_global(DocumentState::default());
+        cx.set_global(CollabState::default());
+
+        // Keep the tabs in line with the stored documents
+        cx.subscribe(&changes, |_, change: &DocumentChange, cx| {
+            cx.update_global::<DocumentState, _>(|state, _| state.apply_change(change));
+        })
+        .detach();
         cx.activate(true);
 
         let window = open_main_window(cx).expect("failed to open window");
@@ -156,14 +168,71 @@ async fn main() -> Result<(), Error> {
             })
             .expect("failed to update window");
 
+        // Save what is left when quitting by other means than `Quit`, and keep a version of
+        // each open document
+        cx.on_app_quit(|cx| {
+            let repository = cx.global::<RepositoryState>().documents.clone();
+            let ids: Vec<i32> = cx
+                .global::<DocumentState>()
+                .documents
+                .iter()
+                .filter(|doc| matches!(doc.state, LoadingState::Loaded(_)))
+                .map(|doc| doc.uid)
+                .collect();
+            let flush =
+                cx.update_global::<DocumentState, _>(|state, cx| state.flush_documents(None, cx));
+
+            async move {
+                let _ = flush.await;
+                for id in ids {
+                    let _ = repository.create_version(id).await;
+                }
+            }
+        })
+        .detach();
+
         set_app_menus(cx);
-        cx.on_action(|_: &Quit, cx| cx.quit());
+        cx.on_action(quit);
         cx.bind_keys([KeyBinding::new("cmd-q", Quit, None)]);
     });
 
     Ok(())
 }
 
+/// Save the edited documents before quitting, asking whether to quit anyway when that fails
+fn quit(_: &Quit, cx: &mut App) {
+    let flush = cx.update_global::<DocumentState, _>(|state, cx| state.flush_documents(None, cx));
+
+    cx.spawn(async move |cx| {
+        let result = flush.await;
+
+        let _ = cx.update(|cx| {
+            let Err(error) = result else {
+                cx.quit();
+                return;
+            };
+            let Some(window) = cx.active_window() else {
+                cx.quit();
+                return;
+            };
+
+            let _ = window.update(cx, |_, window, cx| {
+                window.push_notification(error.to_string(), cx);
+                ConfirmDialog::new("Quit without saving?")
+                    .message("Some edits could not be saved and will be lost.")
+                    .confirm_text("Quit")
+                    .danger()
+                    .on_confirm(|_, cx| {
+                        cx.quit();
+                        true
+                    })
+                    .open(window, cx);
+            });
+        });
+    })
+    .detach();
+}
+
 fn set_app_menus(cx: &mut App) {
     cx.set_dock_menu(vec![
         MenuItem::os_submenu("Services", SystemMenuType::Services),
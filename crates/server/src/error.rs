@@ -0,0 +1,50 @@
+use axum::{
+    Json,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+
+/// Error returned by a route, rendered as `{ "error": message }`
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound,
+    BadRequest(String),
+    /// The `If-Match` header does not match the current version
+    PreconditionFailed,
+    /// A write was sent without `If-Match`
+    PreconditionRequired,
+    Internal(anyhow::Error),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Internal(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::NotFound => (StatusCode::NOT_FOUND, "Document not found".to_string()),
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            Self::PreconditionFailed => (
+                StatusCode::PRECONDITION_FAILED,
+                "The document has been changed since it was read".to_string(),
+            ),
+            Self::PreconditionRequired => (
+                StatusCode::PRECONDITION_REQUIRED,
+                "Send the ETag of the document in If-Match".to_string(),
+            ),
+            Self::Internal(error) => {
+                tracing::error!("Request failed: {:#}", error);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Internal server error".to_string(),
+                )
+            }
+        };
+
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
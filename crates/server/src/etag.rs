@@ -0,0 +1,43 @@
+use axum::http::{HeaderMap, HeaderValue, header};
+use remindr_core::domain::database::document::DocumentModel;
+use sha2::{Digest, Sha256};
+
+use crate::error::ApiError;
+
+/// Strong ETag of a document, the SHA-256 of its fields, stable across builds and servers
+pub fn etag(document: &DocumentModel) -> String {
+    let digest = Sha256::digest(serde_json::to_vec(document).unwrap_or_default());
+    let hash: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+
+    format!("\"{}\"", hash)
+}
+
+pub fn header_value(document: &DocumentModel) -> HeaderValue {
+    HeaderValue::from_str(&etag(document)).expect("an ETag is a valid header value")
+}
+
+/// Whether `header` lists the ETag of `document`, `*` matching any version
+pub fn matches(
+    headers: &HeaderMap,
+    name: header::HeaderName,
+    document: &DocumentModel,
+) -> Option<bool> {
+    let value = headers.get(name)?.to_str().ok()?;
+    let current = etag(document);
+
+    Some(
+        value
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == current),
+    )
+}
+
+/// Refuse a write unless `If-Match` names the current version of `document`
+pub fn check_if_match(headers: &HeaderMap, document: &DocumentModel) -> Result<(), ApiError> {
+    match matches(headers, header::IF_MATCH, document) {
+        Some(true) => Ok(()),
+        Some(false) => Err(ApiError::PreconditionFailed),
+        None => Err(ApiError::PreconditionRequired),
+    }
+}
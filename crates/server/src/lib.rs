@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use axum::Router;
+use remindr_core::infrastructure::repositories::any_document_repository::AnyDocumentRepository;
+use tokio::{net::TcpListener, sync::Mutex};
+
+pub mod error;
+pub mod etag;
+pub mod routes;
+
+#[derive(Clone)]
+pub struct AppState {
+    pub documents: AnyDocumentRepository,
+    /// Held from the ETag check to the write, so that two clients cannot both update the
+    /// version they read
+    pub write_lock: Arc<Mutex<()>>,
+}
+
+/// The REST API, versioned under `/api/v1`
+pub fn router(documents: AnyDocumentRepository) -> Router {
+    let state = AppState {
+        documents,
+        write_lock: Arc::new(Mutex::new(())),
+    };
+
+    Router::new()
+        .nest("/api/v1", routes::api())
+        .with_state(state)
+}
+
+pub async fn serve(listener: TcpListener, documents: AnyDocumentRepository) -> anyhow::Result<()> {
+    axum::serve(listener, router(documents)).await?;
+    Ok(())
+}
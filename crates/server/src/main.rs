@@ -0,0 +1,38 @@
+use std::{env, path::PathBuf};
+
+use anyhow::{Context, Error};
+use remindr_core::infrastructure::repositories::any_document_repository::AnyDocumentRepository;
+use tokio::net::TcpListener;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+const DEFAULT_DATABASE: &str = "remindr.sqlite";
+
+/// Serves the database given by `REMINDR_DATABASE_URL`, a Postgres URL or a SQLite file,
+/// on `REMINDR_ADDRESS`, logging at the level set by `RUST_LOG`
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+        )
+        .init();
+
+    let database = env::var("REMINDR_DATABASE_URL").unwrap_or_else(|_| DEFAULT_DATABASE.into());
+    let address = env::var("REMINDR_ADDRESS").unwrap_or_else(|_| DEFAULT_ADDRESS.into());
+
+    let documents = if database.starts_with("postgres://") || database.starts_with("postgresql://")
+    {
+        AnyDocumentRepository::connect_postgres(&database).await
+    } else {
+        AnyDocumentRepository::connect_sqlite(&PathBuf::from(&database)).await
+    }
+    .context("Failed to open the database")?;
+
+    let listener = TcpListener::bind(&address)
+        .await
+        .with_context(|| format!("Failed to listen on {}", address))?;
+    tracing::info!("Listening on http://{}", address);
+
+    remindr_server::serve(listener, documents).await
+}
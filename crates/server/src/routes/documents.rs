@@ -0,0 +1,184 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use remindr_core::domain::database::document::DocumentModel;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    AppState,
+    error::ApiError,
+    etag,
+    routes::{Page, PageQuery},
+};
+
+/// A document without its content, as listed
+#[derive(Serialize)]
+pub struct DocumentSummary {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub position: i32,
+    pub title: String,
+}
+
+impl From<DocumentModel> for DocumentSummary {
+    fn from(document: DocumentModel) -> Self {
+        Self {
+            id: document.id,
+            parent_id: document.parent_id,
+            position: document.position,
+            title: document.title,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateDocument {
+    pub title: String,
+    #[serde(default)]
+    pub parent_id: Option<i32>,
+    #[serde(default = "empty_content")]
+    pub content: Value,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateDocument {
+    pub title: String,
+    pub content: Value,
+}
+
+fn empty_content() -> Value {
+    Value::Array(Vec::new())
+}
+
+pub async fn list(
+    State(state): State<AppState>,
+    Query(paging): Query<PageQuery>,
+) -> Result<Json<Page<DocumentSummary>>, ApiError> {
+    let (documents, total) = state
+        .documents
+        .get_documents_page(paging.offset(), paging.limit())
+        .await?;
+    let summaries = documents.into_iter().map(DocumentSummary::from).collect();
+
+    Ok(Json(Page::new(summaries, total, &paging)))
+}
+
+pub async fn show(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let document = find(&state, id).await?;
+
+    if etag::matches(&headers, header::IF_NONE_MATCH, &document) == Some(true) {
+        return Ok((
+            StatusCode::NOT_MODIFIED,
+            [(header::ETAG, etag::header_value(&document))],
+        )
+            .into_response());
+    }
+
+    Ok(document_response(StatusCode::OK, document))
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Json(body): Json<CreateDocument>,
+) -> Result<Response, ApiError> {
+    check_content(&body.content)?;
+    if let Some(parent_id) = body.parent_id {
+        let parent = state.documents.find_document(parent_id).await?;
+        if parent.is_none() {
+            return Err(ApiError::BadRequest(format!(
+                "Parent document {} not found",
+                parent_id
+            )));
+        }
+    }
+
+    let id = state
+        .documents
+        .insert_document(DocumentModel {
+            id: 0,
+            parent_id: body.parent_id,
+            position: 0,
+            title: body.title,
+            content: body.content,
+        })
+        .await?;
+    let document = find(&state, id).await?;
+
+    let mut response = document_response(StatusCode::CREATED, document);
+    if let Ok(location) = HeaderValue::from_str(&format!("/api/v1/documents/{}", id)) {
+        response.headers_mut().insert(header::LOCATION, location);
+    }
+
+    Ok(response)
+}
+
+/// Replace the title and content of a document, `If-Match` naming the version it is based on
+pub async fn update(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+    Json(body): Json<UpdateDocument>,
+) -> Result<Response, ApiError> {
+    check_content(&body.content)?;
+
+    let _guard = state.write_lock.lock().await;
+    let document = find(&state, id).await?;
+    etag::check_if_match(&headers, &document)?;
+
+    let document = DocumentModel {
+        title: body.title,
+        content: body.content,
+        ..document
+    };
+    state.documents.update_document(document.clone()).await?;
+
+    Ok(document_response(StatusCode::OK, document))
+}
+
+/// Move a document and its pages to the trash, checking `If-Match` when sent
+pub async fn delete(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+    headers: HeaderMap,
+) -> Result<StatusCode, ApiError> {
+    let _guard = state.write_lock.lock().await;
+    let document = find(&state, id).await?;
+    if etag::matches(&headers, header::IF_MATCH, &document) == Some(false) {
+        return Err(ApiError::PreconditionFailed);
+    }
+
+    state.documents.delete_document(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find(state: &AppState, id: i32) -> Result<DocumentModel, ApiError> {
+    state
+        .documents
+        .find_document(id)
+        .await?
+        .ok_or(ApiError::NotFound)
+}
+
+fn document_response(status: StatusCode, document: DocumentModel) -> Response {
+    let etag = etag::header_value(&document);
+    (status, [(header::ETAG, etag)], Json(document)).into_response()
+}
+
+/// The content of a document is its list of nodes
+fn check_content(content: &Value) -> Result<(), ApiError> {
+    if !content.is_array() {
+        return Err(ApiError::BadRequest(
+            "`content` must be an array of nodes".to_string(),
+        ));
+    }
+
+    Ok(())
+}
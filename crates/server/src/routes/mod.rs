@@ -0,0 +1,79 @@
+use axum::{Router, routing::get};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+
+pub mod documents;
+pub mod search;
+
+const DEFAULT_PER_PAGE: usize = 50;
+const MAX_PER_PAGE: usize = 200;
+
+pub fn api() -> Router<AppState> {
+    Router::new()
+        .route("/documents", get(documents::list).post(documents::create))
+        .route(
+            "/documents/{id}",
+            get(documents::show)
+                .put(documents::update)
+                .delete(documents::delete),
+        )
+        .route("/search", get(search::search))
+}
+
+/// `?page=&per_page=` of the listing routes, pages starting at 1
+#[derive(Deserialize)]
+pub struct PageQuery {
+    #[serde(default = "first_page")]
+    pub page: usize,
+    #[serde(default = "default_per_page")]
+    pub per_page: usize,
+}
+
+impl PageQuery {
+    pub fn page(&self) -> usize {
+        self.page.max(1)
+    }
+
+    pub fn per_page(&self) -> usize {
+        self.per_page.clamp(1, MAX_PER_PAGE)
+    }
+
+    /// Number of items before the page, for the `OFFSET` of its query
+    pub fn offset(&self) -> i64 {
+        (self.page() - 1).saturating_mul(self.per_page()) as i64
+    }
+
+    /// Number of items on the page, for the `LIMIT` of its query
+    pub fn limit(&self) -> i64 {
+        self.per_page() as i64
+    }
+}
+
+fn first_page() -> usize {
+    1
+}
+
+fn default_per_page() -> usize {
+    DEFAULT_PER_PAGE
+}
+
+#[derive(Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: usize,
+    pub per_page: usize,
+    pub total: usize,
+}
+
+impl<T> Page<T> {
+    /// Page of `query` holding `items`, already limited to it, out of `total` items
+    pub fn new(items: Vec<T>, total: i64, query: &PageQuery) -> Self {
+        Self {
+            items,
+            page: query.page(),
+            per_page: query.per_page(),
+            total: total.max(0) as usize,
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use remindr_core::domain::database::search::SearchResultModel;
+use serde::Deserialize;
+
+use crate::{
+    AppState,
+    error::ApiError,
+    routes::{Page, PageQuery},
+};
+
+#[derive(Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+}
+
+/// Blocks and titles matching `q`, best matches first
+pub async fn search(
+    State(state): State<AppState>,
+    Query(search): Query<SearchQuery>,
+    Query(paging): Query<PageQuery>,
+) -> Result<Json<Page<SearchResultModel>>, ApiError> {
+    let (results, total) = state
+        .documents
+        .search_documents_page(&search.q, paging.offset(), paging.limit())
+        .await?;
+    Ok(Json(Page::new(results, total, &paging)))
+}
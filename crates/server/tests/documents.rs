@@ -0,0 +1,176 @@
+use remindr_core::{
+    Utils, infrastructure::repositories::any_document_repository::AnyDocumentRepository,
+};
+use reqwest::{Client, StatusCode, header};
+use serde_json::{Value, json};
+use tokio::net::TcpListener;
+
+/// Serve a new SQLite database on a free local port, returning the base URL of the API
+async fn spawn_server() -> String {
+    let path =
+        std::env::temp_dir().join(format!("remindr-server-{}.sqlite", Utils::generate_uuid()));
+    let documents = AnyDocumentRepository::connect_sqlite(&path).await.unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(remindr_server::serve(listener, documents));
+
+    format!("http://{}/api/v1", address)
+}
+
+async fn create(client: &Client, api: &str, body: Value) -> (Value, String) {
+    let response = client
+        .post(format!("{}/documents", api))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    (response.json().await.unwrap(), etag)
+}
+
+#[tokio::test]
+async fn documents_lifecycle() {
+    let api = spawn_server().await;
+    let client = Client::new();
+
+    let (document, etag) = create(&client, &api, json!({ "title": "Roadmap" })).await;
+    assert_eq!(document["title"], "Roadmap");
+    assert_eq!(document["content"], json!([]));
+    let url = format!("{}/documents/{}", api, document["id"]);
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ETAG], etag.as_str());
+
+    let response = client
+        .get(&url)
+        .header(header::IF_NONE_MATCH, &etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+    let update = json!({ "title": "Roadmap 2026", "content": [] });
+    let response = client.put(&url).json(&update).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_REQUIRED);
+
+    let response = client
+        .put(&url)
+        .header(header::IF_MATCH, &etag)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let new_etag = response.headers()[header::ETAG]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert_ne!(new_etag, etag);
+
+    // Written over a version that is no longer the current one
+    let response = client
+        .put(&url)
+        .header(header::IF_MATCH, &etag)
+        .json(&update)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PRECONDITION_FAILED);
+
+    let response = client
+        .delete(&url)
+        .header(header::IF_MATCH, &new_etag)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn documents_are_paged() {
+    let api = spawn_server().await;
+    let client = Client::new();
+
+    for title in ["One", "Two", "Three"] {
+        create(&client, &api, json!({ "title": title })).await;
+    }
+
+    let page: Value = client
+        .get(format!("{}/documents?page=2&per_page=2", api))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(page["total"], 3);
+    assert_eq!(page["items"].as_array().unwrap().len(), 1);
+    assert_eq!(page["items"][0]["title"], "Three");
+}
+
+#[tokio::test]
+async fn search_finds_blocks() {
+    let api = spawn_server().await;
+    let client = Client::new();
+
+    let node_id = Utils::generate_uuid();
+    let (document, _) = create(
+        &client,
+        &api,
+        json!({
+            "title": "Meeting notes",
+            "content": [{
+                "id": node_id,
+                "type": "text",
+                "metadata": { "content": "Ship the quarterly report", "spans": [] },
+            }],
+        }),
+    )
+    .await;
+
+    let results: Value = client
+        .get(format!("{}/search?q=quarterly", api))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(results["total"], 1);
+    assert_eq!(results["items"][0]["document_id"], document["id"]);
+    assert_eq!(results["items"][0]["node_id"], node_id.to_string());
+}
+
+#[tokio::test]
+async fn invalid_documents_are_rejected() {
+    let api = spawn_server().await;
+    let client = Client::new();
+
+    let response = client
+        .post(format!("{}/documents", api))
+        .json(&json!({ "title": "Broken", "content": { "type": "text" } }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = client
+        .post(format!("{}/documents", api))
+        .json(&json!({ "title": "Orphan", "parent_id": 404 }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}